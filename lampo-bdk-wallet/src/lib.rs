@@ -1,4 +1,5 @@
 //! Wallet Manager implementation with BDK
+use std::collections::HashSet;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
@@ -37,6 +38,7 @@ use lampo_common::conf::{LampoConf, Network};
 use lampo_common::keys::LampoKeys;
 use lampo_common::model::response::NewAddress;
 use lampo_common::model::response::Utxo;
+use lampo_common::model::response::WalletBalance;
 use lampo_common::secp256k1::SecretKey;
 use lampo_common::wallet::{BlockRef, WalletManager};
 use lampo_common::{async_trait, error};
@@ -60,6 +62,10 @@ pub struct BDKWalletManager {
     /// listener sync and carries scan progress. `None` (e.g. in tests) leaves
     /// the wallet syncing immediately, exactly as before.
     coordinator: OnceLock<Arc<ChainSyncCoordinator>>,
+    /// Coins promised to a transaction built outside the wallet, a splice
+    /// for now. Kept in memory: the transaction spends them before a
+    /// restart could matter, or was given up.
+    locked_utxos: StdMutex<HashSet<OutPoint>>,
 }
 
 impl BDKWalletManager {
@@ -245,6 +251,7 @@ impl WalletManager for BDKWalletManager {
                 reindex_from: conf.reindex,
                 conf: conf.clone(),
                 coordinator: OnceLock::new(),
+                locked_utxos: StdMutex::new(HashSet::new()),
             },
             mnemonic_words,
         ))
@@ -279,6 +286,7 @@ impl WalletManager for BDKWalletManager {
             reindex_from: conf.reindex,
             conf: conf.clone(),
             coordinator: OnceLock::new(),
+            locked_utxos: StdMutex::new(HashSet::new()),
         })
     }
 
@@ -308,13 +316,31 @@ impl WalletManager for BDKWalletManager {
 
     fn confirmed_utxos(&self) -> error::Result<Vec<(OutPoint, TxOut)>> {
         let wallet = self.wallet.lock().unwrap();
+        let locked = self.locked_utxos.lock().unwrap();
         Ok(wallet
             .list_unspent()
             .filter(|utxo| utxo.chain_position.is_confirmed())
+            .filter(|utxo| !locked.contains(&utxo.outpoint))
             .map(|utxo| (utxo.outpoint, utxo.txout))
             .collect())
     }
 
+    fn lock_utxos(&self, outpoints: &[OutPoint]) -> error::Result<()> {
+        let mut locked = self.locked_utxos.lock().unwrap();
+        if let Some(outpoint) = outpoints.iter().find(|outpoint| locked.contains(outpoint)) {
+            error::bail!("`{outpoint}` is already locked");
+        }
+        locked.extend(outpoints.iter().copied());
+        Ok(())
+    }
+
+    fn unlock_utxos(&self, outpoints: &[OutPoint]) {
+        let mut locked = self.locked_utxos.lock().unwrap();
+        for outpoint in outpoints {
+            locked.remove(outpoint);
+        }
+    }
+
     fn get_transaction(&self, txid: Txid) -> error::Result<Option<Transaction>> {
         Ok(self
             .wallet
@@ -343,12 +369,31 @@ impl WalletManager for BDKWalletManager {
         Ok(balance.confirmed.to_sat())
     }
 
+    async fn get_wallet_balance(&self) -> error::Result<WalletBalance> {
+        let balance = self.wallet.lock().unwrap().balance();
+        Ok(WalletBalance {
+            confirmed_sat: balance.confirmed.to_sat(),
+            unconfirmed_sat: (balance.trusted_pending + balance.untrusted_pending).to_sat(),
+            immature_sat: balance.immature.to_sat(),
+        })
+    }
+
     async fn create_transaction(
         &self,
         script: ScriptBuf,
         amount: Amount,
         fee_rate: FeeRate,
         best_block: Height,
+    ) -> error::Result<Transaction> {
+        self.create_batch_transaction(vec![(script, amount)], fee_rate, best_block)
+            .await
+    }
+
+    async fn create_batch_transaction(
+        &self,
+        outputs: Vec<(ScriptBuf, Amount)>,
+        fee_rate: FeeRate,
+        best_block: Height,
     ) -> error::Result<Transaction> {
         let mut wallet = self.wallet.lock().unwrap();
 
@@ -356,8 +401,10 @@ impl WalletManager for BDKWalletManager {
         let locktime =
             LockTime::from_height(best_block.to_consensus_u32()).unwrap_or(LockTime::ZERO);
 
+        let locked = self.locked_utxos.lock().unwrap().iter().copied().collect();
         let mut tx = wallet.build_tx();
-        tx.add_recipient(script, amount)
+        tx.set_recipients(outputs)
+            .unspendable(locked)
             .fee_rate(fee_rate)
             .nlocktime(locktime);
         let mut psbt = tx.finish()?;
@@ -372,12 +419,13 @@ impl WalletManager for BDKWalletManager {
         log::info!("lampo-wallet: list transactions");
         let wallet = self.wallet.lock().unwrap();
         log::info!("lampo-wallet: wallet lock taken");
+        let locked = self.locked_utxos.lock().unwrap();
         let txs = wallet
             .list_unspent()
             .map(|tx| Utxo {
                 txid: tx.outpoint.txid.to_string(),
                 vout: tx.outpoint.vout,
-                reserved: tx.is_spent,
+                reserved: tx.is_spent || locked.contains(&tx.outpoint),
                 confirmed: 0,
                 amount_msat: tx.txout.value.to_sat() * 1000_u64,
             })
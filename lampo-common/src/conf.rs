@@ -3,6 +3,8 @@ use std::str::FromStr;
 use bitcoin::absolute::Height;
 use clightningrpc_conf::{CLNConf, SyncCLNConf};
 
+use crate::model::request::ChannelPolicy;
+
 pub use bitcoin::Network;
 pub use lightning::util::config::UserConfig;
 
@@ -40,6 +42,16 @@ pub struct LampoConf {
     /// instead of scanning from genesis. Defaults to `true` and only applies
     /// to a fresh wallet (no UTXOs to miss); set `false` to force a full scan.
     pub fast_sync: Option<bool>,
+    /// Rules inbound channels are accepted by, from the `channel-*` options.
+    pub channel_policy: ChannelPolicy,
+}
+
+/// LDK's defaults, except that a keysend split over several paths is
+/// accepted: lampo sends them, so it should be able to receive them.
+fn default_ldk_conf() -> UserConfig {
+    let mut conf = UserConfig::default();
+    conf.accept_mpp_keysend = true;
+    conf
 }
 
 impl Default for LampoConf {
@@ -54,7 +66,7 @@ impl Default for LampoConf {
             inner: None,
             // default network is testnet
             network: Network::Testnet,
-            ldk_conf: UserConfig::default(),
+            ldk_conf: default_ldk_conf(),
             // default port is 19735 for testnet
             port: 19735,
             root_path: lampo_home,
@@ -75,6 +87,7 @@ impl Default for LampoConf {
             wallet_sync_parallel: None,
             sync_mode: None,
             fast_sync: None,
+            channel_policy: ChannelPolicy::default(),
         }
     }
 }
@@ -276,11 +289,12 @@ impl TryFrom<String> for LampoConf {
             .get_conf("fast-sync")
             .unwrap_or(None)
             .map(|s| s.to_lowercase() == "true" || s == "1");
+        let channel_policy = channel_policy(&conf)?;
         Ok(Self {
             inner: Some(conf),
             root_path,
             network,
-            ldk_conf: UserConfig::default(),
+            ldk_conf: default_ldk_conf(),
             port: u64::from_str(&port)?,
             node,
             core_url,
@@ -299,10 +313,39 @@ impl TryFrom<String> for LampoConf {
             wallet_sync_parallel,
             sync_mode,
             fast_sync,
+            channel_policy,
         })
     }
 }
 
+/// Read the inbound channel policy. The peer lists take one node id per
+/// line, e.g. `channel-deny-peer=02ab...` repeated.
+fn channel_policy(conf: &CLNConf) -> Result<ChannelPolicy, anyhow::Error> {
+    let number = |key: &str| -> Result<Option<u64>, anyhow::Error> {
+        let value = conf.get_conf(key).map_err(|err| anyhow::anyhow!("{err}"))?;
+        value
+            .map(|value| u64::from_str(value.trim()))
+            .transpose()
+            .map_err(|err| anyhow::anyhow!("`{key}`: {err}"))
+    };
+    let policy = ChannelPolicy {
+        min_channel_size_sat: number("channel-min-size")?,
+        max_channel_size_sat: number("channel-max-size")?,
+        allowlist: conf.get_confs("channel-allow-peer"),
+        denylist: conf.get_confs("channel-deny-peer"),
+        max_pending_per_peer: number("channel-max-pending-per-peer")?.map(|max| max as usize),
+        require_anchors: conf
+            .get_conf("channel-require-anchors")
+            .unwrap_or(None)
+            .is_some_and(|s| s.to_lowercase() == "true" || s == "1"),
+        zero_conf_peers: conf.get_confs("channel-zero-conf-peer"),
+    };
+    policy
+        .validate()
+        .map_err(|err| anyhow::anyhow!("invalid channel policy: {err}"))?;
+    Ok(policy)
+}
+
 impl LampoConf {
     pub fn path(&self) -> String {
         format!("{}/{}", self.root_path, self.network)
@@ -1,7 +1,7 @@
 use lightning::types::features::ChannelTypeFeatures;
 
 use crate::bitcoin::{OutPoint, Transaction};
-use crate::model::response::{PaymentHop, PaymentState};
+use crate::model::response::{CustomTlv, HoldInvoiceState, PaymentHop, PaymentState, RefundState};
 use crate::types::{ChannelId, NodeId};
 
 #[derive(Clone, Debug)]
@@ -30,6 +30,20 @@ pub enum LightningEvent {
         channel_value_satoshis: u64,
         funding_transaction: Transaction,
     },
+    /// Both sides signed a splice of `channel_id`, its transaction is
+    /// being broadcast.
+    SplicePending {
+        counterparty_node_id: NodeId,
+        channel_id: ChannelId,
+        new_funding_txo: OutPoint,
+    },
+    /// A splice of `channel_id` was abandoned, the channel keeps its
+    /// previous funding.
+    SpliceFailed {
+        counterparty_node_id: NodeId,
+        channel_id: ChannelId,
+        reason: String,
+    },
     /// A payment settled. Carries the receipt, and is emitted before the
     /// terminal [`LightningEvent::PaymentEvent`] so a caller can pick both up.
     ///
@@ -57,6 +71,50 @@ pub enum LightningEvent {
         // to help the user understand what went wrong.
         reason: Option<String>,
     },
+    /// We claimed an incoming payment.
+    PaymentReceived {
+        /// Hex encoded payment hash.
+        payment_hash: String,
+        amount_msat: u64,
+        /// Custom TLV records the payer sent along.
+        custom_tlvs: Vec<CustomTlv>,
+    },
+    /// A hold invoice changed state without being asked to: its HTLC(s)
+    /// arrived and are parked (`Accepted`), or lampo failed them back
+    /// because the claim deadline got close (`Canceled`).
+    HoldInvoiceEvent {
+        /// Hex encoded payment hash.
+        payment_hash: String,
+        state: HoldInvoiceState,
+        amount_msat: Option<u64>,
+        claim_deadline: Option<u32>,
+    },
+    /// A BOLT 12 refund moved forward. The node that created the refund
+    /// pays it (`Paid` / `Failed`), the node that requested the payment
+    /// receives it (`InvoiceSent` / `Received`).
+    RefundEvent {
+        /// Hex encoded payment id, only known to the paying side.
+        payment_id: Option<String>,
+        /// Hex encoded payment hash, unknown until the invoice is issued.
+        payment_hash: Option<String>,
+        state: RefundState,
+        amount_msat: Option<u64>,
+    },
+    /// A probe made it to the destination. LDK already fed the result to
+    /// the scorer.
+    ProbeSuccessful {
+        /// Hex encoded payment id of the probe.
+        payment_id: String,
+        path: Vec<PaymentHop>,
+    },
+    /// A probe failed before reaching the destination.
+    ProbeFailed {
+        /// Hex encoded payment id of the probe.
+        payment_id: String,
+        path: Vec<PaymentHop>,
+        /// The channel the probe failed at, when known.
+        short_channel_id: Option<u64>,
+    },
     ChannelEvent {
         state: String,
         message: String,
@@ -1,9 +1,15 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
 use crate::async_trait;
 use crate::chan;
 use crate::error;
 use crate::event::Event;
 use crate::json;
 use crate::jsonrpc::Request;
+use crate::model::request::InboundChannel;
+use crate::model::response::{CustomTlv, PaymentKind};
 
 pub trait Handler: Send + Sync {
     fn events(&self) -> chan::UnboundedReceiver<Event>;
@@ -14,3 +20,70 @@ pub trait Handler: Send + Sync {
 pub trait ExternalHandler: Send + Sync {
     async fn handle(&self, req: &Request<json::Value>) -> error::Result<Option<json::Value>>;
 }
+
+/// An inbound payment LDK is ready to claim, as shown to a [`PaymentHook`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InboundPayment {
+    /// Hex encoded payment hash.
+    pub payment_hash: String,
+    pub amount_msat: u64,
+    pub kind: PaymentKind,
+    /// Hex encoded offer id, for a BOLT 12 offer payment.
+    pub offer_id: Option<String>,
+    pub custom_tlvs: Vec<CustomTlv>,
+    /// Block height by which the payment must be claimed.
+    pub claim_deadline: Option<u32>,
+}
+
+/// What a [`PaymentHook`] wants done with an [`InboundPayment`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PaymentHookDecision {
+    /// Claim it, without asking the hooks registered later.
+    Claim,
+    /// Fail the HTLC(s) back to the sender.
+    Fail(String),
+    /// No opinion, ask the next hook.
+    Defer,
+}
+
+/// Business rules run before the preimage of an inbound payment is
+/// released. Hooks only see payments the built-in policy would claim, so
+/// they can refuse a payment but never claim an unsafe one.
+#[async_trait]
+pub trait PaymentHook: Send + Sync {
+    async fn on_payment(&self, payment: &InboundPayment) -> error::Result<PaymentHookDecision>;
+
+    /// How long the node waits for a decision before it falls back to the
+    /// built-in policy. The HTLCs stay locked up meanwhile, and all the
+    /// hooks together get no more than what is left before the
+    /// payment's `claim_deadline`.
+    fn timeout(&self) -> Duration {
+        Duration::from_secs(10)
+    }
+}
+
+/// What a [`ChannelHook`] wants done with an [`InboundChannel`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ChannelHookDecision {
+    /// Accept it, without asking the hooks registered later.
+    Accept,
+    Reject(String),
+    /// No opinion, ask the next hook.
+    Defer,
+}
+
+/// Decides on inbound channels after the configured [`ChannelPolicy`]
+/// accepted them, so a hook can only be stricter than the policy.
+///
+/// [`ChannelPolicy`]: crate::model::request::ChannelPolicy
+#[async_trait]
+pub trait ChannelHook: Send + Sync {
+    async fn on_open_channel(&self, channel: &InboundChannel)
+        -> error::Result<ChannelHookDecision>;
+
+    /// How long the node waits for a decision before it accepts the
+    /// channel as the policy did.
+    fn timeout(&self) -> Duration {
+        Duration::from_secs(10)
+    }
+}
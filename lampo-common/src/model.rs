@@ -1,39 +1,82 @@
+mod balances;
+mod channel_config;
+mod channel_policy;
 mod close_channel;
+mod closed_channels;
 mod connect;
+mod forwards;
 mod getinfo;
+mod hold_invoice;
 mod invoice;
 mod keysend;
+mod ledger;
 mod network;
 mod new_addr;
 mod on_chain;
 mod open_channel;
+mod pay_limits;
 mod pay_timeout;
+mod payer_proof;
+mod probe;
+mod refund;
+mod route;
+mod sign_message;
+mod splice;
+mod sweeps;
 
 pub use connect::Connect;
 pub use getinfo::GetInfo;
 
 pub mod request {
+    pub use crate::model::channel_config::request::*;
+    pub use crate::model::channel_policy::request::*;
     pub use crate::model::close_channel::request::*;
+    pub use crate::model::closed_channels::request::*;
     pub use crate::model::connect::Connect;
+    pub use crate::model::forwards::request::*;
     pub use crate::model::getinfo::*;
+    pub use crate::model::hold_invoice::request::*;
     pub use crate::model::invoice::request::*;
     pub use crate::model::keysend::request::*;
+    pub use crate::model::ledger::request::*;
     pub use crate::model::network::request::*;
     pub use crate::model::new_addr::request::*;
     #[allow(unused_imports)]
     pub use crate::model::on_chain::request::*;
     pub use crate::model::open_channel::request::*;
+    pub use crate::model::pay_limits::PaymentLimits;
     pub use crate::model::pay_timeout::PayTimeout;
+    pub use crate::model::payer_proof::request::*;
+    pub use crate::model::probe::request::*;
+    pub use crate::model::refund::request::*;
+    pub use crate::model::route::request::*;
+    pub use crate::model::sign_message::request::*;
+    pub use crate::model::splice::request::*;
+    pub use crate::model::sweeps::request::*;
 }
 
 pub mod response {
+    pub use crate::model::balances::response::*;
+    pub use crate::model::channel_config::response::*;
+    pub use crate::model::channel_policy::request::ChannelPolicy;
     pub use crate::model::close_channel::response::*;
+    pub use crate::model::closed_channels::response::*;
     pub use crate::model::connect::Connect;
+    pub use crate::model::forwards::response::*;
     pub use crate::model::getinfo::*;
+    pub use crate::model::hold_invoice::response::*;
     pub use crate::model::invoice::response::*;
     pub use crate::model::keysend::response::*;
+    pub use crate::model::ledger::response::*;
     pub use crate::model::network::response::*;
     pub use crate::model::new_addr::response::*;
     pub use crate::model::on_chain::response::*;
     pub use crate::model::open_channel::response::*;
+    pub use crate::model::payer_proof::response::*;
+    pub use crate::model::probe::response::*;
+    pub use crate::model::refund::response::*;
+    pub use crate::model::route::response::*;
+    pub use crate::model::sign_message::response::*;
+    pub use crate::model::splice::response::*;
+    pub use crate::model::sweeps::response::*;
 }
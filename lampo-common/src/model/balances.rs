@@ -0,0 +1,133 @@
+//! Node balance model.
+//!
+//! Adds up what the wallet holds, what LDK can still claim from our
+//! channels and what the sweeper is moving back to the wallet, so the
+//! funds locked after a force close stay visible.
+
+pub mod response {
+    use paperclip::actix::Apiv2Schema;
+    use serde::{Deserialize, Serialize};
+
+    /// The on-chain wallet, in satoshis.
+    #[derive(Clone, Debug, Default, Serialize, Deserialize, Apiv2Schema, PartialEq, Eq)]
+    pub struct WalletBalance {
+        pub confirmed_sat: u64,
+        pub unconfirmed_sat: u64,
+        /// Coinbase outputs that cannot be spent yet.
+        pub immature_sat: u64,
+    }
+
+    impl WalletBalance {
+        pub fn total_sat(&self) -> u64 {
+            self.confirmed_sat + self.unconfirmed_sat + self.immature_sat
+        }
+    }
+
+    /// The kind of a claimable channel balance, see LDK's `Balance`.
+    #[derive(Clone, Copy, Debug, Serialize, Deserialize, Apiv2Schema, PartialEq, Eq)]
+    #[serde(rename_all = "snake_case")]
+    pub enum ClaimableKind {
+        /// Our balance in an open channel, what a close would pay us.
+        OnChannel,
+        /// Paid to us on chain, waiting for confirmations or a timelock.
+        AwaitingConfirmations,
+        /// An HTLC we can claim with the preimage before the peer times
+        /// it out.
+        Contentious,
+        /// An HTLC we claim back once it times out, unless the peer claims
+        /// it first.
+        MaybeTimeout,
+        /// An HTLC the peer times out unless we learn the preimage.
+        MaybePreimage,
+        /// A revoked output the peer broadcast, ours to take.
+        CounterpartyRevoked,
+    }
+
+    /// Part of what one channel can still pay us.
+    #[derive(Clone, Debug, Serialize, Deserialize, Apiv2Schema)]
+    pub struct ClaimableBalance {
+        pub channel_id: String,
+        pub peer_id: String,
+        pub kind: ClaimableKind,
+        pub amount_sat: u64,
+        /// Height the amount becomes claimable, or stops being so for
+        /// `contentious` and `maybe_preimage`.
+        pub height: Option<u32>,
+    }
+
+    /// An output the sweeper moves to the wallet.
+    #[derive(Clone, Debug, Serialize, Deserialize, Apiv2Schema)]
+    pub struct PendingSweep {
+        /// Empty for outputs not tied to a channel.
+        pub channel_id: Option<String>,
+        pub amount_sat: u64,
+        /// The latest sweep transaction, empty until the first broadcast.
+        pub spending_txid: Option<String>,
+        /// Whether the sweep confirmed, and the wallet already counts it.
+        pub confirmed: bool,
+    }
+
+    #[derive(Clone, Debug, Serialize, Deserialize, Apiv2Schema)]
+    pub struct Balances {
+        pub wallet: WalletBalance,
+        pub channels: Vec<ClaimableBalance>,
+        pub pending_sweeps: Vec<PendingSweep>,
+        /// Wallet, channels and unconfirmed sweeps together.
+        pub total_sat: u64,
+    }
+
+    impl Balances {
+        pub fn new(
+            wallet: WalletBalance,
+            channels: Vec<ClaimableBalance>,
+            pending_sweeps: Vec<PendingSweep>,
+        ) -> Self {
+            let total_sat = wallet.total_sat()
+                + channels.iter().map(|b| b.amount_sat).sum::<u64>()
+                + pending_sweeps
+                    .iter()
+                    .filter(|sweep| !sweep.confirmed)
+                    .map(|sweep| sweep.amount_sat)
+                    .sum::<u64>();
+            Balances {
+                wallet,
+                channels,
+                pending_sweeps,
+                total_sat,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::response::{Balances, ClaimableBalance, ClaimableKind, PendingSweep, WalletBalance};
+
+    #[test]
+    fn total_skips_confirmed_sweeps() {
+        let wallet = WalletBalance {
+            confirmed_sat: 100,
+            unconfirmed_sat: 10,
+            immature_sat: 1,
+        };
+        let channel = ClaimableBalance {
+            channel_id: "aa".to_owned(),
+            peer_id: "bb".to_owned(),
+            kind: ClaimableKind::AwaitingConfirmations,
+            amount_sat: 1_000,
+            height: Some(150),
+        };
+        let sweep = |amount_sat, confirmed| PendingSweep {
+            channel_id: Some("aa".to_owned()),
+            amount_sat,
+            spending_txid: None,
+            confirmed,
+        };
+        let balances = Balances::new(
+            wallet,
+            vec![channel],
+            vec![sweep(20_000, false), sweep(300_000, true)],
+        );
+        assert_eq!(balances.total_sat, 21_111);
+    }
+}
@@ -0,0 +1,131 @@
+//! Per-channel forwarding policy model.
+//!
+//! LDK fixes the HTLC limits when the channel opens, so only the fees, the
+//! CLTV delta and the force-close knobs can change afterwards.
+
+pub mod request {
+    use paperclip::actix::Apiv2Schema;
+    use serde::{Deserialize, Serialize};
+
+    /// Change the routing fees only, see [`SetChannelConfig`] for the rest.
+    #[derive(Clone, Debug, Default, Serialize, Deserialize, Apiv2Schema)]
+    pub struct SetChannelFee {
+        /// Hex encoded channel ids.
+        #[serde(default)]
+        pub channel_ids: Vec<String>,
+        /// Node ids whose channels are all changed.
+        #[serde(default)]
+        pub peer_ids: Vec<String>,
+        pub fee_base_msat: Option<u32>,
+        pub fee_proportional_millionths: Option<u32>,
+    }
+
+    /// Change the forwarding policy of open channels. Fields left empty
+    /// keep their current value.
+    ///
+    /// The HTLC minimum and maximum are not settable: LDK fixes them in the
+    /// open channel handshake, the response reports them.
+    ///
+    /// The change applies to every channel when both `channel_ids` and
+    /// `peer_ids` are empty, otherwise to the channels matching either.
+    #[derive(Clone, Debug, Default, Serialize, Deserialize, Apiv2Schema)]
+    pub struct SetChannelConfig {
+        #[serde(default)]
+        pub channel_ids: Vec<String>,
+        #[serde(default)]
+        pub peer_ids: Vec<String>,
+        pub fee_base_msat: Option<u32>,
+        pub fee_proportional_millionths: Option<u32>,
+        pub cltv_expiry_delta: Option<u16>,
+        /// Most fee we pay to avoid a force close, see LDK's
+        /// `force_close_avoidance_max_fee_satoshis`.
+        pub force_close_avoidance_max_fee_sat: Option<u64>,
+        /// Forward HTLCs that pay the next hop less than the onion asks,
+        /// needed behind an LSP that skims a fee.
+        pub accept_underpaying_htlcs: Option<bool>,
+    }
+
+    impl SetChannelConfig {
+        pub fn matches(&self, channel_id: &str, peer_id: &str) -> bool {
+            (self.channel_ids.is_empty() && self.peer_ids.is_empty())
+                || self.channel_ids.iter().any(|id| id == channel_id)
+                || self.peer_ids.iter().any(|id| id == peer_id)
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.fee_base_msat.is_none()
+                && self.fee_proportional_millionths.is_none()
+                && self.cltv_expiry_delta.is_none()
+                && self.force_close_avoidance_max_fee_sat.is_none()
+                && self.accept_underpaying_htlcs.is_none()
+        }
+    }
+
+    impl From<SetChannelFee> for SetChannelConfig {
+        fn from(fee: SetChannelFee) -> Self {
+            SetChannelConfig {
+                channel_ids: fee.channel_ids,
+                peer_ids: fee.peer_ids,
+                fee_base_msat: fee.fee_base_msat,
+                fee_proportional_millionths: fee.fee_proportional_millionths,
+                ..Default::default()
+            }
+        }
+    }
+}
+
+pub mod response {
+    use paperclip::actix::Apiv2Schema;
+    use serde::{Deserialize, Serialize};
+
+    /// The forwarding policy of one channel after the change.
+    #[derive(Clone, Debug, Serialize, Deserialize, Apiv2Schema)]
+    pub struct ChannelConfig {
+        pub channel_id: String,
+        pub peer_id: String,
+        pub fee_base_msat: u32,
+        pub fee_proportional_millionths: u32,
+        pub cltv_expiry_delta: u16,
+        pub force_close_avoidance_max_fee_sat: u64,
+        pub accept_underpaying_htlcs: bool,
+        /// Smallest HTLC we accept on the channel, fixed when it opened.
+        pub htlc_minimum_msat: Option<u64>,
+        /// Largest HTLC we accept on the channel, fixed when it opened.
+        pub htlc_maximum_msat: Option<u64>,
+    }
+
+    #[derive(Clone, Debug, Serialize, Deserialize, Apiv2Schema)]
+    pub struct ChannelConfigs {
+        pub channels: Vec<ChannelConfig>,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::request::{SetChannelConfig, SetChannelFee};
+
+    #[test]
+    fn matches_ids_and_peers() {
+        assert!(SetChannelConfig::default().matches("aa", "bb"));
+
+        let config = SetChannelConfig {
+            channel_ids: vec!["aa".to_owned()],
+            peer_ids: vec!["cc".to_owned()],
+            ..Default::default()
+        };
+        assert!(config.matches("aa", "bb"));
+        assert!(config.matches("dd", "cc"));
+        assert!(!config.matches("dd", "bb"));
+    }
+
+    #[test]
+    fn fee_request_is_a_partial_config() {
+        let config = SetChannelConfig::from(SetChannelFee {
+            fee_base_msat: Some(0),
+            ..Default::default()
+        });
+        assert!(!config.is_empty());
+        assert!(config.cltv_expiry_delta.is_none());
+        assert!(SetChannelConfig::from(SetChannelFee::default()).is_empty());
+    }
+}
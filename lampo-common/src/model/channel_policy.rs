@@ -0,0 +1,213 @@
+//! Inbound channel acceptance policy.
+//!
+//! The policy starts from `lampo.conf` and can be replaced at runtime with
+//! `setchannelpolicy`. A policy set by RPC is stored and keeps replacing
+//! the one of `lampo.conf` across restarts, until it is set again.
+
+pub mod request {
+    use std::str::FromStr;
+
+    use paperclip::actix::Apiv2Schema;
+    use serde::{Deserialize, Serialize};
+
+    use crate::bitcoin::secp256k1::PublicKey;
+    use crate::error;
+
+    /// Rules an inbound channel has to pass before we accept it. An empty
+    /// policy accepts every channel, as lampo always did.
+    #[derive(Clone, Debug, Default, Serialize, Deserialize, Apiv2Schema, PartialEq, Eq)]
+    pub struct ChannelPolicy {
+        pub min_channel_size_sat: Option<u64>,
+        pub max_channel_size_sat: Option<u64>,
+        /// Node ids allowed to open channels to us, everyone when empty.
+        #[serde(default)]
+        pub allowlist: Vec<String>,
+        /// Node ids never allowed to open channels to us.
+        #[serde(default)]
+        pub denylist: Vec<String>,
+        /// Most channels a peer may have waiting to confirm.
+        pub max_pending_per_peer: Option<usize>,
+        /// Refuse channels without anchor outputs.
+        #[serde(default)]
+        pub require_anchors: bool,
+        /// Node ids trusted with zero-conf channels. They skip the size
+        /// limits too.
+        #[serde(default)]
+        pub zero_conf_peers: Vec<String>,
+    }
+
+    impl ChannelPolicy {
+        /// Reject a policy that would refuse every channel by mistake, or
+        /// name peers that cannot exist.
+        pub fn validate(&self) -> error::Result<()> {
+            if let (Some(min), Some(max)) = (self.min_channel_size_sat, self.max_channel_size_sat) {
+                if min > max {
+                    error::bail!(
+                        "`min_channel_size_sat` {min} is above `max_channel_size_sat` {max}"
+                    );
+                }
+            }
+            let lists = [
+                ("allowlist", &self.allowlist),
+                ("denylist", &self.denylist),
+                ("zero_conf_peers", &self.zero_conf_peers),
+            ];
+            for (name, node_ids) in lists {
+                for node_id in node_ids {
+                    if let Err(err) = PublicKey::from_str(node_id) {
+                        error::bail!("`{name}`: `{node_id}` is not a node id: {err}");
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        pub fn evaluate(&self, channel: &InboundChannel) -> ChannelPolicyDecision {
+            let peer = &channel.counterparty_node_id;
+            if self.denylist.contains(peer) {
+                return ChannelPolicyDecision::Reject("peer is denied".to_owned());
+            }
+            if !self.allowlist.is_empty() && !self.allowlist.contains(peer) {
+                return ChannelPolicyDecision::Reject("peer is not allowed".to_owned());
+            }
+            if self.require_anchors && !channel.anchors {
+                return ChannelPolicyDecision::Reject("anchor outputs are required".to_owned());
+            }
+            if self
+                .max_pending_per_peer
+                .is_some_and(|max| channel.pending_with_peer >= max)
+            {
+                return ChannelPolicyDecision::Reject(
+                    "too many pending channels with this peer".to_owned(),
+                );
+            }
+            if self.zero_conf_peers.contains(peer) {
+                return ChannelPolicyDecision::Accept { zero_conf: true };
+            }
+            if self
+                .min_channel_size_sat
+                .is_some_and(|min| channel.funding_sat < min)
+            {
+                return ChannelPolicyDecision::Reject(format!(
+                    "channel of {} sat is below the minimum size",
+                    channel.funding_sat
+                ));
+            }
+            if self
+                .max_channel_size_sat
+                .is_some_and(|max| channel.funding_sat > max)
+            {
+                return ChannelPolicyDecision::Reject(format!(
+                    "channel of {} sat is above the maximum size",
+                    channel.funding_sat
+                ));
+            }
+            ChannelPolicyDecision::Accept { zero_conf: false }
+        }
+    }
+
+    /// An inbound channel request, as seen by the policy and the channel
+    /// hooks.
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    pub struct InboundChannel {
+        /// Hex encoded temporary channel id.
+        pub temporary_channel_id: String,
+        pub counterparty_node_id: String,
+        pub funding_sat: u64,
+        pub anchors: bool,
+        pub announced: bool,
+        /// Channels with the same peer not confirmed yet.
+        pub pending_with_peer: usize,
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub enum ChannelPolicyDecision {
+        Accept { zero_conf: bool },
+        Reject(String),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::request::{ChannelPolicy, ChannelPolicyDecision, InboundChannel};
+
+    fn channel(peer: &str, funding_sat: u64) -> InboundChannel {
+        InboundChannel {
+            temporary_channel_id: "00".to_owned(),
+            counterparty_node_id: peer.to_owned(),
+            funding_sat,
+            anchors: true,
+            announced: true,
+            pending_with_peer: 0,
+        }
+    }
+
+    fn accepted(decision: ChannelPolicyDecision) -> bool {
+        matches!(decision, ChannelPolicyDecision::Accept { .. })
+    }
+
+    #[test]
+    fn empty_policy_accepts_everything() {
+        let policy = ChannelPolicy::default();
+        assert_eq!(
+            policy.evaluate(&channel("aa", 1)),
+            ChannelPolicyDecision::Accept { zero_conf: false }
+        );
+    }
+
+    #[test]
+    fn size_limits() {
+        let policy = ChannelPolicy {
+            min_channel_size_sat: Some(100_000),
+            max_channel_size_sat: Some(1_000_000),
+            zero_conf_peers: vec!["trusted".to_owned()],
+            ..Default::default()
+        };
+        assert!(!accepted(policy.evaluate(&channel("aa", 99_999))));
+        assert!(accepted(policy.evaluate(&channel("aa", 100_000))));
+        assert!(!accepted(policy.evaluate(&channel("aa", 1_000_001))));
+        assert_eq!(
+            policy.evaluate(&channel("trusted", 1)),
+            ChannelPolicyDecision::Accept { zero_conf: true }
+        );
+    }
+
+    #[test]
+    fn peer_lists_and_limits() {
+        let policy = ChannelPolicy {
+            allowlist: vec!["aa".to_owned(), "bb".to_owned()],
+            denylist: vec!["bb".to_owned()],
+            max_pending_per_peer: Some(1),
+            require_anchors: true,
+            ..Default::default()
+        };
+        assert!(accepted(policy.evaluate(&channel("aa", 1))));
+        assert!(!accepted(policy.evaluate(&channel("bb", 1))));
+        assert!(!accepted(policy.evaluate(&channel("cc", 1))));
+
+        let mut pending = channel("aa", 1);
+        pending.pending_with_peer = 1;
+        assert!(!accepted(policy.evaluate(&pending)));
+        let mut legacy = channel("aa", 1);
+        legacy.anchors = false;
+        assert!(!accepted(policy.evaluate(&legacy)));
+    }
+
+    #[test]
+    fn validate_sizes_and_node_ids() {
+        let node_id = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+        let mut policy = ChannelPolicy {
+            min_channel_size_sat: Some(100_000),
+            max_channel_size_sat: Some(100_000),
+            denylist: vec![node_id.to_owned()],
+            ..Default::default()
+        };
+        assert!(policy.validate().is_ok());
+
+        policy.min_channel_size_sat = Some(100_001);
+        assert!(policy.validate().is_err());
+        policy.min_channel_size_sat = None;
+        policy.zero_conf_peers = vec!["aa".to_owned()];
+        assert!(policy.validate().is_err());
+    }
+}
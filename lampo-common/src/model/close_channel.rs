@@ -9,14 +9,39 @@ pub mod request {
     use crate::error;
     use crate::types::*;
 
-    #[derive(Clone, Serialize, Deserialize, Apiv2Schema)]
+    #[derive(Clone, Debug, Default, Serialize, Deserialize, Apiv2Schema)]
     pub struct CloseChannel {
         pub node_id: String,
         // Hex of the channel
         pub channel_id: Option<String>,
+        /// Broadcast our latest commitment instead of negotiating a close.
+        #[serde(default)]
+        pub force: bool,
+        /// Force close if the cooperative close is not done after this many
+        /// seconds.
+        #[serde(default)]
+        pub unilateral_timeout: Option<u64>,
+        /// Address our side of a cooperative close is paid to, instead of
+        /// the wallet.
+        #[serde(default)]
+        pub destination: Option<String>,
+        /// Target feerate of a cooperative close, in sat per 1000 weight.
+        #[serde(default)]
+        pub feerate_sat_per_kw: Option<u32>,
     }
 
     impl CloseChannel {
+        /// Reject option combinations that cannot be honoured.
+        pub fn validate(&self) -> error::Result<()> {
+            if self.force && (self.destination.is_some() || self.feerate_sat_per_kw.is_some()) {
+                error::bail!("`destination` and `feerate_sat_per_kw` need a cooperative close");
+            }
+            if self.force && self.unilateral_timeout.is_some() {
+                error::bail!("`unilateral_timeout` needs a cooperative close");
+            }
+            Ok(())
+        }
+
         pub fn counterpart_node_id(&self) -> error::Result<PublicKey> {
             let node_id = PublicKey::from_str(&self.node_id)?;
             Ok(node_id)
@@ -58,6 +83,9 @@ pub mod response {
         pub message: String,
         pub peer_id: String,
         pub funding_utxo: String,
+        /// Whether we ended up force closing the channel.
+        #[serde(default)]
+        pub forced: bool,
     }
 }
 
@@ -74,6 +102,7 @@ pub mod tests {
         let req = crate::model::request::CloseChannel {
             node_id: node_id.clone(),
             channel_id: channel_hex,
+            ..Default::default()
         };
         let channel_bytes = [
             10, 68, 103, 117, 38, 172, 140, 96, 118, 22, 189, 145, 37, 141, 126, 93, 241, 216, 111,
@@ -82,4 +111,21 @@ pub mod tests {
         let channel_id_bytes = req.channel_id();
         assert_eq!(channel_bytes, channel_id_bytes.unwrap().0);
     }
+
+    #[test]
+    fn close_options_validation() {
+        let req = crate::model::request::CloseChannel {
+            force: true,
+            destination: Some("bcrt1q".to_string()),
+            ..Default::default()
+        };
+        assert!(req.validate().is_err());
+
+        let req = crate::model::request::CloseChannel {
+            unilateral_timeout: Some(30),
+            feerate_sat_per_kw: Some(253),
+            ..Default::default()
+        };
+        assert!(req.validate().is_ok());
+    }
 }
@@ -0,0 +1,108 @@
+//! Closed channel history model.
+//!
+//! [`response::ClosedChannel`] is also the record lampod persists: it is
+//! written when the channel becomes ready and completed when it closes, so
+//! any field added later must be `#[serde(default)]`.
+
+pub mod request {
+    use paperclip::actix::Apiv2Schema;
+    use serde::{Deserialize, Serialize};
+
+    use crate::model::closed_channels::response::ClosedChannel;
+
+    /// Filter for `listclosedchannels`, everything when empty.
+    #[derive(Clone, Debug, Default, Serialize, Deserialize, Apiv2Schema)]
+    pub struct ListClosedChannels {
+        /// Only channels with this node id.
+        pub peer_id: Option<String>,
+    }
+
+    impl ListClosedChannels {
+        pub fn matches(&self, channel: &ClosedChannel) -> bool {
+            channel.closed_at.is_some()
+                && (self.peer_id.is_none() || self.peer_id == channel.peer_id)
+        }
+    }
+}
+
+pub mod response {
+    use paperclip::actix::Apiv2Schema;
+    use serde::{Deserialize, Serialize};
+
+    /// A channel we had with a peer, keyed by its hex channel id.
+    #[derive(Clone, Debug, Default, Serialize, Deserialize, Apiv2Schema)]
+    pub struct ClosedChannel {
+        pub channel_id: String,
+        /// Unknown for channels that closed before ever being announced or
+        /// confirmed, and for those opened before lampo kept this history.
+        #[serde(default)]
+        pub short_channel_id: Option<u64>,
+        #[serde(default)]
+        pub peer_id: Option<String>,
+        #[serde(default)]
+        pub capacity_sat: Option<u64>,
+        /// `txid:vout` of the funding output.
+        #[serde(default)]
+        pub funding_txo: Option<String>,
+        /// Our balance when the channel closed, before on-chain fees and
+        /// without pending HTLCs.
+        #[serde(default)]
+        pub final_local_balance_msat: Option<u64>,
+        #[serde(default)]
+        pub closure_reason: Option<String>,
+        /// The transaction spending the funding output, when we broadcast
+        /// it. Missing when the peer force-closed the channel, their
+        /// commitment is not known here.
+        #[serde(default)]
+        pub closing_txid: Option<String>,
+        /// Whether our outputs of the channel were handed to the sweeper,
+        /// which moves them to the wallet once they are spendable.
+        #[serde(default)]
+        pub swept: bool,
+        /// Unix timestamp (seconds) the channel closed, empty while it is
+        /// open.
+        #[serde(default)]
+        pub closed_at: Option<u64>,
+    }
+
+    impl ClosedChannel {
+        pub fn new(channel_id: String) -> Self {
+            ClosedChannel {
+                channel_id,
+                ..Default::default()
+            }
+        }
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Apiv2Schema)]
+    pub struct ClosedChannels {
+        pub channels: Vec<ClosedChannel>,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::request::ListClosedChannels;
+    use super::response::ClosedChannel;
+
+    #[test]
+    fn list_only_closed_channels() {
+        let mut channel = ClosedChannel::new("aa".to_owned());
+        channel.peer_id = Some("bb".to_owned());
+        assert!(!ListClosedChannels::default().matches(&channel));
+
+        channel.closed_at = Some(10);
+        assert!(ListClosedChannels::default().matches(&channel));
+        let filter = ListClosedChannels {
+            peer_id: Some("cc".to_owned()),
+        };
+        assert!(!filter.matches(&channel));
+    }
+
+    #[test]
+    fn old_records_decode() {
+        let channel: ClosedChannel = crate::json::from_str(r#"{"channel_id": "aa"}"#).unwrap();
+        assert!(!channel.swept);
+        assert!(channel.closed_at.is_none());
+    }
+}
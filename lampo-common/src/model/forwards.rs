@@ -0,0 +1,208 @@
+//! Forwarding history model.
+//!
+//! [`response::ForwardInfo`] is also the record lampod persists, so any
+//! field added later must be `#[serde(default)]`.
+
+pub mod request {
+    use paperclip::actix::Apiv2Schema;
+    use serde::{Deserialize, Serialize};
+
+    use crate::model::forwards::response::{ForwardInfo, ForwardStatus};
+
+    /// Filter for `listforwards`. Every field is optional and they combine
+    /// with AND.
+    #[derive(Clone, Debug, Default, Serialize, Deserialize, Apiv2Schema)]
+    pub struct ListForwards {
+        pub status: Option<ForwardStatus>,
+        /// Hex encoded channel id the HTLC came in from.
+        pub in_channel: Option<String>,
+        /// Hex encoded channel id the HTLC went out to.
+        pub out_channel: Option<String>,
+        /// Only forwards resolved at or after this unix timestamp (seconds).
+        pub since: Option<u64>,
+        /// Only forwards resolved at or before this unix timestamp (seconds).
+        pub until: Option<u64>,
+    }
+
+    impl ListForwards {
+        pub fn matches(&self, forward: &ForwardInfo) -> bool {
+            if self.status.as_ref().is_some_and(|s| *s != forward.status) {
+                return false;
+            }
+            if self.in_channel.is_some() && self.in_channel != forward.in_channel {
+                return false;
+            }
+            if self.out_channel.is_some() && self.out_channel != forward.out_channel {
+                return false;
+            }
+            self.since
+                .map_or(true, |since| forward.resolved_at >= since)
+                && self
+                    .until
+                    .map_or(true, |until| forward.resolved_at <= until)
+        }
+    }
+
+    /// Window for `forwardstats`, everything when empty.
+    #[derive(Clone, Debug, Default, Serialize, Deserialize, Apiv2Schema)]
+    pub struct ForwardStats {
+        pub since: Option<u64>,
+        pub until: Option<u64>,
+    }
+}
+
+pub mod response {
+    use std::collections::BTreeMap;
+
+    use paperclip::actix::Apiv2Schema;
+    use serde::{Deserialize, Serialize};
+
+    const SECONDS_PER_DAY: u64 = 86_400;
+
+    #[derive(Clone, Copy, Debug, Serialize, Deserialize, Apiv2Schema, PartialEq, Eq)]
+    #[serde(rename_all = "lowercase")]
+    pub enum ForwardStatus {
+        Settled,
+        Failed,
+    }
+
+    /// An HTLC we forwarded, or refused to.
+    #[derive(Clone, Debug, Serialize, Deserialize, Apiv2Schema)]
+    pub struct ForwardInfo {
+        /// Random hex id.
+        pub forward_id: String,
+        pub status: ForwardStatus,
+        /// Hex encoded channel id the HTLC came in from.
+        pub in_channel: Option<String>,
+        /// Hex encoded channel id the HTLC went out to, unknown when the
+        /// next hop could not be resolved.
+        pub out_channel: Option<String>,
+        /// Amount received, `out_msat` plus the fee.
+        pub in_msat: Option<u64>,
+        /// Amount forwarded to the next hop.
+        pub out_msat: Option<u64>,
+        /// Fee we earned, after any amount skimmed for an LSP.
+        pub fee_msat: Option<u64>,
+        /// Whether the HTLC was claimed on chain.
+        pub onchain: bool,
+        pub failure_reason: Option<String>,
+        /// Unix timestamp (seconds) the forward settled or failed.
+        pub resolved_at: u64,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Apiv2Schema)]
+    pub struct Forwards {
+        pub forwards: Vec<ForwardInfo>,
+    }
+
+    /// Settled forwards of one outgoing channel on one day.
+    #[derive(Clone, Debug, Serialize, Deserialize, Apiv2Schema, PartialEq, Eq)]
+    pub struct ChannelDayStats {
+        /// Unix timestamp (seconds) of the start of the UTC day.
+        pub day: u64,
+        pub out_channel: String,
+        pub forwards: u64,
+        pub out_msat: u64,
+        pub fee_msat: u64,
+    }
+
+    #[derive(Debug, Default, Serialize, Deserialize, Apiv2Schema)]
+    pub struct ForwardStats {
+        pub settled: u64,
+        pub failed: u64,
+        pub fee_msat: u64,
+        pub out_msat: u64,
+        /// Ordered by day, then by channel.
+        pub days: Vec<ChannelDayStats>,
+    }
+
+    impl ForwardStats {
+        pub fn new<'a>(forwards: impl IntoIterator<Item = &'a ForwardInfo>) -> Self {
+            let mut stats = ForwardStats::default();
+            let mut days = BTreeMap::<(u64, String), ChannelDayStats>::new();
+            for forward in forwards {
+                if forward.status == ForwardStatus::Failed {
+                    stats.failed += 1;
+                    continue;
+                }
+                let fee_msat = forward.fee_msat.unwrap_or_default();
+                let out_msat = forward.out_msat.unwrap_or_default();
+                stats.settled += 1;
+                stats.fee_msat += fee_msat;
+                stats.out_msat += out_msat;
+
+                let day = forward.resolved_at - forward.resolved_at % SECONDS_PER_DAY;
+                let out_channel = forward.out_channel.clone().unwrap_or_default();
+                let entry =
+                    days.entry((day, out_channel.clone()))
+                        .or_insert_with(|| ChannelDayStats {
+                            day,
+                            out_channel,
+                            forwards: 0,
+                            out_msat: 0,
+                            fee_msat: 0,
+                        });
+                entry.forwards += 1;
+                entry.out_msat += out_msat;
+                entry.fee_msat += fee_msat;
+            }
+            stats.days = days.into_values().collect();
+            stats
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::request::ListForwards;
+    use super::response::{ForwardInfo, ForwardStats, ForwardStatus};
+
+    fn forward(out_channel: &str, fee_msat: u64, resolved_at: u64) -> ForwardInfo {
+        ForwardInfo {
+            forward_id: format!("{out_channel}{resolved_at}"),
+            status: ForwardStatus::Settled,
+            in_channel: Some("aa".to_owned()),
+            out_channel: Some(out_channel.to_owned()),
+            in_msat: Some(1_000 + fee_msat),
+            out_msat: Some(1_000),
+            fee_msat: Some(fee_msat),
+            onchain: false,
+            failure_reason: None,
+            resolved_at,
+        }
+    }
+
+    #[test]
+    fn stats_group_by_day_and_channel() {
+        let mut failed = forward("bb", 0, 10);
+        failed.status = ForwardStatus::Failed;
+        let forwards = [
+            forward("bb", 1, 10),
+            forward("bb", 2, 20),
+            forward("cc", 4, 30),
+            forward("bb", 8, 86_400 + 5),
+            failed,
+        ];
+        let stats = ForwardStats::new(&forwards);
+        assert_eq!(stats.settled, 4);
+        assert_eq!(stats.failed, 1);
+        assert_eq!(stats.fee_msat, 15);
+        assert_eq!(stats.days.len(), 3);
+        assert_eq!(stats.days[0].out_channel, "bb");
+        assert_eq!(stats.days[0].forwards, 2);
+        assert_eq!(stats.days[0].fee_msat, 3);
+        assert_eq!(stats.days[2].day, 86_400);
+    }
+
+    #[test]
+    fn list_filter() {
+        let filter = ListForwards {
+            out_channel: Some("bb".to_owned()),
+            since: Some(15),
+            ..Default::default()
+        };
+        assert!(filter.matches(&forward("bb", 1, 20)));
+        assert!(!filter.matches(&forward("bb", 1, 10)));
+        assert!(!filter.matches(&forward("cc", 1, 20)));
+    }
+}
@@ -0,0 +1,62 @@
+//! Hold invoice model.
+
+pub mod request {
+    use paperclip::actix::Apiv2Schema;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize, Debug, Apiv2Schema)]
+    pub struct GenerateHoldInvoice {
+        /// Hex encoded payment hash. The caller keeps the preimage until
+        /// it settles the invoice.
+        pub payment_hash: String,
+        pub amount_msat: Option<u64>,
+        pub description: String,
+        pub expiring_in: Option<u32>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Apiv2Schema)]
+    pub struct SettleHoldInvoice {
+        /// Hex encoded preimage of the invoice payment hash.
+        pub payment_preimage: String,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Apiv2Schema)]
+    pub struct CancelHoldInvoice {
+        /// Hex encoded payment hash.
+        pub payment_hash: String,
+    }
+}
+
+pub mod response {
+    use paperclip::actix::Apiv2Schema;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Clone, Copy, Debug, Serialize, Deserialize, Apiv2Schema, PartialEq, Eq)]
+    #[serde(rename_all = "lowercase")]
+    pub enum HoldInvoiceState {
+        /// Waiting for a payment.
+        Open,
+        /// The HTLC(s) arrived and are parked until settle or cancel.
+        Accepted,
+        Settled,
+        Canceled,
+    }
+
+    /// A hold invoice. This is also the record lampod persists, so any
+    /// field added later must be `#[serde(default)]`.
+    #[derive(Clone, Debug, Serialize, Deserialize, Apiv2Schema)]
+    pub struct HoldInvoice {
+        /// Hex encoded payment hash, the key of the entry.
+        pub payment_hash: String,
+        pub bolt11: String,
+        pub state: HoldInvoiceState,
+        pub amount_msat: Option<u64>,
+        /// Amount of the parked HTLC(s), once accepted.
+        pub amount_received_msat: Option<u64>,
+        /// Block height by which the parked HTLC(s) must be settled. Lampo
+        /// cancels them a few blocks earlier on its own.
+        pub claim_deadline: Option<u32>,
+        /// Hex encoded preimage, once settled.
+        pub payment_preimage: Option<String>,
+    }
+}
@@ -4,19 +4,50 @@ pub mod request {
     use paperclip::actix::Apiv2Schema;
     use serde::{Deserialize, Serialize};
 
+    use crate::model::pay_limits::PaymentLimits;
     use crate::model::pay_timeout::PayTimeout;
 
-    #[derive(Serialize, Deserialize, Debug, Apiv2Schema)]
+    #[derive(Clone, Serialize, Deserialize, Debug, Default, Apiv2Schema)]
     pub struct GenerateInvoice {
         pub amount_msat: Option<u64>,
         pub description: String,
         pub expiring_in: Option<u32>,
+        /// Commit to the hash of `description` instead of carrying it, as
+        /// LNURL-pay wants. The ledger still keeps the full description.
+        #[serde(default)]
+        pub description_hash_only: bool,
+        pub min_final_cltv_expiry_delta: Option<u16>,
+        /// Hex encoded preimage to use instead of one derived by the node.
+        pub preimage: Option<String>,
+        /// On-chain address the payer may fall back to.
+        pub fallback_address: Option<String>,
+        /// Add a route hint for every unannounced channel, so the invoice
+        /// can be paid through them.
+        #[serde(default)]
+        pub expose_private_channels: bool,
+        /// Unique name to look the invoice up with `listinvoices`.
+        pub label: Option<String>,
     }
 
-    #[derive(Serialize, Deserialize, Debug, Apiv2Schema)]
+    #[derive(Clone, Serialize, Deserialize, Debug, Default, Apiv2Schema)]
     pub struct GenerateOffer {
         pub amount_msat: Option<u64>,
         pub description: Option<String>,
+        /// Unique name to look the offer up with `listoffers`.
+        pub label: Option<String>,
+        /// Unix timestamp (seconds) after which the offer cannot be paid.
+        pub absolute_expiry: Option<u64>,
+        /// Most items one invoice request may ask for, `0` for no limit.
+        /// Without it an offer is for a single item.
+        pub quantity_max: Option<u64>,
+        /// Human readable name of the issuer, e.g. the shop.
+        pub issuer: Option<String>,
+        /// Networks the offer can be paid on, ours when empty.
+        #[serde(default)]
+        pub chains: Vec<String>,
+        /// Most blinded paths to put in the offer, `0` for none, which
+        /// exposes our node id.
+        pub paths: Option<usize>,
     }
 
     #[derive(Debug, Serialize, Deserialize, Apiv2Schema)]
@@ -32,24 +63,53 @@ pub mod request {
         /// How long the RPC waits for the terminal `PaymentEvent` (`fast` / `medium` / `large`).
         #[serde(default)]
         pub timeout: PayTimeout,
+        /// Return as soon as the payment is sent, in the `Pending` state.
+        /// Follow it up with `paystatus` or `waitpay`.
+        #[serde(default)]
+        pub r#async: bool,
+        /// Fee and routing limits, given inline with the other fields.
+        #[serde(flatten)]
+        pub limits: PaymentLimits,
     }
 
     #[derive(Serialize, Deserialize, Apiv2Schema)]
     pub struct Bolt12Pay {
         pub payer_note: Option<String>,
     }
+
+    #[derive(Debug, Serialize, Deserialize, Apiv2Schema)]
+    pub struct PayStatus {
+        /// Hex encoded payment id, as returned by `pay` / `keysend`.
+        pub payment_id: String,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Apiv2Schema)]
+    pub struct WaitPay {
+        /// Hex encoded payment id, as returned by `pay` / `keysend`.
+        pub payment_id: String,
+        /// How long to wait for the payment to settle (`fast` / `medium` / `large`).
+        #[serde(default)]
+        pub timeout: PayTimeout,
+    }
 }
 
 pub mod response {
     use std::vec::Vec;
 
     use bitcoin::{secp256k1::PublicKey, Network};
-    use lightning::offers::offer::Offer as LDKOffer;
-    use lightning::routing::router::RouteHop;
+    use bitcoin::{Address, WitnessProgram};
+    use lightning::bolt11_invoice::{Fallback, SignedRawBolt11Invoice};
+    use lightning::offers::invoice::Bolt12Invoice as LDKBolt12Invoice;
+    use lightning::offers::invoice_request::InvoiceRequest as LDKInvoiceRequest;
+    use lightning::offers::offer::{Amount, Offer as LDKOffer};
+    use lightning::offers::payer_proof::PayerProof as LDKPayerProof;
+    use lightning::offers::refund::Refund as LDKRefund;
+    use lightning::routing::router::{RouteHint, RouteHintHop, RouteHop};
     use paperclip::actix::Apiv2Schema;
     use serde::{Deserialize, Serialize};
 
     use crate::ldk;
+    use crate::model::keysend::response::CustomTlv;
 
     #[derive(Serialize, Deserialize, Debug, Apiv2Schema)]
     pub struct Invoice {
@@ -58,6 +118,8 @@ pub mod response {
 
     #[derive(Serialize, Deserialize, Debug, Apiv2Schema)]
     pub struct Offer {
+        #[serde(default)]
+        pub offer_id: String,
         pub bolt12: String,
         pub metadata: Option<String>,
         pub metadata_pubkey: Option<String>,
@@ -66,6 +128,7 @@ pub mod response {
     impl From<ldk::offers::offer::Offer> for Offer {
         fn from(value: ldk::offers::offer::Offer) -> Self {
             Self {
+                offer_id: hex::encode(value.id().0),
                 bolt12: value.to_string(),
                 metadata: value.metadata().map(hex::encode),
                 metadata_pubkey: value
@@ -78,14 +141,121 @@ pub mod response {
     #[derive(Debug, Serialize, Deserialize, Apiv2Schema)]
     pub struct Bolt11InvoiceInfo {
         pub issuer_id: Option<String>,
+        /// Milliseconds the invoice stays valid after `created_at`.
         pub expiry_time: Option<u64>,
         pub description: Option<String>,
-        pub routes: Vec<String>,
-        pub hints: Vec<String>,
+        /// Hex encoded hash of the description, for an invoice that commits
+        /// to one instead of carrying it.
+        pub description_hash: Option<String>,
+        pub payment_hash: Option<String>,
+        pub payment_secret: Option<String>,
+        pub payment_metadata: Option<String>,
+        /// One entry per private route, each a list of hops.
+        pub route_hints: Vec<Vec<RouteHintHopInfo>>,
+        /// Hex encoded feature bits, little endian.
+        pub features: Option<String>,
+        pub min_final_cltv_expiry_delta: u64,
+        pub fallback_addresses: Vec<String>,
+        /// Unix timestamp (seconds) of creation.
+        pub created_at: u64,
         pub network: String,
         pub amount_msat: Option<u64>,
+        /// Whether the signature matches `issuer_id`. An invoice with a bad
+        /// signature is still decoded, but must not be paid.
+        pub signature_valid: bool,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Apiv2Schema)]
+    pub struct RouteHintHopInfo {
+        pub node_id: String,
+        pub short_channel_id: u64,
+        pub fee_base_msat: u32,
+        pub fee_proportional_millionths: u32,
+        pub cltv_expiry_delta: u16,
+        pub htlc_minimum_msat: Option<u64>,
+        pub htlc_maximum_msat: Option<u64>,
+    }
+
+    impl From<&RouteHintHop> for RouteHintHopInfo {
+        fn from(hop: &RouteHintHop) -> Self {
+            Self {
+                node_id: hop.src_node_id.to_string(),
+                short_channel_id: hop.short_channel_id,
+                fee_base_msat: hop.fees.base_msat,
+                fee_proportional_millionths: hop.fees.proportional_millionths,
+                cltv_expiry_delta: hop.cltv_expiry_delta,
+                htlc_minimum_msat: hop.htlc_minimum_msat,
+                htlc_maximum_msat: hop.htlc_maximum_msat,
+            }
+        }
+    }
+
+    /// Built from the raw invoice rather than from `Bolt11Invoice`, which
+    /// refuses to parse an invoice with a bad signature.
+    impl From<&SignedRawBolt11Invoice> for Bolt11InvoiceInfo {
+        fn from(signed: &SignedRawBolt11Invoice) -> Self {
+            let raw = signed.raw_invoice();
+            let network = Network::from(raw.currency());
+            let issuer_id = raw
+                .payee_pub_key()
+                .map(|key| key.0)
+                .or_else(|| signed.recover_payee_pub_key().ok().map(|key| key.0));
+            let route_hints = raw
+                .private_routes()
+                .into_iter()
+                .map(|route| {
+                    let hint: &RouteHint = route;
+                    hint.0.iter().map(RouteHintHopInfo::from).collect()
+                })
+                .collect();
+            // Same conversion as `Bolt11Invoice::fallback_addresses`.
+            let fallback_addresses = raw
+                .fallbacks()
+                .into_iter()
+                .filter_map(|fallback| match fallback {
+                    Fallback::SegWitProgram { version, program } => {
+                        WitnessProgram::new(*version, program)
+                            .ok()
+                            .map(|program| Address::from_witness_program(program, network))
+                    }
+                    Fallback::PubKeyHash(pkh) => Some(Address::p2pkh(*pkh, network)),
+                    Fallback::ScriptHash(sh) => Some(Address::p2sh_from_hash(*sh, network)),
+                })
+                .map(|address| address.to_string())
+                .collect();
+            Self {
+                issuer_id: issuer_id.map(|id| id.to_string()),
+                expiry_time: Some(
+                    raw.expiry_time()
+                        .map(|expiry| expiry.as_duration())
+                        .unwrap_or(std::time::Duration::from_secs(
+                            ldk::invoice::DEFAULT_EXPIRY_TIME,
+                        ))
+                        .as_millis() as u64,
+                ),
+                description: raw.description().map(|desc| desc.to_string()),
+                description_hash: raw.description_hash().map(|hash| hex::encode(hash.0)),
+                payment_hash: raw.payment_hash().map(|hash| hex::encode(hash.0)),
+                payment_secret: raw.payment_secret().map(|secret| hex::encode(secret.0)),
+                payment_metadata: raw.payment_metadata().map(hex::encode),
+                route_hints,
+                features: raw
+                    .features()
+                    .map(|features| hex::encode(features.le_flags())),
+                min_final_cltv_expiry_delta: raw
+                    .min_final_cltv_expiry_delta()
+                    .map(|delta| delta.0)
+                    .unwrap_or(ldk::invoice::DEFAULT_MIN_FINAL_CLTV_EXPIRY_DELTA),
+                fallback_addresses,
+                created_at: raw.data.timestamp.as_unix_timestamp(),
+                network: network.to_string(),
+                amount_msat: raw.amount_pico_btc().map(|pico| pico / 10),
+                signature_valid: signed.check_signature(),
+            }
+        }
     }
 
+    /// A BOLT 12 offer. The name predates the other BOLT 12 types.
     #[derive(Debug, Serialize, Deserialize)]
     pub struct Bolt12InvoiceInfo {
         pub issuer_id: Option<String>,
@@ -94,6 +264,136 @@ pub mod response {
         pub description: Option<String>,
         pub offer_paths: Vec<BlindedPath>,
         pub network: String,
+        /// `None` for an offer without an amount or in another currency.
+        #[serde(default)]
+        pub amount_msat: Option<u64>,
+        #[serde(default)]
+        pub issuer_signing_pubkey: Option<String>,
+        /// Unix timestamp (seconds) after which the offer cannot be paid.
+        #[serde(default)]
+        pub absolute_expiry: Option<u64>,
+    }
+
+    /// A BOLT 12 invoice, the answer to an invoice request or a refund.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct Bolt12PaymentInvoiceInfo {
+        pub payment_hash: String,
+        pub amount_msat: u64,
+        /// Node id the invoice is signed with.
+        pub signing_pubkey: String,
+        pub payer_id: String,
+        pub description: Option<String>,
+        pub issuer: Option<String>,
+        pub payer_note: Option<String>,
+        pub quantity: Option<u64>,
+        /// Unix timestamp (seconds) of creation.
+        pub created_at: u64,
+        /// Unix timestamp (seconds) after which the invoice cannot be paid.
+        pub expires_at: u64,
+        pub payment_paths: Vec<BlindedPath>,
+        pub fallback_addresses: Vec<String>,
+        /// Hex encoded feature bits, little endian.
+        pub features: String,
+        pub network: Option<String>,
+    }
+
+    impl From<LDKBolt12Invoice> for Bolt12PaymentInvoiceInfo {
+        fn from(invoice: LDKBolt12Invoice) -> Self {
+            let payment_paths = invoice
+                .payment_paths()
+                .iter()
+                .map(|path| BlindedPath {
+                    blinded_hops: path
+                        .blinded_hops()
+                        .iter()
+                        .map(|node| node.blinded_node_id.to_string())
+                        .collect::<Vec<String>>(),
+                    blinding_points: path.blinding_point().to_string(),
+                })
+                .collect::<Vec<BlindedPath>>();
+            let created_at = invoice.created_at();
+            Self {
+                payment_hash: hex::encode(invoice.payment_hash().0),
+                amount_msat: invoice.amount_msats(),
+                signing_pubkey: invoice.signing_pubkey().to_string(),
+                payer_id: invoice.payer_signing_pubkey().to_string(),
+                description: invoice.description().map(|desc| desc.to_string()),
+                issuer: invoice.issuer().map(|issuer| issuer.to_string()),
+                payer_note: invoice.payer_note().map(|note| note.to_string()),
+                quantity: invoice.quantity(),
+                created_at: created_at.as_secs(),
+                expires_at: (created_at + invoice.relative_expiry()).as_secs(),
+                payment_paths,
+                fallback_addresses: invoice
+                    .fallbacks()
+                    .iter()
+                    .map(|address| address.to_string())
+                    .collect(),
+                features: hex::encode(invoice.invoice_features().le_flags()),
+                network: Network::from_chain_hash(invoice.chain()).map(|net| net.to_string()),
+            }
+        }
+    }
+
+    /// A BOLT 12 invoice request, sent by a payer to an offer issuer.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct InvoiceRequestInfo {
+        pub payer_id: String,
+        pub amount_msat: Option<u64>,
+        pub quantity: Option<u64>,
+        pub payer_note: Option<String>,
+        /// Description of the offer the request is for.
+        pub description: Option<String>,
+        pub issuer: Option<String>,
+        pub issuer_signing_pubkey: Option<String>,
+        pub network: Option<String>,
+    }
+
+    impl From<LDKInvoiceRequest> for InvoiceRequestInfo {
+        fn from(request: LDKInvoiceRequest) -> Self {
+            Self {
+                payer_id: request.payer_signing_pubkey().to_string(),
+                amount_msat: request.amount_msats(),
+                quantity: request.quantity(),
+                payer_note: request.payer_note().map(|note| note.to_string()),
+                description: request.description().map(|desc| desc.to_string()),
+                issuer: request.issuer().map(|issuer| issuer.to_string()),
+                issuer_signing_pubkey: request.issuer_signing_pubkey().map(|key| key.to_string()),
+                network: Network::from_chain_hash(request.chain()).map(|net| net.to_string()),
+            }
+        }
+    }
+
+    /// A BOLT 12 payer proof. LDK verifies it while parsing, so a decoded
+    /// proof is a valid one.
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct PayerProofInfo {
+        pub payment_hash: String,
+        pub payment_preimage: String,
+    }
+
+    impl From<LDKPayerProof> for PayerProofInfo {
+        fn from(proof: LDKPayerProof) -> Self {
+            Self {
+                payment_hash: hex::encode(proof.payment_hash().0),
+                payment_preimage: hex::encode(proof.payment_preimage().0),
+            }
+        }
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    pub struct Bolt12RefundInfo {
+        /// Node id the refund payer signs with.
+        pub payer_id: String,
+        pub amount_msat: u64,
+        pub description: String,
+        pub payer_note: Option<String>,
+        pub issuer: Option<String>,
+        pub quantity: Option<u64>,
+        /// Unix timestamp (seconds) after which the refund cannot be requested.
+        pub absolute_expiry: Option<u64>,
+        pub paths: Vec<BlindedPath>,
+        pub network: Option<String>,
     }
 
     #[derive(Debug, Serialize, Deserialize, Apiv2Schema)]
@@ -101,6 +401,10 @@ pub mod response {
     pub enum Decode {
         Bolt11(Bolt11InvoiceInfo),
         Bolt12(Bolt12InvoiceInfo),
+        Refund(Bolt12RefundInfo),
+        Bolt12Invoice(Bolt12PaymentInvoiceInfo),
+        InvoiceRequest(InvoiceRequestInfo),
+        PayerProof(PayerProofInfo),
     }
 
     impl From<Bolt11InvoiceInfo> for Decode {
@@ -115,6 +419,59 @@ pub mod response {
         }
     }
 
+    impl From<Bolt12RefundInfo> for Decode {
+        fn from(refund: Bolt12RefundInfo) -> Self {
+            Decode::Refund(refund)
+        }
+    }
+
+    impl From<Bolt12PaymentInvoiceInfo> for Decode {
+        fn from(invoice: Bolt12PaymentInvoiceInfo) -> Self {
+            Decode::Bolt12Invoice(invoice)
+        }
+    }
+
+    impl From<InvoiceRequestInfo> for Decode {
+        fn from(request: InvoiceRequestInfo) -> Self {
+            Decode::InvoiceRequest(request)
+        }
+    }
+
+    impl From<PayerProofInfo> for Decode {
+        fn from(proof: PayerProofInfo) -> Self {
+            Decode::PayerProof(proof)
+        }
+    }
+
+    impl From<LDKRefund> for Bolt12RefundInfo {
+        fn from(refund: LDKRefund) -> Self {
+            let paths = refund
+                .paths()
+                .iter()
+                .map(|path| BlindedPath {
+                    blinded_hops: path
+                        .blinded_hops()
+                        .iter()
+                        .map(|node| node.blinded_node_id.to_string())
+                        .collect::<Vec<String>>(),
+                    blinding_points: path.blinding_point().to_string(),
+                })
+                .collect::<Vec<BlindedPath>>();
+
+            Bolt12RefundInfo {
+                payer_id: refund.payer_signing_pubkey().to_string(),
+                amount_msat: refund.amount_msats(),
+                description: refund.description().to_string(),
+                payer_note: refund.payer_note().map(|note| note.to_string()),
+                issuer: refund.issuer().map(|issuer| issuer.to_string()),
+                quantity: refund.quantity(),
+                absolute_expiry: refund.absolute_expiry().map(|expiry| expiry.as_secs()),
+                paths,
+                network: Network::from_chain_hash(refund.chain()).map(|net| net.to_string()),
+            }
+        }
+    }
+
     impl From<LDKOffer> for Bolt12InvoiceInfo {
         fn from(offer: LDKOffer) -> Self {
             let chains = offer
@@ -150,6 +507,10 @@ pub mod response {
             let offer_id = hex::encode(offer.id().0);
             let desc = offer.description().map(|desc| desc.to_string());
             let issuer_id = offer.issuer().map(|id| id.to_string());
+            let amount_msat = match offer.amount() {
+                Some(Amount::Bitcoin { amount_msats }) => Some(amount_msats),
+                _ => None,
+            };
 
             Bolt12InvoiceInfo {
                 offer_id,
@@ -158,6 +519,9 @@ pub mod response {
                 offer_chains: chains,
                 offer_paths: paths,
                 issuer_id,
+                amount_msat,
+                issuer_signing_pubkey: offer.issuer_signing_pubkey().map(|key| key.to_string()),
+                absolute_expiry: offer.absolute_expiry().map(|expiry| expiry.as_secs()),
             }
         }
     }
@@ -170,6 +534,9 @@ pub mod response {
 
     #[derive(Serialize, Deserialize, Debug, Apiv2Schema)]
     pub struct PayResult {
+        /// Hex encoded payment id, the handle for `paystatus` / `waitpay`.
+        #[serde(default)]
+        pub payment_id: String,
         pub path: Vec<PaymentHop>,
         pub payment_hash: Option<String>,
         pub state: PaymentState,
@@ -178,6 +545,9 @@ pub mod response {
         /// Bech32 encoded BOLT 12 payer proof, proving to a third party that
         /// this node paid the invoice. Only set for settled offer payments.
         pub payer_proof: Option<String>,
+        /// Custom TLV records sent along with a keysend payment.
+        #[serde(default)]
+        pub custom_tlvs: Vec<CustomTlv>,
     }
 
     #[derive(Debug, Clone, Serialize, Deserialize, Apiv2Schema, PartialEq)]
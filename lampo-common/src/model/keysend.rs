@@ -8,6 +8,8 @@ pub mod request {
 
     use crate::bitcoin::secp256k1::PublicKey;
     use crate::error;
+    use crate::model::keysend::response::CustomTlv;
+    use crate::model::pay_limits::PaymentLimits;
     use crate::model::pay_timeout::PayTimeout;
     #[derive(Serialize, Deserialize, Apiv2Schema)]
     pub struct KeySend {
@@ -16,6 +18,20 @@ pub mod request {
         /// How long the RPC waits for the terminal `PaymentEvent` (`fast` / `medium` / `large`).
         #[serde(default)]
         pub timeout: PayTimeout,
+        /// Return as soon as the payment is sent, see `Pay::async`.
+        #[serde(default)]
+        pub r#async: bool,
+        /// Fee and routing limits, see `Pay::limits`.
+        #[serde(flatten)]
+        pub limits: PaymentLimits,
+        /// Allow splitting the payment over several paths. The recipient
+        /// has to accept multi-part keysend, lampo does.
+        #[serde(default)]
+        pub mpp: bool,
+        /// Extra records for the recipient, e.g. podcasting 2.0 or
+        /// messaging payloads.
+        #[serde(default)]
+        pub custom_tlvs: Vec<CustomTlv>,
     }
 
     impl KeySend {
@@ -23,13 +39,50 @@ pub mod request {
             let destination = PublicKey::from_str(&self.destination)?;
             Ok(destination)
         }
+
+        /// The custom records as LDK wants them: decoded and sorted by type.
+        pub fn custom_tlvs(&self) -> error::Result<Vec<(u64, Vec<u8>)>> {
+            let mut records = self
+                .custom_tlvs
+                .iter()
+                .map(|tlv| Ok((tlv.r#type, crate::hex::decode(&tlv.value)?)))
+                .collect::<error::Result<Vec<_>>>()?;
+            records.sort_by_key(|(tlv_type, _)| *tlv_type);
+            if let Some(pair) = records.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+                error::bail!("custom TLV type `{}` is given twice", pair[0].0);
+            }
+            // Types below 2^16 are reserved to the protocol.
+            if let Some((tlv_type, _)) = records.iter().find(|(tlv_type, _)| *tlv_type < 1 << 16) {
+                error::bail!(
+                    "custom TLV type `{tlv_type}` is in the range reserved to the protocol"
+                );
+            }
+            Ok(records)
+        }
     }
 }
 
 pub mod response {
-
+    use paperclip::actix::Apiv2Schema;
     use serde::{Deserialize, Serialize};
 
+    /// A custom TLV record of a keysend payment.
+    #[derive(Clone, Debug, Serialize, Deserialize, Apiv2Schema, PartialEq, Eq)]
+    pub struct CustomTlv {
+        pub r#type: u64,
+        /// Hex encoded value.
+        pub value: String,
+    }
+
+    impl From<&(u64, Vec<u8>)> for CustomTlv {
+        fn from((tlv_type, value): &(u64, Vec<u8>)) -> Self {
+            Self {
+                r#type: *tlv_type,
+                value: crate::hex::encode(value),
+            }
+        }
+    }
+
     #[derive(Serialize, Deserialize)]
     pub struct KeySendInfo {
         pub payment_preimage: String,
@@ -41,3 +94,46 @@ pub mod response {
         pub status: String,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::request::KeySend;
+    use super::response::CustomTlv;
+
+    fn keysend(custom_tlvs: Vec<CustomTlv>) -> KeySend {
+        KeySend {
+            destination: String::new(),
+            amount_msat: 1_000,
+            timeout: Default::default(),
+            r#async: false,
+            limits: Default::default(),
+            mpp: false,
+            custom_tlvs,
+        }
+    }
+
+    fn tlv(r#type: u64, value: &str) -> CustomTlv {
+        CustomTlv {
+            r#type,
+            value: value.to_owned(),
+        }
+    }
+
+    #[test]
+    fn custom_tlvs_are_sorted_by_type() {
+        let request = keysend(vec![tlv(7629171, "cafe"), tlv(7629169, "00")]);
+        assert_eq!(
+            request.custom_tlvs().unwrap(),
+            vec![(7629169, vec![0x00]), (7629171, vec![0xca, 0xfe])]
+        );
+    }
+
+    #[test]
+    fn refuses_invalid_custom_tlvs() {
+        assert!(keysend(vec![tlv(7629169, "zz")]).custom_tlvs().is_err());
+        assert!(keysend(vec![tlv(7629169, "00"), tlv(7629169, "01")])
+            .custom_tlvs()
+            .is_err());
+        assert!(keysend(vec![tlv(5, "00")]).custom_tlvs().is_err());
+    }
+}
@@ -0,0 +1,405 @@
+//! Payment ledger model.
+//!
+//! These types are both the `listpays` / `listinvoices` responses and the
+//! records lampod persists, so any field added later must be
+//! `#[serde(default)]` to keep reading the records written before it.
+
+pub mod request {
+    use paperclip::actix::Apiv2Schema;
+    use serde::{Deserialize, Serialize};
+
+    use crate::model::invoice::response::PaymentState;
+    use crate::model::ledger::response::{InvoiceInfo, InvoiceStatus, OfferInfo, PaymentInfo};
+
+    /// Filter for `listpays`. Every field is optional and they combine
+    /// with AND; an empty request lists every outgoing payment.
+    #[derive(Clone, Debug, Default, Serialize, Deserialize, Apiv2Schema)]
+    pub struct ListPays {
+        pub status: Option<PaymentState>,
+        /// Hex encoded payment hash.
+        pub payment_hash: Option<String>,
+        /// Only payments created at or after this unix timestamp (seconds).
+        pub since: Option<u64>,
+        /// Only payments created at or before this unix timestamp (seconds).
+        pub until: Option<u64>,
+    }
+
+    impl ListPays {
+        pub fn matches(&self, pay: &PaymentInfo) -> bool {
+            if self.status.as_ref().is_some_and(|s| *s != pay.status) {
+                return false;
+            }
+            if self.payment_hash.is_some() && self.payment_hash != pay.payment_hash {
+                return false;
+            }
+            in_range(pay.created_at, self.since, self.until)
+        }
+    }
+
+    /// Filter for `listinvoices`, same semantics as [`ListPays`].
+    #[derive(Clone, Debug, Default, Serialize, Deserialize, Apiv2Schema)]
+    pub struct ListInvoices {
+        pub status: Option<InvoiceStatus>,
+        /// Hex encoded payment hash.
+        pub payment_hash: Option<String>,
+        pub label: Option<String>,
+        /// Only invoices created at or after this unix timestamp (seconds).
+        pub since: Option<u64>,
+        /// Only invoices created at or before this unix timestamp (seconds).
+        pub until: Option<u64>,
+    }
+
+    impl ListInvoices {
+        pub fn matches(&self, invoice: &InvoiceInfo) -> bool {
+            if self.status.as_ref().is_some_and(|s| *s != invoice.status) {
+                return false;
+            }
+            if self.payment_hash.is_some() && self.payment_hash != invoice.payment_hash {
+                return false;
+            }
+            if self.label.is_some() && self.label != invoice.label {
+                return false;
+            }
+            in_range(invoice.created_at, self.since, self.until)
+        }
+    }
+
+    /// Filter for `listoffers`.
+    #[derive(Clone, Debug, Default, Serialize, Deserialize, Apiv2Schema)]
+    pub struct ListOffers {
+        /// Hex encoded offer id.
+        pub offer_id: Option<String>,
+        pub label: Option<String>,
+        /// Skip offers that are disabled or expired.
+        #[serde(default)]
+        pub active_only: bool,
+    }
+
+    impl ListOffers {
+        pub fn matches(&self, offer: &OfferInfo, now: u64) -> bool {
+            if self
+                .offer_id
+                .as_ref()
+                .is_some_and(|id| *id != offer.offer_id)
+            {
+                return false;
+            }
+            if self.label.is_some() && self.label != offer.label {
+                return false;
+            }
+            !self.active_only || offer.is_active(now)
+        }
+    }
+
+    /// Stop accepting payments to an offer. LDK still answers its invoice
+    /// requests, so payers get an invoice and their payment is rejected
+    /// by the recipient instead of the invoice request.
+    #[derive(Clone, Debug, Serialize, Deserialize, Apiv2Schema)]
+    pub struct DisableOffer {
+        /// Hex encoded offer id.
+        pub offer_id: String,
+    }
+
+    fn in_range(timestamp: u64, since: Option<u64>, until: Option<u64>) -> bool {
+        since.map_or(true, |since| timestamp >= since)
+            && until.map_or(true, |until| timestamp <= until)
+    }
+}
+
+pub mod response {
+    use paperclip::actix::Apiv2Schema;
+    use serde::{Deserialize, Serialize};
+
+    use crate::model::invoice::response::{PaymentHop, PaymentState};
+    use crate::model::keysend::response::CustomTlv;
+
+    /// What kind of payment request a ledger entry belongs to.
+    #[derive(Clone, Copy, Debug, Serialize, Deserialize, Apiv2Schema, PartialEq, Eq)]
+    #[serde(rename_all = "lowercase")]
+    pub enum PaymentKind {
+        Bolt11,
+        Bolt12,
+        Keysend,
+        /// A BOLT 12 refund, paid by the node that created it.
+        Refund,
+    }
+
+    /// An outgoing payment.
+    #[derive(Clone, Debug, Serialize, Deserialize, Apiv2Schema)]
+    pub struct PaymentInfo {
+        /// Hex encoded payment id, the key of the entry.
+        pub payment_id: String,
+        /// Hex encoded payment hash. Unknown for an offer payment until the
+        /// BOLT 12 invoice is received.
+        pub payment_hash: Option<String>,
+        pub kind: PaymentKind,
+        /// The bolt11 invoice, bolt12 offer or refund that was paid, if any.
+        pub bolt: Option<String>,
+        pub destination: Option<String>,
+        /// Amount delivered to the destination, fees excluded.
+        pub amount_msat: Option<u64>,
+        /// Routing fees paid, known once the payment succeeds.
+        pub fee_msat: Option<u64>,
+        /// Hex encoded preimage, known once the payment succeeds.
+        pub payment_preimage: Option<String>,
+        pub status: PaymentState,
+        pub failure_reason: Option<String>,
+        /// Unix timestamp (seconds) of the `pay` / `keysend` call.
+        pub created_at: u64,
+        /// Unix timestamp (seconds) the payment succeeded or failed.
+        pub completed_at: Option<u64>,
+        /// Every path tried so far, in the order LDK reported its outcome.
+        /// A multi-part payment has one successful attempt per part.
+        #[serde(default)]
+        pub attempts: Vec<PaymentAttempt>,
+        /// Custom TLV records sent along with a keysend payment.
+        #[serde(default)]
+        pub custom_tlvs: Vec<CustomTlv>,
+    }
+
+    /// The outcome of routing a payment (or one part of it) over one path.
+    #[derive(Clone, Debug, Serialize, Deserialize, Apiv2Schema)]
+    pub struct PaymentAttempt {
+        pub path: Vec<PaymentHop>,
+        pub state: PaymentState,
+        /// The channel that failed the attempt, when LDK could tell.
+        pub failed_short_channel_id: Option<u64>,
+        /// Whether the failure ruled out any further retry of the payment.
+        #[serde(default)]
+        pub permanent: bool,
+    }
+
+    impl PaymentInfo {
+        pub fn new(payment_id: String, kind: PaymentKind, created_at: u64) -> Self {
+            Self {
+                payment_id,
+                payment_hash: None,
+                kind,
+                bolt: None,
+                destination: None,
+                amount_msat: None,
+                fee_msat: None,
+                payment_preimage: None,
+                status: PaymentState::Pending,
+                failure_reason: None,
+                created_at,
+                completed_at: None,
+                attempts: Vec::new(),
+                custom_tlvs: Vec::new(),
+            }
+        }
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Apiv2Schema)]
+    pub struct Pays {
+        pub pays: Vec<PaymentInfo>,
+    }
+
+    #[derive(Clone, Copy, Debug, Serialize, Deserialize, Apiv2Schema, PartialEq, Eq)]
+    #[serde(rename_all = "lowercase")]
+    pub enum InvoiceStatus {
+        Unpaid,
+        Paid,
+        Expired,
+    }
+
+    /// An incoming payment: an invoice we issued, or a payment we claimed
+    /// without one (keysend, BOLT 12).
+    #[derive(Clone, Debug, Serialize, Deserialize, Apiv2Schema)]
+    pub struct InvoiceInfo {
+        /// Hex encoded payment hash, the key of the entry.
+        pub payment_hash: Option<String>,
+        pub kind: PaymentKind,
+        pub bolt11: Option<String>,
+        /// Hex encoded id of the offer a BOLT 12 payment was made against.
+        pub offer_id: Option<String>,
+        pub description: Option<String>,
+        /// Amount requested, `None` for "any amount" invoices.
+        pub amount_msat: Option<u64>,
+        /// Amount actually claimed.
+        pub amount_received_msat: Option<u64>,
+        /// Hex encoded preimage, known once the payment is claimed.
+        pub payment_preimage: Option<String>,
+        pub status: InvoiceStatus,
+        /// Unix timestamp (seconds) of creation.
+        pub created_at: u64,
+        pub expires_at: Option<u64>,
+        pub paid_at: Option<u64>,
+        /// Custom TLV records the payer sent along, keysend payments mostly.
+        #[serde(default)]
+        pub custom_tlvs: Vec<CustomTlv>,
+        /// The label given when the invoice was created.
+        #[serde(default)]
+        pub label: Option<String>,
+    }
+
+    impl InvoiceInfo {
+        pub fn new(payment_hash: String, kind: PaymentKind, created_at: u64) -> Self {
+            Self {
+                payment_hash: Some(payment_hash),
+                kind,
+                bolt11: None,
+                offer_id: None,
+                description: None,
+                amount_msat: None,
+                amount_received_msat: None,
+                payment_preimage: None,
+                status: InvoiceStatus::Unpaid,
+                created_at,
+                expires_at: None,
+                paid_at: None,
+                custom_tlvs: Vec::new(),
+                label: None,
+            }
+        }
+
+        /// An unpaid invoice past its expiry is reported as expired. The
+        /// stored status is not rewritten: it stays a pure function of time.
+        pub fn refresh_status(&mut self, now: u64) {
+            if self.status == InvoiceStatus::Unpaid
+                && self.expires_at.is_some_and(|expiry| expiry <= now)
+            {
+                self.status = InvoiceStatus::Expired;
+            }
+        }
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Apiv2Schema)]
+    pub struct Invoices {
+        pub invoices: Vec<InvoiceInfo>,
+    }
+
+    /// An offer we generated.
+    #[derive(Clone, Debug, Serialize, Deserialize, Apiv2Schema)]
+    pub struct OfferInfo {
+        /// Hex encoded offer id, the key of the entry.
+        pub offer_id: String,
+        pub bolt12: String,
+        pub description: Option<String>,
+        pub amount_msat: Option<u64>,
+        /// Unix timestamp (seconds) of creation.
+        pub created_at: u64,
+        #[serde(default)]
+        pub label: Option<String>,
+        /// Unix timestamp (seconds) after which the offer cannot be paid.
+        #[serde(default)]
+        pub absolute_expiry: Option<u64>,
+        /// Payments for a disabled offer are failed back.
+        #[serde(default)]
+        pub disabled: bool,
+    }
+
+    impl OfferInfo {
+        pub fn is_active(&self, now: u64) -> bool {
+            !self.disabled && self.absolute_expiry.map_or(true, |expiry| now < expiry)
+        }
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Apiv2Schema)]
+    pub struct Offers {
+        pub offers: Vec<OfferInfo>,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::request::{ListInvoices, ListOffers, ListPays};
+    use super::response::{InvoiceInfo, InvoiceStatus, OfferInfo, PaymentInfo, PaymentKind};
+    use crate::model::response::PaymentState;
+
+    fn pay(hash: &str, created_at: u64, status: PaymentState) -> PaymentInfo {
+        let mut pay = PaymentInfo::new(hash.to_owned(), PaymentKind::Bolt11, created_at);
+        pay.payment_hash = Some(hash.to_owned());
+        pay.status = status;
+        pay
+    }
+
+    #[test]
+    fn empty_filter_matches_everything() {
+        let filter = ListPays::default();
+        assert!(filter.matches(&pay("aa", 10, PaymentState::Pending)));
+        assert!(filter.matches(&pay("bb", 20, PaymentState::Failure)));
+    }
+
+    #[test]
+    fn filters_combine() {
+        let filter = ListPays {
+            status: Some(PaymentState::Success),
+            payment_hash: None,
+            since: Some(10),
+            until: Some(20),
+        };
+        assert!(filter.matches(&pay("aa", 10, PaymentState::Success)));
+        assert!(filter.matches(&pay("aa", 20, PaymentState::Success)));
+        assert!(!filter.matches(&pay("aa", 21, PaymentState::Success)));
+        assert!(!filter.matches(&pay("aa", 15, PaymentState::Failure)));
+
+        let filter = ListPays {
+            payment_hash: Some("aa".to_owned()),
+            ..Default::default()
+        };
+        assert!(filter.matches(&pay("aa", 1, PaymentState::Pending)));
+        assert!(!filter.matches(&pay("bb", 1, PaymentState::Pending)));
+    }
+
+    #[test]
+    fn unpaid_invoice_expires() {
+        let mut invoice = InvoiceInfo::new("aa".to_owned(), PaymentKind::Bolt11, 10);
+        invoice.expires_at = Some(100);
+        invoice.refresh_status(99);
+        assert_eq!(invoice.status, InvoiceStatus::Unpaid);
+        invoice.refresh_status(100);
+        assert_eq!(invoice.status, InvoiceStatus::Expired);
+
+        let filter = ListInvoices {
+            status: Some(InvoiceStatus::Expired),
+            ..Default::default()
+        };
+        assert!(filter.matches(&invoice));
+
+        let filter = ListInvoices {
+            label: Some("coffee".to_owned()),
+            ..Default::default()
+        };
+        assert!(!filter.matches(&invoice));
+        invoice.label = Some("coffee".to_owned());
+        assert!(filter.matches(&invoice));
+
+        // A paid invoice stays paid whatever its expiry says.
+        let mut invoice = InvoiceInfo::new("bb".to_owned(), PaymentKind::Bolt11, 10);
+        invoice.expires_at = Some(100);
+        invoice.status = InvoiceStatus::Paid;
+        invoice.refresh_status(1_000);
+        assert_eq!(invoice.status, InvoiceStatus::Paid);
+    }
+
+    #[test]
+    fn offer_filters() {
+        let mut offer = OfferInfo {
+            offer_id: "aa".to_owned(),
+            bolt12: "lno1".to_owned(),
+            description: None,
+            amount_msat: None,
+            created_at: 10,
+            label: Some("coffee".to_owned()),
+            absolute_expiry: Some(100),
+            disabled: false,
+        };
+        let active = ListOffers {
+            active_only: true,
+            ..Default::default()
+        };
+        assert!(active.matches(&offer, 99));
+        assert!(!active.matches(&offer, 100));
+        assert!(ListOffers::default().matches(&offer, 100));
+
+        offer.disabled = true;
+        assert!(!active.matches(&offer, 50));
+
+        let filter = ListOffers {
+            label: Some("tea".to_owned()),
+            ..Default::default()
+        };
+        assert!(!filter.matches(&offer, 50));
+    }
+}
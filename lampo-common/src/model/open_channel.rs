@@ -5,8 +5,12 @@ pub mod request {
 
     use serde::{Deserialize, Serialize};
 
+    use crate::bitcoin::consensus::encode::deserialize_hex;
+    use crate::bitcoin::psbt::Psbt;
+    use crate::bitcoin::Transaction;
     use crate::error;
-    use crate::types::NodeId;
+    use crate::hex::FromHex;
+    use crate::types::{ChannelId, NodeId};
 
     #[derive(Clone, Serialize, Deserialize)]
     pub struct OpenChannel {
@@ -25,6 +29,64 @@ pub mod request {
             Ok(node_id)
         }
     }
+
+    /// Fund a channel opened by `fundchannel_start` with a transaction
+    /// signed elsewhere, given either as a finalized PSBT or as raw hex.
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    pub struct FundChannelComplete {
+        pub temporary_channel_id: String,
+        /// Base64 encoded PSBT, every input finalized.
+        pub psbt: Option<String>,
+        /// Hex encoded signed transaction.
+        pub tx: Option<String>,
+    }
+
+    impl FundChannelComplete {
+        pub fn temporary_channel_id(&self) -> error::Result<ChannelId> {
+            parse_channel_id(&self.temporary_channel_id)
+        }
+
+        pub fn transaction(&self) -> error::Result<Transaction> {
+            match (&self.psbt, &self.tx) {
+                (Some(psbt), None) => {
+                    let psbt = Psbt::from_str(psbt)?;
+                    let finalized = psbt.inputs.iter().all(|input| {
+                        input.final_script_witness.is_some() || input.final_script_sig.is_some()
+                    });
+                    if !finalized {
+                        error::bail!("the psbt has inputs not finalized");
+                    }
+                    Ok(psbt.extract_tx_unchecked_fee_rate())
+                }
+                (None, Some(tx)) => Ok(deserialize_hex(tx)?),
+                _ => error::bail!("provide exactly one of `psbt` and `tx`"),
+            }
+        }
+    }
+
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    pub struct FundChannelCancel {
+        pub temporary_channel_id: String,
+    }
+
+    impl FundChannelCancel {
+        pub fn temporary_channel_id(&self) -> error::Result<ChannelId> {
+            parse_channel_id(&self.temporary_channel_id)
+        }
+    }
+
+    fn parse_channel_id(channel_id: &str) -> error::Result<ChannelId> {
+        let bytes = <[u8; 32]>::from_hex(channel_id)
+            .map_err(|err| error::anyhow!("invalid channel id `{channel_id}`: {err}"))?;
+        Ok(ChannelId::from_bytes(bytes))
+    }
+
+    /// Open a channel to each peer, all funded by one transaction. Either
+    /// every channel is funded or none is.
+    #[derive(Clone, Serialize, Deserialize)]
+    pub struct MultiFundChannel {
+        pub channels: Vec<OpenChannel>,
+    }
 }
 
 pub mod response {
@@ -59,6 +121,50 @@ pub mod response {
         }
     }
 
+    /// The output an external wallet has to pay to fund the channel.
+    #[derive(Clone, Serialize, Deserialize, Debug)]
+    pub struct FundChannelStart {
+        pub temporary_channel_id: String,
+        pub node_id: String,
+        pub funding_address: String,
+        /// Hex encoded script of the funding output.
+        pub scriptpubkey: String,
+        pub amount: u64,
+    }
+
+    #[derive(Clone, Serialize, Deserialize, Debug)]
+    pub struct FundChannelComplete {
+        pub temporary_channel_id: String,
+        pub txid: String,
+        /// Index of the channel output in the funding transaction.
+        pub funding_outnum: u32,
+    }
+
+    /// The channel dropped by `fundchannel_cancel`.
+    #[derive(Clone, Serialize, Deserialize, Debug)]
+    pub struct FundChannelCancel {
+        pub temporary_channel_id: String,
+        pub node_id: String,
+    }
+
+    /// Outcome of `multifundchannel`. When any channel fails the whole
+    /// batch is rolled back: `txid` is empty and each channel says why.
+    #[derive(Serialize, Deserialize, Debug)]
+    pub struct MultiFundChannel {
+        pub tx: Option<Transaction>,
+        pub txid: Option<Txid>,
+        pub channels: Vec<BatchChannel>,
+    }
+
+    #[derive(Clone, Serialize, Deserialize, Debug)]
+    pub struct BatchChannel {
+        pub node_id: String,
+        pub amount: u64,
+        /// Index of the channel output in the funding transaction.
+        pub funding_outnum: Option<u32>,
+        pub error: Option<String>,
+    }
+
     #[derive(Clone, Serialize, Deserialize, Debug)]
     pub struct Channel {
         // Channel_id needs to be string as it currently does not derive Serialize
@@ -72,5 +178,22 @@ pub mod response {
         pub public: bool,
         pub available_balance_for_send_msat: u64,
         pub available_balance_for_recv_msat: u64,
+        /// Forwarding policy, unknown until the channel is negotiated.
+        #[serde(default)]
+        pub fee_base_msat: Option<u32>,
+        #[serde(default)]
+        pub fee_proportional_millionths: Option<u32>,
+        #[serde(default)]
+        pub cltv_expiry_delta: Option<u16>,
+        /// Smallest HTLC we accept on this channel.
+        #[serde(default)]
+        pub htlc_minimum_msat: Option<u64>,
+        /// Largest HTLC we accept on this channel.
+        #[serde(default)]
+        pub htlc_maximum_msat: Option<u64>,
+        /// Txid of a splice waiting to lock, the channel keeps its current
+        /// funding until then.
+        #[serde(default)]
+        pub pending_splice_txid: Option<String>,
     }
 }
@@ -0,0 +1,176 @@
+//! Fee and routing limits shared by `pay` and `keysend`.
+use std::str::FromStr;
+
+use paperclip::actix::Apiv2Schema;
+use serde::{Deserialize, Serialize};
+
+use crate::bitcoin::secp256k1::PublicKey;
+use crate::error;
+use crate::ldk::ln::outbound_payment::Retry;
+use crate::ldk::routing::router::{RouteParameters, RouteParametersConfig};
+
+/// Every field is optional: an empty set of limits keeps LDK's defaults,
+/// which put no cap on the routing fee.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, Apiv2Schema)]
+pub struct PaymentLimits {
+    /// Upper bound on the routing fees of the whole payment.
+    pub maxfee_msat: Option<u64>,
+    /// Upper bound on the routing fees as a percentage of the amount. When
+    /// `maxfee_msat` is set too, the lower of the two applies.
+    pub maxfeepercent: Option<f64>,
+    /// Upper bound on the total CLTV expiry delta of every path.
+    pub max_cltv_delta: Option<u32>,
+    /// Upper bound on the number of paths the payment is split over (LDK's
+    /// `max_path_count`, also accepted under that name).
+    #[serde(alias = "max_path_count")]
+    pub max_parts: Option<u8>,
+    /// Node ids and short channel ids (`BLOCKxTXxOUTPUT` or decimal) to
+    /// route around.
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// Keep retrying the payment for this many seconds.
+    pub retry_for: Option<u64>,
+}
+
+impl PaymentLimits {
+    /// The fee cap for a payment delivering `amount_msat`.
+    pub fn fee_cap(&self, amount_msat: Option<u64>) -> error::Result<Option<u64>> {
+        let Some(percent) = self.maxfeepercent else {
+            return Ok(self.maxfee_msat);
+        };
+        if !percent.is_finite() || percent < 0.0 {
+            error::bail!("`maxfeepercent` must be a non-negative number, got `{percent}`");
+        }
+        let Some(amount_msat) = amount_msat else {
+            error::bail!("`maxfeepercent` needs the amount of the payment");
+        };
+        let cap = (amount_msat as f64 * percent / 100.0).floor() as u64;
+        Ok(Some(self.maxfee_msat.map_or(cap, |max| max.min(cap))))
+    }
+
+    fn max_parts(&self) -> error::Result<Option<u8>> {
+        if self.max_parts == Some(0) {
+            error::bail!("`max_parts` must be at least 1");
+        }
+        Ok(self.max_parts)
+    }
+
+    /// `config` with the fee, CLTV and path count limits applied. A limit
+    /// that is not set keeps the value of `config`.
+    pub fn route_params_config(
+        &self,
+        mut config: RouteParametersConfig,
+        amount_msat: Option<u64>,
+    ) -> error::Result<RouteParametersConfig> {
+        if let Some(cap) = self.fee_cap(amount_msat)? {
+            config.max_total_routing_fee_msat = Some(cap);
+        }
+        if let Some(max_cltv) = self.max_cltv_delta {
+            config.max_total_cltv_expiry_delta = max_cltv;
+        }
+        if let Some(max_parts) = self.max_parts()? {
+            config.max_path_count = max_parts;
+        }
+        Ok(config)
+    }
+
+    /// Same as [`Self::route_params_config`], for a payment whose route
+    /// parameters are built by hand. Exclusions are left to the caller:
+    /// excluding a node needs the network graph.
+    pub fn apply(&self, route_params: &mut RouteParameters) -> error::Result<()> {
+        if let Some(cap) = self.fee_cap(Some(route_params.final_value_msat))? {
+            route_params.max_total_routing_fee_msat = Some(cap);
+        }
+        if let Some(max_cltv) = self.max_cltv_delta {
+            route_params.payment_params.max_total_cltv_expiry_delta = max_cltv;
+        }
+        if let Some(max_parts) = self.max_parts()? {
+            route_params.payment_params.max_path_count = max_parts;
+        }
+        Ok(())
+    }
+
+    pub fn retry(&self, default: Retry) -> Retry {
+        self.retry_for.map_or(default, |secs| {
+            Retry::Timeout(std::time::Duration::from_secs(secs))
+        })
+    }
+
+    /// Split `exclude` into node ids and short channel ids.
+    pub fn exclusions(&self) -> error::Result<(Vec<PublicKey>, Vec<u64>)> {
+        let mut nodes = Vec::new();
+        let mut channels = Vec::new();
+        for entry in &self.exclude {
+            if let Ok(node) = PublicKey::from_str(entry) {
+                nodes.push(node);
+            } else {
+                channels.push(parse_short_channel_id(entry)?);
+            }
+        }
+        Ok((nodes, channels))
+    }
+}
+
+fn parse_short_channel_id(scid: &str) -> error::Result<u64> {
+    if let Ok(scid) = scid.parse::<u64>() {
+        return Ok(scid);
+    }
+    let parts = scid
+        .split('x')
+        .map(u64::from_str)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| error::anyhow!("`{scid}` is neither a node id nor a short channel id"))?;
+    match parts[..] {
+        [block, tx, output] if block < 1 << 24 && tx < 1 << 24 && output < 1 << 16 => {
+            Ok((block << 40) | (tx << 16) | output)
+        }
+        _ => error::bail!("`{scid}` is neither a node id nor a short channel id"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fee_cap_takes_the_lowest_limit() {
+        let mut limits = PaymentLimits::default();
+        assert_eq!(limits.fee_cap(Some(1_000_000)).unwrap(), None);
+
+        limits.maxfeepercent = Some(0.5);
+        assert_eq!(limits.fee_cap(Some(1_000_000)).unwrap(), Some(5_000));
+        assert!(limits.fee_cap(None).is_err());
+
+        limits.maxfee_msat = Some(1_000);
+        assert_eq!(limits.fee_cap(Some(1_000_000)).unwrap(), Some(1_000));
+        limits.maxfee_msat = Some(10_000);
+        assert_eq!(limits.fee_cap(Some(1_000_000)).unwrap(), Some(5_000));
+
+        limits.maxfeepercent = Some(-1.0);
+        assert!(limits.fee_cap(Some(1_000_000)).is_err());
+    }
+
+    #[test]
+    fn exclusions_split_nodes_and_channels() {
+        let node = "02eec7245d6b7d2ccb30380bfbe2a3648cd7a942653f5aa340edcea1f283686619";
+        let limits = PaymentLimits {
+            exclude: vec![node.to_owned(), "103x1x0".to_owned(), "42".to_owned()],
+            ..Default::default()
+        };
+        let (nodes, channels) = limits.exclusions().unwrap();
+        assert_eq!(nodes, vec![PublicKey::from_str(node).unwrap()]);
+        assert_eq!(channels, vec![(103 << 40) | (1 << 16), 42]);
+
+        let limits = PaymentLimits {
+            exclude: vec!["not-a-channel".to_owned()],
+            ..Default::default()
+        };
+        assert!(limits.exclusions().is_err());
+    }
+
+    #[test]
+    fn deserializes_max_path_count_alias() {
+        let limits: PaymentLimits = serde_json::from_str(r#"{"max_path_count": 3}"#).unwrap();
+        assert_eq!(limits.max_parts, Some(3));
+    }
+}
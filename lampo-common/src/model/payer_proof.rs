@@ -0,0 +1,119 @@
+//! BOLT 12 payer proof model.
+
+pub mod request {
+    use paperclip::actix::Apiv2Schema;
+    use serde::{Deserialize, Serialize};
+
+    /// TLV types of the fields a payer proof can disclose by name.
+    pub const OFFER_DESCRIPTION_TYPE: u64 = 10;
+    pub const OFFER_ISSUER_TYPE: u64 = 18;
+    pub const INVOICE_CREATED_AT_TYPE: u64 = 164;
+    pub const INVOICE_AMOUNT_TYPE: u64 = 170;
+
+    /// Rebuild the payer proof of a settled BOLT 12 payment. The proof
+    /// always discloses what LDK includes by default, the flags widen it.
+    #[derive(Clone, Debug, Default, Serialize, Deserialize, Apiv2Schema)]
+    pub struct GetPayerProof {
+        /// Hex encoded payment hash, as returned by `pay`.
+        pub payment_hash: String,
+        #[serde(default)]
+        pub include_description: bool,
+        #[serde(default)]
+        pub include_issuer: bool,
+        #[serde(default)]
+        pub include_amount: bool,
+        #[serde(default)]
+        pub include_created_at: bool,
+        /// Further invoice TLV types to disclose.
+        #[serde(default)]
+        pub include_types: Vec<u64>,
+    }
+
+    impl GetPayerProof {
+        /// Every TLV type to disclose, sorted and without duplicates.
+        pub fn disclosed_types(&self) -> Vec<u64> {
+            let mut types = self.include_types.clone();
+            let named = [
+                (self.include_description, OFFER_DESCRIPTION_TYPE),
+                (self.include_issuer, OFFER_ISSUER_TYPE),
+                (self.include_created_at, INVOICE_CREATED_AT_TYPE),
+                (self.include_amount, INVOICE_AMOUNT_TYPE),
+            ];
+            types.extend(
+                named
+                    .into_iter()
+                    .filter_map(|(include, r#type)| include.then_some(r#type)),
+            );
+            types.sort_unstable();
+            types.dedup();
+            types
+        }
+    }
+
+    #[derive(Clone, Debug, Serialize, Deserialize, Apiv2Schema)]
+    pub struct VerifyPayerProof {
+        pub payer_proof: String,
+        /// The offer the proof claims to pay.
+        pub offer: String,
+        /// Hex encoded payment hash the proof must commit to, if known.
+        pub payment_hash: Option<String>,
+    }
+}
+
+pub mod response {
+    use paperclip::actix::Apiv2Schema;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Clone, Debug, Serialize, Deserialize, Apiv2Schema)]
+    pub struct PayerProof {
+        pub payment_hash: String,
+        pub payer_proof: String,
+    }
+
+    /// Outcome of `verifypayerproof`. The disclosed fields are reported
+    /// only for a valid proof.
+    #[derive(Clone, Debug, Default, Serialize, Deserialize, Apiv2Schema)]
+    pub struct PayerProofVerification {
+        pub valid: bool,
+        /// Why the proof was rejected.
+        pub reason: Option<String>,
+        pub payment_hash: Option<String>,
+        pub payment_preimage: Option<String>,
+        pub payer_id: Option<String>,
+        pub issuer_signing_pubkey: Option<String>,
+        pub description: Option<String>,
+        pub issuer: Option<String>,
+        pub amount_msat: Option<u64>,
+        /// Unix timestamp (seconds) the invoice was created at.
+        pub created_at: Option<u64>,
+    }
+
+    impl PayerProofVerification {
+        pub fn invalid(reason: impl Into<String>) -> Self {
+            Self {
+                reason: Some(reason.into()),
+                ..Default::default()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::request::{GetPayerProof, INVOICE_AMOUNT_TYPE, OFFER_DESCRIPTION_TYPE};
+
+    #[test]
+    fn disclosed_types_merge_named_fields() {
+        let request = GetPayerProof {
+            include_description: true,
+            include_amount: true,
+            include_types: vec![INVOICE_AMOUNT_TYPE, 240],
+            ..Default::default()
+        };
+        assert_eq!(
+            request.disclosed_types(),
+            vec![OFFER_DESCRIPTION_TYPE, INVOICE_AMOUNT_TYPE, 240]
+        );
+        assert!(GetPayerProof::default().disclosed_types().is_empty());
+    }
+}
@@ -0,0 +1,68 @@
+//! Payment probing model.
+
+pub mod request {
+    use std::str::FromStr;
+
+    use paperclip::actix::Apiv2Schema;
+    use serde::{Deserialize, Serialize};
+
+    use crate::bitcoin::secp256k1::PublicKey;
+    use crate::error;
+    use crate::model::pay_timeout::PayTimeout;
+
+    #[derive(Serialize, Deserialize, Debug, Apiv2Schema)]
+    pub struct Probe {
+        /// Node id to probe a payment to.
+        pub destination: String,
+        pub amount_msat: u64,
+        /// How long to wait for the probes to come back (`fast` / `medium` / `large`).
+        #[serde(default)]
+        pub timeout: PayTimeout,
+    }
+
+    impl Probe {
+        pub fn destination(&self) -> error::Result<PublicKey> {
+            let destination = PublicKey::from_str(&self.destination)?;
+            Ok(destination)
+        }
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Apiv2Schema)]
+    pub struct ProbeInvoice {
+        pub invoice_str: String,
+        /// Required for an invoice without an amount, ignored otherwise.
+        pub amount_msat: Option<u64>,
+        /// How long to wait for the probes to come back (`fast` / `medium` / `large`).
+        #[serde(default)]
+        pub timeout: PayTimeout,
+    }
+}
+
+pub mod response {
+    use paperclip::actix::Apiv2Schema;
+    use serde::{Deserialize, Serialize};
+
+    use crate::model::invoice::response::{PaymentHop, PaymentState};
+
+    /// The outcome of one probe, sent along one path of the route.
+    #[derive(Clone, Debug, Serialize, Deserialize, Apiv2Schema)]
+    pub struct ProbePath {
+        /// Hex encoded payment id of the probe.
+        pub payment_id: String,
+        pub path: Vec<PaymentHop>,
+        /// `Pending` when the probe did not come back before the timeout.
+        pub state: PaymentState,
+        /// The channel the probe failed at, when known.
+        pub failed_short_channel_id: Option<u64>,
+        /// The node that could not forward over `failed_short_channel_id`.
+        /// `None` when unknown or when it is our own node.
+        pub failing_node_id: Option<String>,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Apiv2Schema)]
+    pub struct ProbeResult {
+        /// One entry per path of the route. Empty when the destination is a
+        /// peer we have a channel with: single hop paths are not probed.
+        pub probes: Vec<ProbePath>,
+    }
+}
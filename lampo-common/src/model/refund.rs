@@ -0,0 +1,65 @@
+//! BOLT 12 refund model.
+//!
+//! A refund is an "offer for money": the node that creates it pays, and
+//! the node holding it requests the payment by sending back an invoice.
+
+pub mod request {
+    use paperclip::actix::Apiv2Schema;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize, Debug, Apiv2Schema)]
+    pub struct GenerateRefund {
+        pub amount_msat: u64,
+        pub description: Option<String>,
+        pub payer_note: Option<String>,
+        /// Seconds the refund stays valid for, one day by default.
+        pub expiring_in: Option<u32>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Apiv2Schema)]
+    pub struct RequestRefundPayment {
+        /// Bech32 encoded refund (`lnr...`).
+        pub refund: String,
+    }
+}
+
+pub mod response {
+    use paperclip::actix::Apiv2Schema;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize, Debug, Apiv2Schema)]
+    pub struct Refund {
+        /// Bech32 encoded refund (`lnr...`) to hand to the payee.
+        pub bolt12: String,
+        /// Hex encoded id of the payment the refund turns into, for
+        /// `paystatus` / `waitpay`.
+        pub payment_id: String,
+        /// Unix timestamp (seconds) after which the refund can no longer
+        /// be requested.
+        pub expires_at: u64,
+    }
+
+    /// The invoice sent to the refund payer by `requestrefundpayment`.
+    #[derive(Serialize, Deserialize, Debug, Apiv2Schema)]
+    pub struct RefundInvoice {
+        /// Hex encoded payment hash, to follow the payment in `listinvoices`.
+        pub payment_hash: String,
+        pub amount_msat: u64,
+        /// Unix timestamp (seconds) the invoice expires at.
+        pub expires_at: u64,
+    }
+
+    #[derive(Clone, Copy, Debug, Serialize, Deserialize, Apiv2Schema, PartialEq, Eq)]
+    #[serde(rename_all = "snake_case")]
+    pub enum RefundState {
+        /// The payee sent its invoice to the refund payer.
+        InvoiceSent,
+        /// The payee received the refund.
+        Received,
+        /// The payer paid the refund.
+        Paid,
+        /// The payer could not pay the refund, or nobody requested it
+        /// before it expired.
+        Failed,
+    }
+}
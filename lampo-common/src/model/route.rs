@@ -0,0 +1,93 @@
+//! Route finding model.
+
+pub mod request {
+    use std::str::FromStr;
+
+    use paperclip::actix::Apiv2Schema;
+    use serde::{Deserialize, Serialize};
+
+    use crate::bitcoin::secp256k1::PublicKey;
+    use crate::error;
+
+    #[derive(Serialize, Deserialize, Debug, Apiv2Schema)]
+    pub struct GetRoute {
+        /// Node id the route leads to.
+        pub destination: String,
+        /// Amount delivered to the destination, fees excluded.
+        pub amount_msat: u64,
+        /// Upper bound on the routing fees of the whole route.
+        pub max_fee_msat: Option<u64>,
+        /// Upper bound on the total CLTV expiry delta of every path.
+        pub max_cltv_expiry_delta: Option<u32>,
+        /// Node ids the route must avoid.
+        #[serde(default)]
+        pub exclude_nodes: Vec<String>,
+        /// Short channel ids the route must avoid.
+        #[serde(default)]
+        pub exclude_channels: Vec<u64>,
+    }
+
+    impl GetRoute {
+        pub fn destination(&self) -> error::Result<PublicKey> {
+            let destination = PublicKey::from_str(&self.destination)?;
+            Ok(destination)
+        }
+
+        pub fn exclude_nodes(&self) -> error::Result<Vec<PublicKey>> {
+            let mut nodes = Vec::with_capacity(self.exclude_nodes.len());
+            for node in &self.exclude_nodes {
+                nodes.push(PublicKey::from_str(node)?);
+            }
+            Ok(nodes)
+        }
+    }
+}
+
+pub mod response {
+    use paperclip::actix::Apiv2Schema;
+    use serde::{Deserialize, Serialize};
+
+    use crate::ldk::routing::router::{Path, Route as LDKRoute};
+    use crate::model::invoice::response::PaymentHop;
+
+    /// One path of a route. A route has more than one path when the amount
+    /// has to be split over several channels.
+    #[derive(Clone, Debug, Serialize, Deserialize, Apiv2Schema)]
+    pub struct RoutePath {
+        pub hops: Vec<PaymentHop>,
+        /// Amount this path delivers to the destination.
+        pub amount_msat: u64,
+        pub fee_msat: u64,
+        /// Sum of the CLTV expiry deltas of the hops.
+        pub cltv_expiry_delta: u32,
+    }
+
+    impl From<&Path> for RoutePath {
+        fn from(path: &Path) -> Self {
+            Self {
+                hops: path.hops.iter().cloned().map(PaymentHop::from).collect(),
+                amount_msat: path.final_value_msat(),
+                fee_msat: path.fee_msat(),
+                cltv_expiry_delta: path.hops.iter().map(|hop| hop.cltv_expiry_delta).sum(),
+            }
+        }
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Apiv2Schema)]
+    pub struct Route {
+        pub paths: Vec<RoutePath>,
+        pub amount_msat: u64,
+        /// Routing fees of the whole route.
+        pub fee_msat: u64,
+    }
+
+    impl From<LDKRoute> for Route {
+        fn from(route: LDKRoute) -> Self {
+            Self {
+                paths: route.paths.iter().map(RoutePath::from).collect(),
+                amount_msat: route.get_total_amount(),
+                fee_msat: route.get_total_fees(),
+            }
+        }
+    }
+}
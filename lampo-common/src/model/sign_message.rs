@@ -0,0 +1,43 @@
+//! Message signing model.
+//!
+//! Signatures use the zbase32 format of CLN and LND, so a message signed by
+//! one of them can be checked here and the other way round.
+
+pub mod request {
+    use paperclip::actix::Apiv2Schema;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Clone, Debug, Serialize, Deserialize, Apiv2Schema)]
+    pub struct SignMessage {
+        pub message: String,
+    }
+
+    #[derive(Clone, Debug, Serialize, Deserialize, Apiv2Schema)]
+    pub struct CheckMessage {
+        pub message: String,
+        pub zbase: String,
+        /// Node id the signature must be from. Without it the key is
+        /// recovered from the signature.
+        pub pubkey: Option<String>,
+    }
+}
+
+pub mod response {
+    use paperclip::actix::Apiv2Schema;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Clone, Debug, Serialize, Deserialize, Apiv2Schema)]
+    pub struct SignMessage {
+        pub zbase: String,
+    }
+
+    #[derive(Clone, Debug, Serialize, Deserialize, Apiv2Schema)]
+    pub struct CheckMessage {
+        /// Node id that signed the message.
+        pub pubkey: String,
+        /// With a recovered key, true only when the key belongs to us or
+        /// to a node of the network graph: any valid signature recovers to
+        /// some key.
+        pub verified: bool,
+    }
+}
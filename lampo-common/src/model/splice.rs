@@ -0,0 +1,47 @@
+//! Channel splicing model.
+//!
+//! A splice resizes a channel without closing it: the old funding output is
+//! spent by a new funding transaction, and the channel keeps working while
+//! the splice confirms.
+
+pub mod request {
+    use paperclip::actix::Apiv2Schema;
+    use serde::{Deserialize, Serialize};
+
+    /// Add wallet funds to a channel.
+    #[derive(Clone, Debug, Serialize, Deserialize, Apiv2Schema)]
+    pub struct SpliceIn {
+        /// Hex encoded channel id.
+        pub channel_id: String,
+        pub amount_sat: u64,
+        /// Feerate of the splice transaction in sat per 1000 weight, the
+        /// channel funding estimate when empty.
+        pub feerate_sat_per_kw: Option<u32>,
+    }
+
+    /// Move funds out of a channel to an on-chain address.
+    #[derive(Clone, Debug, Serialize, Deserialize, Apiv2Schema)]
+    pub struct SpliceOut {
+        /// Hex encoded channel id.
+        pub channel_id: String,
+        pub amount_sat: u64,
+        pub address: String,
+        /// Feerate of the splice transaction in sat per 1000 weight, the
+        /// channel funding estimate when empty.
+        pub feerate_sat_per_kw: Option<u32>,
+    }
+}
+
+pub mod response {
+    use paperclip::actix::Apiv2Schema;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Clone, Debug, Serialize, Deserialize, Apiv2Schema)]
+    pub struct Splice {
+        pub channel_id: String,
+        pub peer_id: String,
+        /// The funding output the channel moves to once the splice locks.
+        pub new_funding_txo: String,
+        pub txid: String,
+    }
+}
@@ -0,0 +1,94 @@
+//! Output sweeper model.
+//!
+//! Outputs of closed channels that only we can spend are handed to LDK's
+//! `OutputSweeper`, which keeps spending them to the wallet until the
+//! spend is buried deep enough.
+
+pub mod request {
+    use paperclip::actix::Apiv2Schema;
+    use serde::{Deserialize, Serialize};
+
+    /// Filter for `listsweeps`, everything when empty.
+    #[derive(Clone, Debug, Default, Serialize, Deserialize, Apiv2Schema)]
+    pub struct ListSweeps {
+        /// Hex encoded channel id.
+        pub channel_id: Option<String>,
+    }
+
+    /// Spend tracked outputs to `address` now, instead of waiting for the
+    /// sweeper.
+    #[derive(Clone, Debug, Serialize, Deserialize, Apiv2Schema)]
+    pub struct Sweep {
+        pub address: String,
+        /// `txid:vout` of the outputs to spend, every one the sweeper has
+        /// not broadcast a spend of yet when empty.
+        #[serde(default)]
+        pub outpoints: Vec<String>,
+        /// Feerate in sat per 1000 weight, our current estimate for output
+        /// spends when empty.
+        pub feerate_sat_per_kw: Option<u32>,
+    }
+}
+
+pub mod response {
+    use paperclip::actix::Apiv2Schema;
+    use serde::{Deserialize, Serialize};
+
+    /// How the output is spent, see LDK's `SpendableOutputDescriptor`.
+    #[derive(Clone, Copy, Debug, Serialize, Deserialize, Apiv2Schema, PartialEq, Eq)]
+    #[serde(rename_all = "snake_case")]
+    pub enum SweepDescriptor {
+        StaticOutput,
+        DelayedPaymentOutput,
+        StaticPaymentOutput,
+    }
+
+    #[derive(Clone, Copy, Debug, Serialize, Deserialize, Apiv2Schema, PartialEq, Eq)]
+    #[serde(rename_all = "snake_case")]
+    pub enum SweepStatus {
+        /// No spend was broadcast yet.
+        Pending,
+        /// A spend was broadcast and waits for its first confirmation.
+        Broadcast,
+        /// The spend confirmed, the sweeper forgets the output once it is
+        /// buried deep enough.
+        Confirmed,
+    }
+
+    /// An output the sweeper tracks.
+    #[derive(Clone, Debug, Serialize, Deserialize, Apiv2Schema)]
+    pub struct TrackedSweep {
+        /// `txid:vout` of the output.
+        pub outpoint: String,
+        pub descriptor: SweepDescriptor,
+        /// Empty for outputs not tied to a channel.
+        pub channel_id: Option<String>,
+        pub amount_sat: u64,
+        pub status: SweepStatus,
+        /// Height the sweeper waits for before the first broadcast.
+        pub delayed_until_height: Option<u32>,
+        /// Height of the latest broadcast.
+        pub broadcast_height: Option<u32>,
+        pub confirmation_height: Option<u32>,
+        pub spending_txid: Option<String>,
+        /// Our current feerate estimate for output spends, in sat per 1000
+        /// weight. The same for every output, not the feerate of
+        /// `spending_txid`.
+        pub current_feerate_estimate_sat_per_kw: u32,
+    }
+
+    #[derive(Clone, Debug, Serialize, Deserialize, Apiv2Schema)]
+    pub struct Sweeps {
+        pub sweeps: Vec<TrackedSweep>,
+    }
+
+    /// A spend `sweep` broadcast.
+    #[derive(Clone, Debug, Serialize, Deserialize, Apiv2Schema)]
+    pub struct SweepTransaction {
+        pub txid: String,
+        /// Hex encoded transaction.
+        pub tx: String,
+        /// The outputs it spends.
+        pub outpoints: Vec<String>,
+    }
+}
@@ -10,7 +10,7 @@ use crate::chainsync::ChainSyncCoordinator;
 use crate::conf::LampoConf;
 use crate::error;
 use crate::keys::LampoKeys;
-use crate::model::response::{NewAddress, Utxo};
+use crate::model::response::{NewAddress, Utxo, WalletBalance};
 
 /// A lightweight reference to a block (height + hash). Pure `bitcoin` types,
 /// so a chain backend and the wallet can exchange chain positions without the
@@ -44,6 +44,15 @@ pub trait WalletManager: Send + Sync {
     /// Get the current balance of the wallet.
     async fn get_onchain_balance(&self) -> error::Result<u64>;
 
+    /// The wallet balance split by confirmation state. Wallets that only
+    /// know their confirmed balance report everything else as zero.
+    async fn get_wallet_balance(&self) -> error::Result<WalletBalance> {
+        Ok(WalletBalance {
+            confirmed_sat: self.get_onchain_balance().await?,
+            ..Default::default()
+        })
+    }
+
     /// Create the transaction from a script and return the transaction
     /// to propagate to the network.
     async fn create_transaction(
@@ -54,6 +63,17 @@ pub trait WalletManager: Send + Sync {
         best_block: Height,
     ) -> error::Result<Transaction>;
 
+    /// Like [`WalletManager::create_transaction`], paying every output in
+    /// one transaction. Used to fund a batch of channels.
+    async fn create_batch_transaction(
+        &self,
+        _outputs: Vec<(ScriptBuf, Amount)>,
+        _fee_rate: FeeRate,
+        _best_block: Height,
+    ) -> error::Result<Transaction> {
+        error::bail!("wallet does not create batch transactions")
+    }
+
     /// Return the list of transaction stored inside the wallet
     async fn list_transactions(&self) -> error::Result<Vec<Utxo>>;
 
@@ -107,6 +127,16 @@ pub trait WalletManager: Send + Sync {
         Ok(Vec::new())
     }
 
+    /// Keep `outpoints` out of [`WalletManager::confirmed_utxos`] and of
+    /// the transactions the wallet builds, until they are unlocked. Fails,
+    /// locking none of them, when one is locked already.
+    fn lock_utxos(&self, _outpoints: &[OutPoint]) -> error::Result<()> {
+        error::bail!("wallet does not lock UTXOs")
+    }
+
+    /// Give back coins [`WalletManager::lock_utxos`] locked.
+    fn unlock_utxos(&self, _outpoints: &[OutPoint]) {}
+
     /// Full previous transaction for `txid`, if the wallet has it.
     fn get_transaction(&self, _txid: Txid) -> error::Result<Option<Transaction>> {
         Ok(None)
@@ -13,3 +13,8 @@ use crate::{post, AppState, ResultJson};
 post!(getinfo, response: response::GetInfo);
 post!(networkchannels, request: json::Value, response: response::NetworkChannels);
 post!(funds, request: json::Value, response: response::Utxos);
+post!(balances, request: json::Value, response: response::Balances);
+post!(listsweeps, request: request::ListSweeps, response: response::Sweeps);
+post!(sweep, request: request::Sweep, response: response::SweepTransaction);
+post!(signmessage, request: request::SignMessage, response: response::SignMessage);
+post!(checkmessage, request: request::CheckMessage, response: response::CheckMessage);
@@ -5,13 +5,37 @@ use paste::paste;
 
 use lampo_common::json;
 use lampo_common::model::{request, response};
-use lampod::jsonrpc::offchain::{json_decode, json_invoice, json_keysend, json_offer, json_pay};
+use lampod::jsonrpc::offchain::{
+    json_decode, json_disableoffer, json_forwardstats, json_getpayerproof, json_getroute,
+    json_holdinvoice, json_holdinvoice_cancel, json_holdinvoice_settle, json_invoice, json_keysend,
+    json_listinvoices, json_listoffers, json_listpays, json_offer, json_pay, json_paystatus,
+    json_probe, json_probeinvoice, json_refund, json_requestrefundpayment, json_verifypayerproof,
+    json_waitpay,
+};
 
 use crate::{post, AppState, ResultJson};
 
 post!(invoice, request: request::GenerateInvoice, response: response::Invoice);
+post!(holdinvoice, request: request::GenerateHoldInvoice, response: response::HoldInvoice);
+post!(holdinvoice_settle, request: request::SettleHoldInvoice, response: response::HoldInvoice);
+post!(holdinvoice_cancel, request: request::CancelHoldInvoice, response: response::HoldInvoice);
 post!(offer, request: request::GenerateOffer, response: response::Offer);
+post!(listoffers, request: request::ListOffers, response: response::Offers);
+post!(disableoffer, request: request::DisableOffer, response: response::OfferInfo);
+post!(refund, request: request::GenerateRefund, response: response::Refund);
+post!(requestrefundpayment, request: request::RequestRefundPayment, response: response::RefundInvoice);
 // FIXME(vincenzopalazzo): the decode should be generic over any kind of string
 post!(decode, request: request::DecodeInvoice, response: response::Decode);
 post!(pay, request: request::Pay, response: response::PayResult);
 post!(keysend, request: request::KeySend, response: response::PayResult);
+post!(paystatus, request: request::PayStatus, response: response::PaymentInfo);
+post!(waitpay, request: request::WaitPay, response: response::PayResult);
+post!(probe, request: request::Probe, response: response::ProbeResult);
+post!(probeinvoice, request: request::ProbeInvoice, response: response::ProbeResult);
+post!(getroute, request: request::GetRoute, response: response::Route);
+post!(getpayerproof, request: request::GetPayerProof, response: response::PayerProof);
+post!(verifypayerproof, request: request::VerifyPayerProof, response: response::PayerProofVerification);
+post!(listpays, request: request::ListPays, response: response::Pays);
+post!(listinvoices, request: request::ListInvoices, response: response::Invoices);
+post!(listforwards, request: request::ListForwards, response: response::Forwards);
+post!(forwardstats, request: request::ForwardStats, response: response::ForwardStats);
@@ -15,3 +15,14 @@ post!(connect, request: request::Connect, response: request::Connect);
 post!(close, request: request::CloseChannel, response: response::CloseChannel);
 post!(channels, request: json::Value, response: json::Value);
 post!(fundchannel, request: request::OpenChannel, response: json::Value);
+post!(multifundchannel, request: request::MultiFundChannel, response: json::Value);
+post!(fundchannel_start, request: request::OpenChannel, response: json::Value);
+post!(fundchannel_complete, request: request::FundChannelComplete, response: json::Value);
+post!(fundchannel_cancel, request: request::FundChannelCancel, response: json::Value);
+post!(getchannelpolicy, request: json::Value, response: response::ChannelPolicy);
+post!(setchannelpolicy, request: request::ChannelPolicy, response: response::ChannelPolicy);
+post!(setchannelfee, request: request::SetChannelFee, response: response::ChannelConfigs);
+post!(setchannelconfig, request: request::SetChannelConfig, response: response::ChannelConfigs);
+post!(splicein, request: request::SpliceIn, response: response::Splice);
+post!(spliceout, request: request::SpliceOut, response: response::Splice);
+post!(listclosedchannels, request: request::ListClosedChannels, response: response::ClosedChannels);
@@ -17,10 +17,22 @@ use lampo_common::json;
 use lampod::LampoDaemon;
 
 use commands::daemon::rest_stop;
-use commands::inventory::{rest_funds, rest_getinfo, rest_networkchannels};
-use commands::offchain::{rest_decode, rest_invoice, rest_keysend, rest_pay};
+use commands::inventory::{
+    rest_balances, rest_checkmessage, rest_funds, rest_getinfo, rest_listsweeps,
+    rest_networkchannels, rest_signmessage, rest_sweep,
+};
+use commands::offchain::{
+    rest_decode, rest_disableoffer, rest_forwardstats, rest_getpayerproof, rest_getroute,
+    rest_holdinvoice, rest_holdinvoice_cancel, rest_holdinvoice_settle, rest_invoice, rest_keysend,
+    rest_listinvoices, rest_listoffers, rest_listpays, rest_pay, rest_paystatus, rest_probe,
+    rest_probeinvoice, rest_refund, rest_requestrefundpayment, rest_verifypayerproof, rest_waitpay,
+};
 use commands::onchain::rest_new_addr;
 use commands::peer::{rest_channels, rest_close, rest_connect, rest_fundchannel};
+use commands::peer::{rest_fundchannel_cancel, rest_fundchannel_complete, rest_fundchannel_start};
+use commands::peer::{rest_getchannelpolicy, rest_setchannelconfig, rest_setchannelfee};
+use commands::peer::{rest_listclosedchannels, rest_multifundchannel};
+use commands::peer::{rest_setchannelpolicy, rest_splicein, rest_spliceout};
 
 use crate::commands::offchain::rest_offer;
 
@@ -258,14 +270,48 @@ pub async fn run<T: ToSocketAddrs + Display>(
             .service(rest_channels)
             .service(rest_connect)
             .service(rest_fundchannel)
+            .service(rest_multifundchannel)
+            .service(rest_fundchannel_start)
+            .service(rest_fundchannel_complete)
+            .service(rest_fundchannel_cancel)
             .service(rest_close)
+            .service(rest_getchannelpolicy)
+            .service(rest_setchannelpolicy)
+            .service(rest_setchannelfee)
+            .service(rest_setchannelconfig)
+            .service(rest_splicein)
+            .service(rest_spliceout)
+            .service(rest_listclosedchannels)
             .service(rest_networkchannels)
+            .service(rest_signmessage)
+            .service(rest_checkmessage)
             .service(rest_invoice)
+            .service(rest_holdinvoice)
+            .service(rest_holdinvoice_settle)
+            .service(rest_holdinvoice_cancel)
             .service(rest_offer)
+            .service(rest_listoffers)
+            .service(rest_disableoffer)
+            .service(rest_refund)
+            .service(rest_requestrefundpayment)
             .service(rest_decode)
             .service(rest_pay)
             .service(rest_keysend)
+            .service(rest_paystatus)
+            .service(rest_waitpay)
+            .service(rest_probe)
+            .service(rest_probeinvoice)
+            .service(rest_getroute)
+            .service(rest_getpayerproof)
+            .service(rest_verifypayerproof)
+            .service(rest_listpays)
+            .service(rest_listinvoices)
+            .service(rest_listforwards)
+            .service(rest_forwardstats)
             .service(rest_funds)
+            .service(rest_balances)
+            .service(rest_listsweeps)
+            .service(rest_sweep)
             .service(rest_new_addr)
             .service(rest_stop)
             .build()
@@ -1,9 +1,13 @@
 //! Handler module implementation that
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
 use std::net::SocketAddr;
 use std::sync::Arc;
 
 use lampo_common::bitcoin::absolute::Height;
 use lampo_common::bitcoin::hashes::Hash;
+use lampo_common::bitcoin::psbt::Psbt;
+use lampo_common::bitcoin::secp256k1::PublicKey;
 use lampo_common::bitcoin::{Amount, OutPoint, Transaction, WPubkeyHash};
 use tokio::sync::RwLock;
 
@@ -14,24 +18,37 @@ use lampo_common::error::Ok;
 use lampo_common::event::ln::LightningEvent;
 use lampo_common::event::onchain::OnChainEvent;
 use lampo_common::event::{Emitter, Event, Subscriber};
-use lampo_common::handler::ExternalHandler;
 use lampo_common::handler::Handler as EventHandler;
+use lampo_common::handler::{ChannelHook, ChannelHookDecision};
+use lampo_common::handler::{ExternalHandler, InboundPayment, PaymentHook, PaymentHookDecision};
+use lampo_common::hex::FromHex;
 use lampo_common::json;
 use lampo_common::jsonrpc::Request;
 use lampo_common::keys::LampoKeysManager;
 use lampo_common::ldk;
 use lampo_common::ldk::chain::chaininterface::BroadcasterInterface;
 use lampo_common::ldk::events::bump_transaction::BumpTransactionEventHandler;
-use lampo_common::ldk::sign::{NodeSigner, SpendableOutputDescriptor};
-use lampo_common::ldk::types::payment::PaymentPreimage;
+use lampo_common::ldk::events::HTLCHandlingFailureType;
+use lampo_common::ldk::ln::outbound_payment::RecipientOnionFields;
+use lampo_common::ldk::sign::{EntropySource, NodeSigner, SpendableOutputDescriptor};
+use lampo_common::ldk::types::payment::{PaymentHash, PaymentPreimage};
 use lampo_common::ldk::util::wallet_utils::{Utxo, Wallet, WalletSource};
+use lampo_common::model::request::{ChannelPolicy, ChannelPolicyDecision, InboundChannel};
+use lampo_common::model::response::CustomTlv;
 use lampo_common::model::response::PaymentHop;
 use lampo_common::model::response::PaymentState;
+use lampo_common::model::response::{ForwardInfo, ForwardStatus};
+use lampo_common::model::response::{HoldInvoice, HoldInvoiceState};
+use lampo_common::model::response::{InvoiceInfo, InvoiceStatus, PaymentAttempt, PaymentKind};
+use lampo_common::model::response::{PaymentInfo, RefundState};
 use lampo_common::utils::logger::LampoLogger;
 
 use crate::chain::{FeeTarget, LampoChainManager, WalletManager};
 use crate::command::Command;
+use crate::ln::hold_invoice;
+use crate::ln::ledger;
 use crate::ln::payer_proof::{self, PayerProofRecord};
+use crate::ln::EXTERNAL_FUNDING_TAG;
 use crate::ln::{LampoChannelManager, LampoInventoryManager, LampoPeerManager};
 use crate::persistence::LampoPersistence;
 use crate::LampoDaemon;
@@ -91,6 +108,21 @@ pub struct LampoHandler {
     persister: Arc<LampoPersistence>,
     bump_tx_event_handler: BumpHandler,
     external_handlers: RwLock<Vec<Arc<dyn ExternalHandler>>>,
+    payment_hooks: RwLock<Vec<Arc<dyn PaymentHook>>>,
+    channel_policy: std::sync::RwLock<ChannelPolicy>,
+    channel_hooks: RwLock<Vec<Arc<dyn ChannelHook>>>,
+    /// Inbound channel requests still being decided, by peer. LDK lists
+    /// them only once accepted, and they count against the policy's
+    /// pending channel limit before that.
+    deciding_inbound: Arc<std::sync::Mutex<HashMap<PublicKey, usize>>>,
+    /// Our last broadcasts, to find the closing transaction of a channel
+    /// whose `ChannelClosed` arrives after LDK broadcast it.
+    recent_broadcasts: std::sync::Mutex<VecDeque<Transaction>>,
+    /// Funding outpoints of the channels that closed before we broadcast
+    /// their closing transaction, with the channel ids and when they
+    /// closed. A commitment the peer publishes never shows up, so entries
+    /// expire after [`AWAITING_CLOSING_TX_SECS`].
+    awaiting_closing_tx: std::sync::Mutex<HashMap<String, (String, std::time::Instant)>>,
     #[allow(dead_code)]
     emitter: Emitter<Event>,
     subscriber: Subscriber<Event>,
@@ -119,6 +151,12 @@ impl LampoHandler {
             persister: lampod.persister(),
             bump_tx_event_handler,
             external_handlers: RwLock::new(Vec::new()),
+            payment_hooks: RwLock::new(Vec::new()),
+            channel_policy: std::sync::RwLock::new(stored_channel_policy(lampod)),
+            channel_hooks: RwLock::new(Vec::new()),
+            deciding_inbound: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            recent_broadcasts: std::sync::Mutex::new(VecDeque::new()),
+            awaiting_closing_tx: std::sync::Mutex::new(HashMap::new()),
             emitter,
             subscriber,
         }
@@ -133,10 +171,254 @@ impl LampoHandler {
         Ok(())
     }
 
+    pub async fn add_payment_hook(&self, hook: Arc<dyn PaymentHook>) -> error::Result<()> {
+        let mut payment_hooks = self.payment_hooks.write().await;
+        payment_hooks.push(hook);
+        Ok(())
+    }
+
+    /// Remember `tx` and, when it spends the funding output of a channel
+    /// that already closed, record it as that channel's closing transaction.
+    fn track_broadcast(&self, tx: &Transaction) {
+        // Held across both, see `closing_txid`.
+        let mut awaiting = self
+            .awaiting_closing_tx
+            .lock()
+            .expect("awaiting closing tx poisoned");
+        {
+            let mut recent = self
+                .recent_broadcasts
+                .lock()
+                .expect("recent broadcasts poisoned");
+            if recent.len() == RECENT_BROADCASTS {
+                recent.pop_front();
+            }
+            recent.push_back(tx.clone());
+        }
+        let closed = tx
+            .input
+            .iter()
+            .filter_map(|input| awaiting.remove(&input.previous_output.to_string()))
+            .map(|(channel_id, _)| channel_id)
+            .collect::<Vec<_>>();
+        drop(awaiting);
+        let txid = tx.compute_txid().to_string();
+        for channel_id in closed {
+            ledger::record_channel(&self.persister, &channel_id, |entry| {
+                entry.closing_txid = Some(txid.clone());
+            });
+        }
+    }
+
+    /// The txid of a recent broadcast spending `funding_txo`. Without one
+    /// the channel waits for [`LampoHandler::track_broadcast`] to see it:
+    /// the same lock covers the lookup and the wait, so a broadcast racing
+    /// the close is caught by one or the other.
+    fn closing_txid(&self, channel_id: &str, funding_txo: &str) -> Option<String> {
+        let mut awaiting = self
+            .awaiting_closing_tx
+            .lock()
+            .expect("awaiting closing tx poisoned");
+        let recent = self
+            .recent_broadcasts
+            .lock()
+            .expect("recent broadcasts poisoned");
+        let txid = recent
+            .iter()
+            .rev()
+            .find(|tx| spends(tx, funding_txo))
+            .map(|tx| tx.compute_txid().to_string());
+        if txid.is_none() {
+            let ttl = std::time::Duration::from_secs(AWAITING_CLOSING_TX_SECS);
+            awaiting.retain(|_, (_, closed_at)| closed_at.elapsed() < ttl);
+            if awaiting.len() >= RECENT_BROADCASTS {
+                let oldest = awaiting
+                    .iter()
+                    .min_by_key(|(_, (_, closed_at))| *closed_at)
+                    .map(|(funding_txo, _)| funding_txo.clone());
+                if let Some(oldest) = oldest {
+                    awaiting.remove(&oldest);
+                }
+            }
+            awaiting.insert(
+                funding_txo.to_owned(),
+                (channel_id.to_owned(), std::time::Instant::now()),
+            );
+        }
+        txid
+    }
+
+    /// Stop waiting for the closing transaction of a channel.
+    fn forget_closing(&self, funding_txo: &str) {
+        self.awaiting_closing_tx
+            .lock()
+            .expect("awaiting closing tx poisoned")
+            .remove(funding_txo);
+    }
+
+    pub async fn add_channel_hook(&self, hook: Arc<dyn ChannelHook>) -> error::Result<()> {
+        let mut channel_hooks = self.channel_hooks.write().await;
+        channel_hooks.push(hook);
+        Ok(())
+    }
+
+    pub fn channel_policy(&self) -> ChannelPolicy {
+        self.channel_policy
+            .read()
+            .expect("channel policy lock poisoned")
+            .clone()
+    }
+
+    /// Replace the inbound channel policy. It is stored, and replaces the
+    /// one of `lampo.conf` after a restart too.
+    pub fn set_channel_policy(&self, policy: ChannelPolicy) -> error::Result<()> {
+        // Held while storing, so the store and memory agree on the winner
+        // of two concurrent calls.
+        let mut current = self
+            .channel_policy
+            .write()
+            .expect("channel policy lock poisoned");
+        ledger::try_update(
+            &self.persister,
+            CHANNEL_POLICY_NAMESPACE,
+            CHANNEL_POLICY_KEY,
+            |_: Option<ChannelPolicy>| Ok(policy.clone()),
+        )?;
+        *current = policy;
+        Ok(())
+    }
+
+    /// Sign the inputs of an interactively built funding transaction that
+    /// belong to our wallet. The others are signed by the peer, or by LDK
+    /// for the shared input of a splice.
+    fn sign_funding_inputs(&self, unsigned: Transaction) -> error::Result<Transaction> {
+        let mut psbt = Psbt::from_unsigned_tx(unsigned)?;
+        for (txin, input) in psbt.unsigned_tx.input.iter().zip(psbt.inputs.iter_mut()) {
+            let prevout = txin.previous_output;
+            if let Some(prevtx) = self.wallet_manager.get_transaction(prevout.txid)? {
+                input.witness_utxo = prevtx.output.get(prevout.vout as usize).cloned();
+            }
+        }
+        self.wallet_manager.sign_psbt(psbt)
+    }
+
+    /// A fresh key for a forwarding ledger entry. LDK gives forwards no id
+    /// of their own.
+    fn forward_id(&self) -> String {
+        let bytes = self
+            .wallet_manager
+            .ldk_keys()
+            .keys_manager
+            .get_secure_random_bytes();
+        lampo_common::hex::encode(bytes)
+    }
+
     pub fn peer_manager(&self) -> Arc<LampoPeerManager> {
         self.peer_manager.clone()
     }
 
+    /// Tell subscribers a refund we created was paid or failed. Refunds are
+    /// only told apart from other payments by their ledger entry.
+    fn emit_refund_event(&self, entry: Option<PaymentInfo>, state: RefundState) {
+        let Some(entry) = entry.filter(|entry| entry.kind == PaymentKind::Refund) else {
+            return;
+        };
+        self.emit(Event::Lightning(LightningEvent::RefundEvent {
+            payment_id: Some(entry.payment_id),
+            payment_hash: entry.payment_hash,
+            state,
+            amount_msat: entry.amount_msat,
+        }));
+    }
+
+    /// The preimage the ledger stores for `payment_hash`, if it hashes
+    /// back to it.
+    fn invoice_preimage(&self, payment_hash: &PaymentHash) -> Option<PaymentPreimage> {
+        let key = lampo_common::hex::encode(payment_hash.0);
+        let invoice = ledger::load_invoice(&self.persister, &key).unwrap_or_else(|err| {
+            log::error!(target: "lampo::handler", "reading invoice `{key}`: {err}");
+            None
+        })?;
+        let preimage = PaymentPreimage(<[u8; 32]>::from_hex(&invoice.payment_preimage?).ok()?);
+        let hash = lampo_common::bitcoin::hashes::sha256::Hash::hash(&preimage.0);
+        (hash.to_byte_array() == payment_hash.0).then_some(preimage)
+    }
+
+    /// Whether `purpose` pays an offer we disabled. Offers missing from the
+    /// ledger predate it and stay payable.
+    fn offer_disabled(&self, purpose: &ldk::events::PaymentPurpose) -> bool {
+        let ldk::events::PaymentPurpose::Bolt12OfferPayment {
+            payment_context, ..
+        } = purpose
+        else {
+            return false;
+        };
+        let offer_id = lampo_common::hex::encode(payment_context.offer_id.0);
+        match ledger::load_offer(&self.persister, &offer_id) {
+            std::result::Result::Ok(offer) => offer.is_some_and(|offer| offer.disabled),
+            Err(err) => {
+                log::error!(target: "lampo::handler", "reading offer `{offer_id}`: {err}");
+                false
+            }
+        }
+    }
+
+    /// Park a payment for a hold invoice until the caller settles or
+    /// cancels it. A payment that cannot be accepted is failed back.
+    fn park_hold_payment(
+        &self,
+        hold: HoldInvoice,
+        payment_hash: PaymentHash,
+        amount_msat: u64,
+        counterparty_skimmed_fee_msat: u64,
+        claim_deadline: Option<u32>,
+    ) {
+        // Settled before a restart and replayed by LDK: claim it again.
+        if hold.state == HoldInvoiceState::Settled {
+            if let Some(preimage) = hold
+                .payment_preimage
+                .as_deref()
+                .and_then(|preimage| <[u8; 32]>::from_hex(preimage).ok())
+            {
+                self.channel_manager
+                    .manager()
+                    .claim_funds(PaymentPreimage(preimage));
+                return;
+            }
+        }
+        let result = hold_invoice::transition(&self.persister, &payment_hash, |invoice| {
+            hold_invoice::accept(
+                invoice,
+                amount_msat,
+                counterparty_skimmed_fee_msat,
+                claim_deadline,
+            )
+        });
+        match result {
+            std::result::Result::Ok(invoice) => {
+                log::info!(
+                    target: "lampo::handler",
+                    "holding payment `{payment_hash}` of {amount_msat} msat until settled (claim deadline {claim_deadline:?})"
+                );
+                self.emit(Event::Lightning(LightningEvent::HoldInvoiceEvent {
+                    payment_hash: invoice.payment_hash,
+                    state: invoice.state,
+                    amount_msat: invoice.amount_received_msat,
+                    claim_deadline: invoice.claim_deadline,
+                }));
+            }
+            Err(err) => {
+                log::error!(
+                    target: "lampo::handler",
+                    "refusing hold payment `{payment_hash}` of {amount_msat} msat: {err}; failing the HTLC(s) back to the sender"
+                );
+                self.channel_manager
+                    .manager()
+                    .fail_htlc_backwards(&payment_hash);
+            }
+        }
+    }
+
     /// Call any method supported by the lampod configuration. This includes
     /// a lot of handler code. This function serves as a broker pattern in some ways,
     /// but it may also function as a chain of responsibility pattern in certain cases.
@@ -154,9 +436,56 @@ impl LampoHandler {
     }
 }
 
+/// Where `setchannelpolicy` stores the policy.
+const CHANNEL_POLICY_NAMESPACE: &str = "channel_policy";
+const CHANNEL_POLICY_KEY: &str = "current";
+
+/// The policy last set with `setchannelpolicy`, or the one of `lampo.conf`
+/// when there is none.
+fn stored_channel_policy(lampod: &LampoDaemon) -> ChannelPolicy {
+    let conf = lampod.conf();
+    let conf = &conf.channel_policy;
+    let stored = ledger::read::<ChannelPolicy>(
+        &lampod.persister(),
+        CHANNEL_POLICY_NAMESPACE,
+        CHANNEL_POLICY_KEY,
+    );
+    match stored {
+        std::result::Result::Ok(Some(policy)) => {
+            if policy != *conf {
+                log::warn!(target: "lampo::handler", "using the channel policy set with `setchannelpolicy`, the one of lampo.conf is ignored");
+            }
+            policy
+        }
+        std::result::Result::Ok(None) => conf.clone(),
+        Err(err) => {
+            log::error!(target: "lampo::handler", "reading the stored channel policy: {err}");
+            conf.clone()
+        }
+    }
+}
+
+/// How many of our broadcasts [`LampoHandler::closing_txid`] looks at,
+/// and how many closes wait for theirs.
+const RECENT_BROADCASTS: usize = 32;
+
+/// How long a closed channel waits for us to broadcast its closing
+/// transaction.
+const AWAITING_CLOSING_TX_SECS: u64 = 60 * 60;
+
+/// Whether `tx` spends the `txid:vout` outpoint.
+fn spends(tx: &Transaction, outpoint: &str) -> bool {
+    tx.input
+        .iter()
+        .any(|input| input.previous_output.to_string() == outpoint)
+}
+
 impl EventHandler for LampoHandler {
     fn emit(&self, event: Event) {
         log::debug!(target: "emitter", "emit event: {:?}", event);
+        if let Event::OnChain(OnChainEvent::SendRawTransaction(tx)) = &event {
+            self.track_broadcast(tx);
+        }
         self.emitter.emit(event)
     }
 
@@ -199,19 +528,104 @@ impl Handler for LampoHandler {
             ldk::events::Event::OpenChannelRequest {
                 temporary_channel_id,
                 counterparty_node_id,
+                funding_satoshis,
+                channel_type,
+                is_announced,
                 ..
             } => {
                 // LDK 0.3 removed `manually_accept_inbound_channels`; inbound
                 // channels are now always surfaced here and must be accepted
-                // explicitly. Auto-accept to preserve the previous behaviour.
-                log::info!(
-                    target: "lampod",
-                    "accepting inbound channel request from `{counterparty_node_id}`"
-                );
-                self.channel_manager
+                // or rejected explicitly.
+                let peer = counterparty_node_id.to_string();
+                let pending_with_peer = self
+                    .channel_manager
                     .manager()
-                    .accept_inbound_channel(&temporary_channel_id, &counterparty_node_id, 0, None)
-                    .map_err(|err| error::anyhow!("{:?}", err))?;
+                    .list_channels()
+                    .iter()
+                    .filter(|channel| {
+                        channel.counterparty.node_id == counterparty_node_id
+                            && !channel.is_channel_ready
+                    })
+                    .count();
+                let pending_with_peer = {
+                    let mut deciding = self
+                        .deciding_inbound
+                        .lock()
+                        .expect("deciding inbound lock poisoned");
+                    let deciding = deciding.entry(counterparty_node_id).or_default();
+                    *deciding += 1;
+                    pending_with_peer + *deciding - 1
+                };
+                let channel = InboundChannel {
+                    temporary_channel_id: temporary_channel_id.to_string(),
+                    counterparty_node_id: peer.clone(),
+                    funding_sat: funding_satoshis,
+                    anchors: channel_type.supports_anchors_zero_fee_htlc_tx(),
+                    announced: is_announced,
+                    pending_with_peer,
+                };
+                // The channel hooks may take their time, and the LDK event
+                // loop waits for this handler: decide on a task of its own.
+                let policy = self.channel_policy();
+                let channel_hooks = self.channel_hooks.read().await.clone();
+                let channel_manager = self.channel_manager.clone();
+                let deciding_inbound = self.deciding_inbound.clone();
+                tokio::spawn(async move {
+                    let verdict = inbound_channel_verdict(&policy, &channel_hooks, &channel).await;
+                    let manager = channel_manager.manager();
+                    let result = match verdict {
+                        Err(reason) => {
+                            log::info!(
+                                target: "lampod",
+                                "rejecting inbound channel request from `{peer}`: {reason}"
+                            );
+                            manager.force_close_broadcasting_latest_txn(
+                                &temporary_channel_id,
+                                &counterparty_node_id,
+                                reason,
+                            )
+                        }
+                        std::result::Result::Ok(true) => {
+                            log::info!(
+                                target: "lampod",
+                                "accepting zero-conf inbound channel request from `{peer}`"
+                            );
+                            manager.accept_inbound_channel_from_trusted_peer_0conf(
+                                &temporary_channel_id,
+                                &counterparty_node_id,
+                                0,
+                                None,
+                            )
+                        }
+                        std::result::Result::Ok(false) => {
+                            log::info!(
+                                target: "lampod",
+                                "accepting inbound channel request from `{peer}`"
+                            );
+                            manager.accept_inbound_channel(
+                                &temporary_channel_id,
+                                &counterparty_node_id,
+                                0,
+                                None,
+                            )
+                        }
+                    };
+                    if let Err(err) = result {
+                        log::error!(
+                            target: "lampod",
+                            "answering inbound channel request from `{peer}`: {err:?}"
+                        );
+                    }
+                    let mut deciding = deciding_inbound
+                        .lock()
+                        .expect("deciding inbound lock poisoned");
+                    if let Some(count) = deciding.get_mut(&counterparty_node_id) {
+                        *count -= 1;
+                        if *count == 0 {
+                            deciding.remove(&counterparty_node_id);
+                        }
+                    }
+                });
                 Ok(())
             }
             ldk::events::Event::ChannelReady {
@@ -222,6 +636,28 @@ impl Handler for LampoHandler {
                 ..
             } => {
                 log::info!("channel ready with node `{counterparty_node_id}`, and channel type {channel_type}");
+                let details = self
+                    .channel_manager
+                    .manager()
+                    .list_channels()
+                    .into_iter()
+                    .find(|channel| channel.channel_id == channel_id);
+                // A splice is over once the channel is ready on its output.
+                self.channel_manager.splice_locked(
+                    &channel_id,
+                    details
+                        .as_ref()
+                        .and_then(|details| details.funding_txo)
+                        .map(|txo| txo.into_bitcoin_outpoint()),
+                );
+                ledger::record_channel(&self.persister, &channel_id.to_string(), |entry| {
+                    entry.peer_id = Some(counterparty_node_id.to_string());
+                    if let Some(details) = details {
+                        entry.short_channel_id = details.short_channel_id;
+                        entry.capacity_sat = Some(details.channel_value_satoshis);
+                        entry.funding_txo = details.funding_txo.map(|txo| txo.to_string());
+                    }
+                });
                 self.emit(Event::Lightning(LightningEvent::ChannelReady {
                     counterparty_node_id,
                     channel_id,
@@ -235,11 +671,16 @@ impl Handler for LampoHandler {
                 reason,
                 counterparty_node_id,
                 channel_funding_txo,
+                channel_capacity_sats,
+                last_local_balance_msat,
                 ..
             } => {
                 if let Some(node_id) = counterparty_node_id {
                     log::warn!("closing channels with `{node_id}`");
                 }
+                // A splice cannot outlive its channel.
+                self.channel_manager.splice_failed(&channel_id);
+                self.channel_manager.external_funding_closed(&channel_id);
 
                 // Provide detailed closure reason based on the ClosureReason enum
                 let detailed_reason = match reason {
@@ -310,6 +751,26 @@ impl Handler for LampoHandler {
 
                 let node_id = counterparty_node_id.map(|id| id.to_string());
                 let txo = channel_funding_txo.map(|txo| txo.to_string());
+                // Channels that never got a funding output leave nothing on
+                // chain worth a history entry.
+                if let Some(funding_txo) = &txo {
+                    let channel_id = channel_id.to_string();
+                    let closing_txid = self.closing_txid(&channel_id, funding_txo);
+                    let mut closing_tx_known = false;
+                    ledger::record_channel(&self.persister, &channel_id, |entry| {
+                        entry.peer_id = node_id.clone().or(entry.peer_id.take());
+                        entry.capacity_sat = channel_capacity_sats.or(entry.capacity_sat);
+                        entry.funding_txo = txo.clone();
+                        entry.final_local_balance_msat = last_local_balance_msat;
+                        entry.closure_reason = Some(detailed_reason.clone());
+                        entry.closing_txid = closing_txid.or(entry.closing_txid.take());
+                        entry.closed_at = Some(ledger::now());
+                        closing_tx_known = entry.closing_txid.is_some();
+                    });
+                    if closing_tx_known {
+                        self.forget_closing(funding_txo);
+                    }
+                }
                 self.emit(Event::Lightning(LightningEvent::CloseChannelEvent {
                     channel_id: channel_id.to_string(),
                     message: detailed_reason.clone(),
@@ -324,13 +785,33 @@ impl Handler for LampoHandler {
                 counterparty_node_id,
                 channel_value_satoshis,
                 output_script,
+                user_channel_id,
                 ..
             } => {
+                // A `multifundchannel` batch funds its channels together, and
+                // an external wallet funds the ones of `fundchannel_start`.
+                let batched = self.channel_manager.batch_funding_ready(
+                    user_channel_id,
+                    temporary_channel_id,
+                    output_script.clone(),
+                );
+                let external = !batched && user_channel_id & EXTERNAL_FUNDING_TAG != 0;
+                if external {
+                    self.channel_manager.external_funding_ready(
+                        temporary_channel_id,
+                        counterparty_node_id,
+                        output_script.clone(),
+                        channel_value_satoshis,
+                    );
+                }
                 self.emit(Event::Lightning(LightningEvent::FundingChannelStart {
                     counterparty_node_id,
                     temporary_channel_id,
                     channel_value_satoshis,
                 }));
+                if batched || external {
+                    return Ok(());
+                }
 
                 log::info!("propagate funding transaction for open a channel with `{counterparty_node_id}`");
                 // Drop the outbound channel when funding cannot proceed: LDK
@@ -447,6 +928,66 @@ impl Handler for LampoHandler {
                 }));
                 Ok(())
             }
+            ldk::events::Event::FundingTransactionReadyForSigning {
+                channel_id,
+                counterparty_node_id,
+                unsigned_transaction,
+                ..
+            } => {
+                log::info!(target: "lampo::handler", "signing our inputs of the funding of `{channel_id}`");
+                let signed = self
+                    .sign_funding_inputs(unsigned_transaction)
+                    .and_then(|tx| {
+                        self.channel_manager
+                            .manager()
+                            .funding_transaction_signed(&channel_id, &counterparty_node_id, tx)
+                            .map_err(|err| error::anyhow!("{:?}", err))
+                    });
+                if let Err(err) = signed {
+                    log::error!(target: "lampo::handler", "signing the funding of `{channel_id}`: {err}");
+                    // Only a splice waits on this, the interactive
+                    // funding of a new channel is not started by lampo.
+                    if self.channel_manager.is_splicing(&channel_id) {
+                        self.channel_manager.splice_failed(&channel_id);
+                        self.emit(Event::Lightning(LightningEvent::SpliceFailed {
+                            counterparty_node_id,
+                            channel_id,
+                            reason: format!("{err}"),
+                        }));
+                    }
+                }
+                Ok(())
+            }
+            ldk::events::Event::SplicePending {
+                channel_id,
+                counterparty_node_id,
+                new_funding_txo,
+                ..
+            } => {
+                log::info!("splice of `{channel_id}` pending with funding `{new_funding_txo}`");
+                self.channel_manager
+                    .splice_pending(channel_id, new_funding_txo);
+                self.emit(Event::Lightning(LightningEvent::SplicePending {
+                    counterparty_node_id,
+                    channel_id,
+                    new_funding_txo,
+                }));
+                Ok(())
+            }
+            ldk::events::Event::SpliceFailed {
+                channel_id,
+                counterparty_node_id,
+                ..
+            } => {
+                log::warn!("splice of `{channel_id}` failed, the channel keeps its funding");
+                self.channel_manager.splice_failed(&channel_id);
+                self.emit(Event::Lightning(LightningEvent::SpliceFailed {
+                    counterparty_node_id,
+                    channel_id,
+                    reason: "the splice negotiation was aborted".to_owned(),
+                }));
+                Ok(())
+            }
             ldk::events::Event::PaymentClaimable {
                 receiver_node_id,
                 payment_hash,
@@ -458,59 +999,162 @@ impl Handler for LampoHandler {
                 payment_id: _,
                 ..
             } => {
-                match decide_payment_claim(
-                    amount_msat,
-                    counterparty_skimmed_fee_msat,
-                    &purpose,
-                ) {
-                    PaymentClaimDecision::Claim(preimage) => {
-                        log::info!(
-                            target: "lampo::handler",
-                            "claiming payment `{payment_hash}` of {amount_msat} msat (claim deadline {claim_deadline:?})"
-                        );
-                        self.channel_manager.manager().claim_funds(preimage);
+                let hold = hold_invoice::load(&self.persister, &payment_hash).unwrap_or_else(|err| {
+                    // Without the record the payment has no preimage and is
+                    // failed back below, which is the safe outcome.
+                    log::error!(target: "lampo::handler", "reading hold invoice `{payment_hash}`: {err}");
+                    None
+                });
+                if let Some(hold) = hold {
+                    self.park_hold_payment(
+                        hold,
+                        payment_hash,
+                        amount_msat,
+                        counterparty_skimmed_fee_msat,
+                        claim_deadline,
+                    );
+                    return Ok(());
+                }
+                // Invoices created with a caller-supplied preimage reach
+                // us without one, the ledger holds it.
+                let decision = match self.invoice_preimage(&payment_hash) {
+                    // LDK answers invoice requests on its own, so the
+                    // payment is where a disabled offer is refused.
+                    _ if self.offer_disabled(&purpose) => {
+                        PaymentClaimDecision::FailBack("the offer was disabled".into())
                     }
-                    PaymentClaimDecision::FailBack(reason) => {
-                        log::error!(
-                            target: "lampo::handler",
-                            "refusing to claim payment `{payment_hash}` of {amount_msat} msat (counterparty skimmed {counterparty_skimmed_fee_msat} msat, claim deadline {claim_deadline:?}): {reason}; failing the HTLC(s) back to the sender"
+                    Some(preimage)
+                        if purpose.preimage().is_none() && counterparty_skimmed_fee_msat == 0 =>
+                    {
+                        PaymentClaimDecision::Claim(preimage)
+                    }
+                    _ => decide_payment_claim(amount_msat, counterparty_skimmed_fee_msat, &purpose),
+                };
+                let preimage = match decision {
+                    PaymentClaimDecision::Claim(preimage) => preimage,
+                    decision => {
+                        settle_payment(
+                            &self.channel_manager,
+                            &payment_hash,
+                            amount_msat,
+                            counterparty_skimmed_fee_msat,
+                            claim_deadline,
+                            decision,
                         );
-                        self.channel_manager
-                            .manager()
-                            .fail_htlc_backwards(&payment_hash);
+                        return Ok(());
                     }
+                };
+                let payment_hooks = self.payment_hooks.read().await.clone();
+                if payment_hooks.is_empty() {
+                    settle_payment(
+                        &self.channel_manager,
+                        &payment_hash,
+                        amount_msat,
+                        counterparty_skimmed_fee_msat,
+                        claim_deadline,
+                        PaymentClaimDecision::Claim(preimage),
+                    );
+                    return Ok(());
                 }
+                // The hooks may take their time, and the LDK event loop
+                // waits for this handler: ask them on their own task, which
+                // claims or fails the payment once they answered.
+                let payment = inbound_payment(
+                    &payment_hash,
+                    amount_msat,
+                    &purpose,
+                    onion_fields.as_ref(),
+                    claim_deadline,
+                );
+                let height = self.channel_manager.manager().current_best_block().height;
+                let budget = hooks_budget(claim_deadline, height);
+                let channel_manager = self.channel_manager.clone();
+                tokio::spawn(async move {
+                    let decision =
+                        match payment_hooks_verdict(&payment_hooks, &payment, budget).await {
+                            Some(reason) => PaymentClaimDecision::FailBack(reason.into()),
+                            None => PaymentClaimDecision::Claim(preimage),
+                        };
+                    settle_payment(
+                        &channel_manager,
+                        &payment_hash,
+                        amount_msat,
+                        counterparty_skimmed_fee_msat,
+                        claim_deadline,
+                        decision,
+                    );
+                });
                 Ok(())
             }
             ldk::events::Event::PaymentClaimed {
-                receiver_node_id,
                 payment_hash,
                 amount_msat,
                 purpose,
+                onion_fields,
                 ..
             } => {
-                let (payment_preimage, payment_secret) = match purpose {
+                let (kind, payment_preimage, offer_id) = match purpose {
                     ldk::events::PaymentPurpose::Bolt11InvoicePayment {
-                        payment_preimage,
-                        payment_secret,
-                        ..
-                    } => (payment_preimage, Some(payment_secret)),
+                        payment_preimage, ..
+                    } => (PaymentKind::Bolt11, payment_preimage, None),
                     ldk::events::PaymentPurpose::Bolt12OfferPayment {
                         payment_preimage,
-                        payment_secret,
+                        payment_context,
                         ..
-                    } => (payment_preimage, Some(payment_secret)),
-                    ldk::events::PaymentPurpose::Bolt12RefundPayment {
+                    } => (
+                        PaymentKind::Bolt12,
                         payment_preimage,
-                        payment_secret,
-                        ..
-                    } => (payment_preimage, Some(payment_secret)),
+                        Some(lampo_common::hex::encode(payment_context.offer_id.0)),
+                    ),
+                    ldk::events::PaymentPurpose::Bolt12RefundPayment {
+                        payment_preimage, ..
+                    } => (PaymentKind::Refund, payment_preimage, None),
                     ldk::events::PaymentPurpose::SpontaneousPayment(preimage) => {
-                        (Some(preimage), None)
+                        (PaymentKind::Keysend, Some(preimage), None)
                     }
                 };
-                log::warn!("please note the payments are not make persistent for the moment");
-                // FIXME: make peristent these information
+                log::info!(target: "lampo::handler", "claimed payment `{payment_hash}` of {amount_msat} msat");
+                let custom_tlvs = onion_fields
+                    .as_ref()
+                    .map(|fields| fields.custom_tlvs().iter().map(CustomTlv::from).collect())
+                    .unwrap_or_else(Vec::new);
+                // Keysend and offer payments have no ledger entry before
+                // they are claimed, so they get one here.
+                let hash = lampo_common::hex::encode(payment_hash.0);
+                let paid_at = ledger::now();
+                let result = ledger::update_invoice(
+                    &self.persister,
+                    &hash,
+                    || InvoiceInfo::new(hash.clone(), kind, paid_at),
+                    |entry| {
+                        entry.offer_id = entry.offer_id.take().or(offer_id);
+                        entry.amount_received_msat = Some(amount_msat);
+                        // A hold invoice claim has no preimage here, it was
+                        // stored when the invoice was settled.
+                        if let Some(preimage) = payment_preimage {
+                            entry.payment_preimage = Some(lampo_common::hex::encode(preimage.0));
+                        }
+                        entry.status = InvoiceStatus::Paid;
+                        entry.paid_at = Some(paid_at);
+                        entry.custom_tlvs = custom_tlvs.clone();
+                    },
+                );
+                if let Err(err) = result {
+                    log::error!(target: "lampo::handler", "storing claimed payment `{hash}` in the ledger: {err}");
+                }
+                self.emit(Event::Lightning(LightningEvent::PaymentReceived {
+                    payment_hash: hash.clone(),
+                    amount_msat,
+                    custom_tlvs,
+                }));
+                if kind == PaymentKind::Refund {
+                    self.emit(Event::Lightning(LightningEvent::RefundEvent {
+                        payment_id: None,
+                        payment_hash: Some(hash),
+                        state: RefundState::Received,
+                        amount_msat: Some(amount_msat),
+                    }));
+                }
                 Ok(())
             }
             ldk::events::Event::SpendableOutputs {
@@ -550,12 +1194,18 @@ impl Handler for LampoHandler {
                     .map_err(|_| {
                         error::anyhow!("failed to persist spendable outputs in the sweeper")
                     })?;
+                if let Some(channel_id) = channel_id {
+                    ledger::record_channel(&self.persister, &channel_id.to_string(), |entry| {
+                        entry.swept = true;
+                    });
+                }
                 Ok(())
             }
             ldk::events::Event::PaymentSent {
                 payment_id,
                 payment_preimage,
                 payment_hash,
+                fee_paid_msat,
                 bolt12_invoice,
                 ..
             } => {
@@ -569,6 +1219,15 @@ impl Handler for LampoHandler {
                     // call that is waiting for it, so there is nothing to emit.
                     return Ok(());
                 };
+                let entry = ledger::record_payment(&self.persister, &payment_id, |entry| {
+                    entry.payment_hash = Some(lampo_common::hex::encode(payment_hash.0));
+                    entry.payment_preimage = Some(lampo_common::hex::encode(payment_preimage.0));
+                    entry.fee_msat = fee_paid_msat;
+                    entry.status = PaymentState::Success;
+                    entry.failure_reason = None;
+                    entry.completed_at = Some(ledger::now());
+                });
+                self.emit_refund_event(entry, RefundState::Paid);
                 let record = PayerProofRecord {
                     preimage: payment_preimage,
                     invoice: bolt12_invoice,
@@ -581,7 +1240,7 @@ impl Handler for LampoHandler {
                     .ldk_keys()
                     .keys_manager
                     .get_expanded_key();
-                let payer_proof = payer_proof::build(&record, &expanded_key, payment_id);
+                let payer_proof = payer_proof::build(&record, &expanded_key, payment_id, &[]);
 
                 self.emit(Event::Lightning(LightningEvent::PaymentReceipt {
                     payment_id: lampo_common::hex::encode(payment_id.0),
@@ -616,6 +1275,14 @@ impl Handler for LampoHandler {
                     .iter()
                     .map(|hop| PaymentHop::from(hop.clone()))
                     .collect::<Vec<PaymentHop>>();
+                ledger::record_payment(&self.persister, &payment_id, |entry| {
+                    entry.attempts.push(PaymentAttempt {
+                        path: path.clone(),
+                        state: PaymentState::Success,
+                        failed_short_channel_id: None,
+                        permanent: false,
+                    });
+                });
                 let hop = LightningEvent::PaymentEvent {
                     state: PaymentState::Success,
                     payment_id: Some(lampo_common::hex::encode(payment_id.0)),
@@ -626,6 +1293,31 @@ impl Handler for LampoHandler {
                 self.emit(Event::Lightning(hop));
                 Ok(())
             }
+            ldk::events::Event::PaymentPathFailed {
+                payment_id,
+                payment_failed_permanently,
+                path,
+                short_channel_id,
+                ..
+            } => {
+                log::debug!(
+                    target: "lampo::handler",
+                    "payment path failed: id `{payment_id:?}`, channel `{short_channel_id:?}`, permanent: {payment_failed_permanently}"
+                );
+                // Without an id there is no ledger entry to attach it to.
+                let Some(payment_id) = payment_id else {
+                    return Ok(());
+                };
+                ledger::record_payment(&self.persister, &payment_id, |entry| {
+                    entry.attempts.push(PaymentAttempt {
+                        path: path.hops.iter().cloned().map(PaymentHop::from).collect(),
+                        state: PaymentState::Failure,
+                        failed_short_channel_id: short_channel_id,
+                        permanent: payment_failed_permanently,
+                    });
+                });
+                Ok(())
+            }
             ldk::events::Event::PaymentFailed {
                 payment_id,
                 payment_hash,
@@ -672,6 +1364,13 @@ impl Handler for LampoHandler {
                     },
                 };
 
+                let entry = ledger::record_payment(&self.persister, &payment_id, |entry| {
+                    entry.status = PaymentState::Failure;
+                    entry.failure_reason = Some(detailed_reason.clone());
+                    entry.completed_at = Some(ledger::now());
+                });
+                self.emit_refund_event(entry, RefundState::Failed);
+
                 let hop = LightningEvent::PaymentEvent {
                     state: PaymentState::Failure,
                     payment_id: Some(lampo_common::hex::encode(payment_id.0)),
@@ -731,6 +1430,92 @@ impl Handler for LampoHandler {
                 self.bump_tx_event_handler.handle_event(&event).await;
                 Ok(())
             }
+            // The background processor feeds both probe events to the
+            // scorer before they get here.
+            ldk::events::Event::ProbeSuccessful {
+                payment_id, path, ..
+            } => {
+                self.emit(Event::Lightning(LightningEvent::ProbeSuccessful {
+                    payment_id: lampo_common::hex::encode(payment_id.0),
+                    path: path.hops.into_iter().map(PaymentHop::from).collect(),
+                }));
+                Ok(())
+            }
+            ldk::events::Event::ProbeFailed {
+                payment_id,
+                path,
+                short_channel_id,
+                ..
+            } => {
+                log::debug!(target: "lampo::handler", "probe `{payment_id:?}` failed at channel `{short_channel_id:?}`");
+                self.emit(Event::Lightning(LightningEvent::ProbeFailed {
+                    payment_id: lampo_common::hex::encode(payment_id.0),
+                    path: path.hops.into_iter().map(PaymentHop::from).collect(),
+                    short_channel_id,
+                }));
+                Ok(())
+            }
+            ldk::events::Event::PaymentForwarded {
+                prev_channel_id,
+                next_channel_id,
+                total_fee_earned_msat,
+                outbound_amount_forwarded_msat,
+                claim_from_onchain_tx,
+                ..
+            } => {
+                let in_msat = outbound_amount_forwarded_msat
+                    .zip(total_fee_earned_msat)
+                    .map(|(out_msat, fee_msat)| out_msat + fee_msat);
+                let forward = ForwardInfo {
+                    forward_id: self.forward_id(),
+                    status: ForwardStatus::Settled,
+                    in_channel: prev_channel_id.map(|id| id.to_string()),
+                    out_channel: next_channel_id.map(|id| id.to_string()),
+                    in_msat,
+                    out_msat: outbound_amount_forwarded_msat,
+                    fee_msat: total_fee_earned_msat,
+                    onchain: claim_from_onchain_tx,
+                    failure_reason: None,
+                    resolved_at: ledger::now(),
+                };
+                log::info!(target: "lampo::handler", "forwarded {:?} msat earning {:?} msat", forward.out_msat, forward.fee_msat);
+                ledger::record_forward(&self.persister, &forward);
+                Ok(())
+            }
+            ldk::events::Event::HTLCHandlingFailed {
+                prev_channel_id,
+                failure_type,
+                failure_reason,
+                ..
+            } => {
+                let out_channel = match &failure_type {
+                    // A payment to us, not a forward.
+                    HTLCHandlingFailureType::Receive { .. } => return Ok(()),
+                    HTLCHandlingFailureType::Forward { channel_id, .. } => {
+                        Some(channel_id.to_string())
+                    }
+                    _ => None,
+                };
+                let failure_reason = match failure_reason {
+                    Some(reason) => format!("{failure_type:?}: {reason:?}"),
+                    None => format!("{failure_type:?}"),
+                };
+                log::info!(target: "lampo::handler", "failed to forward an HTLC from `{prev_channel_id}`: {failure_reason}");
+                let forward = ForwardInfo {
+                    forward_id: self.forward_id(),
+                    status: ForwardStatus::Failed,
+                    in_channel: Some(prev_channel_id.to_string()),
+                    out_channel,
+                    in_msat: None,
+                    out_msat: None,
+                    fee_msat: None,
+                    onchain: false,
+                    failure_reason: Some(failure_reason),
+                    resolved_at: ledger::now(),
+                };
+                ledger::record_forward(&self.persister, &forward);
+                Ok(())
+            }
             _ => {
                 log::warn!(target: "lampo::handler", "unhandled ldk event: {:?}", event);
                 Ok(())
@@ -739,6 +1524,154 @@ impl Handler for LampoHandler {
     }
 }
 
+/// Decide on an inbound channel: the policy first, then the channel
+/// hooks in registration order. Returns whether to accept it as a
+/// zero-conf channel, or why it is rejected. As with the payment hooks,
+/// a hook that errors or times out is skipped.
+async fn inbound_channel_verdict(
+    policy: &ChannelPolicy,
+    channel_hooks: &[Arc<dyn ChannelHook>],
+    channel: &InboundChannel,
+) -> Result<bool, String> {
+    let zero_conf = match policy.evaluate(channel) {
+        ChannelPolicyDecision::Accept { zero_conf } => zero_conf,
+        ChannelPolicyDecision::Reject(reason) => return Err(reason),
+    };
+    for hook in channel_hooks.iter() {
+        let id = &channel.temporary_channel_id;
+        let std::result::Result::Ok(decision) =
+            tokio::time::timeout(hook.timeout(), hook.on_open_channel(channel)).await
+        else {
+            log::warn!(target: "lampo::handler", "channel hook timed out on `{id}`");
+            continue;
+        };
+        match decision {
+            std::result::Result::Ok(ChannelHookDecision::Accept) => break,
+            std::result::Result::Ok(ChannelHookDecision::Reject(reason)) => return Err(reason),
+            std::result::Result::Ok(ChannelHookDecision::Defer) => {}
+            Err(err) => {
+                log::error!(target: "lampo::handler", "channel hook failed on `{id}`: {err}");
+            }
+        }
+    }
+    std::result::Result::Ok(zero_conf)
+}
+
+/// A pessimistic block interval, to turn the blocks left before a claim
+/// deadline into time the payment hooks may take.
+const HOOKS_SECS_PER_BLOCK: u64 = 60;
+
+/// How long the payment hooks may take together before a payment with
+/// `claim_deadline` is decided without them. They must be done before
+/// [`hold_invoice::CANCEL_MARGIN_BLOCKS`] of the deadline, like a parked
+/// hold payment. `None` when the deadline is unknown.
+fn hooks_budget(claim_deadline: Option<u32>, height: u32) -> Option<std::time::Duration> {
+    let blocks_left = claim_deadline?
+        .saturating_sub(height)
+        .saturating_sub(hold_invoice::CANCEL_MARGIN_BLOCKS);
+    Some(std::time::Duration::from_secs(
+        u64::from(blocks_left) * HOOKS_SECS_PER_BLOCK,
+    ))
+}
+
+/// Ask the payment hooks, in registration order, whether `payment` may
+/// be claimed. Returns why it must be failed back, if a hook says so.
+/// A hook that errors or times out is skipped, which leaves the
+/// built-in policy in charge, and so are the hooks left once `budget`
+/// runs out.
+async fn payment_hooks_verdict(
+    payment_hooks: &[Arc<dyn PaymentHook>],
+    payment: &InboundPayment,
+    budget: Option<std::time::Duration>,
+) -> Option<String> {
+    let hash = &payment.payment_hash;
+    let deadline = budget.map(|budget| tokio::time::Instant::now() + budget);
+    for hook in payment_hooks.iter() {
+        let mut timeout = hook.timeout();
+        if let Some(deadline) = deadline {
+            let left = deadline.saturating_duration_since(tokio::time::Instant::now());
+            if left.is_zero() {
+                log::warn!(target: "lampo::handler", "no time left for the payment hooks on `{hash}` before its claim deadline");
+                break;
+            }
+            timeout = timeout.min(left);
+        }
+        let std::result::Result::Ok(decision) =
+            tokio::time::timeout(timeout, hook.on_payment(payment)).await
+        else {
+            log::warn!(target: "lampo::handler", "payment hook timed out on `{hash}`");
+            continue;
+        };
+        match decision {
+            std::result::Result::Ok(PaymentHookDecision::Claim) => return None,
+            std::result::Result::Ok(PaymentHookDecision::Fail(reason)) => return Some(reason),
+            std::result::Result::Ok(PaymentHookDecision::Defer) => {}
+            Err(err) => {
+                log::error!(target: "lampo::handler", "payment hook failed on `{hash}`: {err}");
+            }
+        }
+    }
+    None
+}
+
+/// Claim a `PaymentClaimable` payment, or fail it back to the sender.
+fn settle_payment(
+    channel_manager: &LampoChannelManager,
+    payment_hash: &PaymentHash,
+    amount_msat: u64,
+    counterparty_skimmed_fee_msat: u64,
+    claim_deadline: Option<u32>,
+    decision: PaymentClaimDecision,
+) {
+    match decision {
+        PaymentClaimDecision::Claim(preimage) => {
+            log::info!(
+                target: "lampo::handler",
+                "claiming payment `{payment_hash}` of {amount_msat} msat (claim deadline {claim_deadline:?})"
+            );
+            channel_manager.manager().claim_funds(preimage);
+        }
+        PaymentClaimDecision::FailBack(reason) => {
+            log::error!(
+                target: "lampo::handler",
+                "refusing to claim payment `{payment_hash}` of {amount_msat} msat (counterparty skimmed {counterparty_skimmed_fee_msat} msat, claim deadline {claim_deadline:?}): {reason}; failing the HTLC(s) back to the sender"
+            );
+            channel_manager.manager().fail_htlc_backwards(payment_hash);
+        }
+    }
+}
+
+/// What the payment hooks are shown of a `PaymentClaimable` event.
+fn inbound_payment(
+    payment_hash: &PaymentHash,
+    amount_msat: u64,
+    purpose: &ldk::events::PaymentPurpose,
+    onion_fields: Option<&RecipientOnionFields>,
+    claim_deadline: Option<u32>,
+) -> InboundPayment {
+    let (kind, offer_id) = match purpose {
+        ldk::events::PaymentPurpose::Bolt11InvoicePayment { .. } => (PaymentKind::Bolt11, None),
+        ldk::events::PaymentPurpose::Bolt12OfferPayment {
+            payment_context, ..
+        } => (
+            PaymentKind::Bolt12,
+            Some(lampo_common::hex::encode(payment_context.offer_id.0)),
+        ),
+        ldk::events::PaymentPurpose::Bolt12RefundPayment { .. } => (PaymentKind::Refund, None),
+        ldk::events::PaymentPurpose::SpontaneousPayment(_) => (PaymentKind::Keysend, None),
+    };
+    InboundPayment {
+        payment_hash: lampo_common::hex::encode(payment_hash.0),
+        amount_msat,
+        kind,
+        offer_id,
+        custom_tlvs: onion_fields
+            .map(|fields| fields.custom_tlvs().iter().map(CustomTlv::from).collect())
+            .unwrap_or_default(),
+        claim_deadline,
+    }
+}
+
 /// What to do with a `PaymentClaimable` event.
 #[derive(Debug, PartialEq, Eq)]
 enum PaymentClaimDecision {
@@ -746,7 +1679,7 @@ enum PaymentClaimDecision {
     Claim(PaymentPreimage),
     /// Fail the HTLC(s) back to the sender instead of claiming; the string
     /// explains why the payment was rejected.
-    FailBack(&'static str),
+    FailBack(Cow<'static, str>),
 }
 
 /// Validate a `PaymentClaimable` event *before* releasing the preimage (the
@@ -773,7 +1706,8 @@ fn decide_payment_claim(
     if counterparty_skimmed_fee_msat > 0 {
         return PaymentClaimDecision::FailBack(
             "payment is underpaid: the counterparty skimmed an extra fee, so the \
-             received amount does not cover the invoice",
+             received amount does not cover the invoice"
+                .into(),
         );
     }
     let preimage = match purpose {
@@ -793,7 +1727,7 @@ fn decide_payment_claim(
     match preimage {
         Some(preimage) => PaymentClaimDecision::Claim(preimage.clone()),
         None => PaymentClaimDecision::FailBack(
-            "no preimage available for this payment purpose; cannot claim safely",
+            "no preimage available for this payment purpose; cannot claim safely".into(),
         ),
     }
 }
@@ -863,4 +1797,21 @@ mod payment_claimable_tests {
         );
         assert_eq!(decision, PaymentClaimDecision::Claim(preimage));
     }
+
+    /// The payment hooks stop short of the claim deadline by the margin a
+    /// parked hold payment keeps.
+    #[test]
+    fn hooks_budget_stops_before_the_claim_deadline() {
+        let margin = hold_invoice::CANCEL_MARGIN_BLOCKS;
+        assert_eq!(hooks_budget(None, 100), None);
+        assert_eq!(
+            hooks_budget(Some(100 + margin + 2), 100),
+            Some(std::time::Duration::from_secs(2 * HOOKS_SECS_PER_BLOCK))
+        );
+        assert_eq!(
+            hooks_budget(Some(100 + margin), 100),
+            Some(std::time::Duration::ZERO)
+        );
+        assert_eq!(hooks_budget(Some(90), 100), Some(std::time::Duration::ZERO));
+    }
 }
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use lampo_common::event::ln::LightningEvent;
 use lampo_common::event::Event;
 use lampo_common::handler::Handler;
@@ -6,9 +8,13 @@ use lampo_common::jsonrpc::{Error, RpcError};
 use lampo_common::model::request;
 use lampo_common::model::response;
 
+use crate::ln::ledger;
 use crate::rpc_error;
 use crate::LampoDaemon;
 
+/// How long `close` waits for the channel to close before it gives up.
+const CLOSE_WAIT_TIMEOUT_SECS: u64 = 120;
+
 pub async fn json_channels(ctx: &LampoDaemon, request: &json::Value) -> Result<json::Value, Error> {
     log::info!("call for `list_channels` with request {:?}", request);
     let resp = ctx.channel_manager().list_channels();
@@ -48,43 +54,127 @@ pub async fn json_close(ctx: &LampoDaemon, request: &json::Value) -> Result<json
         // No channels with the given peer.
         return Err(rpc_error!("No channels with associated peer"));
     };
+    let channel_id = res.channel_id()?;
+    let node_id = res.counterpart_node_id()?;
+    let unilateral_timeout = res.unilateral_timeout;
+    let mut forced = res.force;
     ctx.channel_manager().close_channel(res)?;
 
-    // FIXME: would be good to have some sort of macros, because
-    // this is a common patter across lampo
-    let (message, channel_id, node_id, funding_utxo) = loop {
-        let event = events
-            .recv()
-            .await
-            .ok_or(Error::Rpc(RpcError {
-                code: -1,
-                message: format!("No event received"),
-                data: None,
-            }))
-            // FIXME: find a way to map this error
-            .map_err(|err| {
-                Error::Rpc(RpcError {
-                    code: -1,
-                    message: format!("{err}"),
-                    data: None,
-                })
-            })?;
+    // The bus carries the close events of every channel, so only the one
+    // of the channel we are closing completes the request. Past the
+    // unilateral timeout we force close once and keep waiting, the force
+    // close emits its own event right away.
+    let mut wait_secs = unilateral_timeout.unwrap_or(CLOSE_WAIT_TIMEOUT_SECS);
+    let mut deadline = tokio::time::Instant::now() + Duration::from_secs(wait_secs);
+    let (message, node_id, funding_utxo) = loop {
+        let event = match tokio::time::timeout_at(deadline, events.recv()).await {
+            Ok(event) => event.ok_or(rpc_error!("No event received"))?,
+            Err(_) if !forced && unilateral_timeout.is_some() => {
+                log::info!("cooperative close of `{channel_id}` timed out, force closing");
+                ctx.channel_manager().force_close_channel(
+                    &channel_id,
+                    &node_id,
+                    "cooperative close timed out",
+                )?;
+                forced = true;
+                wait_secs = CLOSE_WAIT_TIMEOUT_SECS;
+                deadline = tokio::time::Instant::now() + Duration::from_secs(wait_secs);
+                continue;
+            }
+            Err(_) => {
+                return Err(rpc_error!(
+                    "channel `{channel_id}` is not closed after {wait_secs}s, the close is still pending"
+                ))
+            }
+        };
         if let Event::Lightning(LightningEvent::CloseChannelEvent {
             message,
-            channel_id,
+            channel_id: closed_id,
             counterparty_node_id,
             funding_utxo,
         }) = event
         {
-            break (message, channel_id, counterparty_node_id, funding_utxo);
+            if closed_id == channel_id.to_string() {
+                break (message, counterparty_node_id, funding_utxo);
+            }
         }
     };
 
     // FIXME: wrap this under a struct
     Ok(json::json!({
         "message" : message,
-        "channel_id" : channel_id,
+        "channel_id" : channel_id.to_string(),
         "peer_id" : node_id,
         "funding_utxo" : funding_utxo,
+        "forced" : forced,
     }))
 }
+
+pub async fn json_getchannelpolicy(
+    ctx: &LampoDaemon,
+    request: &json::Value,
+) -> Result<json::Value, Error> {
+    log::info!("call for `getchannelpolicy` with request {:?}", request);
+    let policy: response::ChannelPolicy = ctx.handler().channel_policy();
+    Ok(json::to_value(policy)?)
+}
+
+pub async fn json_setchannelpolicy(
+    ctx: &LampoDaemon,
+    request: &json::Value,
+) -> Result<json::Value, Error> {
+    log::info!("call for `setchannelpolicy` with request {:?}", request);
+    let policy: request::ChannelPolicy = json::from_value(request.clone())?;
+    policy.validate().map_err(|err| rpc_error!("{err}"))?;
+    ctx.handler().set_channel_policy(policy.clone())?;
+    Ok(json::to_value(policy)?)
+}
+
+pub async fn json_setchannelfee(
+    ctx: &LampoDaemon,
+    request: &json::Value,
+) -> Result<json::Value, Error> {
+    log::info!("call for `setchannelfee` with request {:?}", request);
+    let request: request::SetChannelFee = json::from_value(request.clone())?;
+    let configs = ctx
+        .channel_manager()
+        .update_channel_config(&request.into())?;
+    Ok(json::to_value(configs)?)
+}
+
+pub async fn json_setchannelconfig(
+    ctx: &LampoDaemon,
+    request: &json::Value,
+) -> Result<json::Value, Error> {
+    log::info!("call for `setchannelconfig` with request {:?}", request);
+    let request: request::SetChannelConfig = json::from_value(request.clone())?;
+    let configs = ctx.channel_manager().update_channel_config(&request)?;
+    Ok(json::to_value(configs)?)
+}
+
+pub async fn json_splicein(ctx: &LampoDaemon, request: &json::Value) -> Result<json::Value, Error> {
+    log::info!("call for `splicein` with request {:?}", request);
+    let request: request::SpliceIn = json::from_value(request.clone())?;
+    let splice = ctx.channel_manager().splice_in(request).await?;
+    Ok(json::to_value(splice)?)
+}
+
+pub async fn json_spliceout(
+    ctx: &LampoDaemon,
+    request: &json::Value,
+) -> Result<json::Value, Error> {
+    log::info!("call for `spliceout` with request {:?}", request);
+    let request: request::SpliceOut = json::from_value(request.clone())?;
+    let splice = ctx.channel_manager().splice_out(request).await?;
+    Ok(json::to_value(splice)?)
+}
+
+pub async fn json_listclosedchannels(
+    ctx: &LampoDaemon,
+    request: &json::Value,
+) -> Result<json::Value, Error> {
+    log::info!("call for `listclosedchannels` with request {:?}", request);
+    let request: request::ListClosedChannels = json::from_value(request.clone())?;
+    let channels = ledger::list_closed_channels(&ctx.persister(), &request)?;
+    Ok(json::to_value(response::ClosedChannels { channels })?)
+}
@@ -1,10 +1,17 @@
 //! Inventory method implementation
+use std::str::FromStr;
+
+use lampo_common::bitcoin::secp256k1::PublicKey;
 use lampo_common::error;
 use lampo_common::json;
 use lampo_common::jsonrpc::Error;
+use lampo_common::ldk::routing::gossip::NodeId;
+use lampo_common::ldk::sign::NodeSigner;
+use lampo_common::ldk::util::message_signing;
 use lampo_common::model::request::NetworkInfo;
 use lampo_common::model::response::{NetworkChannel, NetworkChannels};
 use lampo_common::model::GetInfo;
+use lampo_common::model::{request, response};
 
 use crate::{async_run, LampoDaemon};
 
@@ -84,3 +91,48 @@ pub async fn json_networkchannels(
         channels: network_channels,
     })?)
 }
+
+pub async fn json_signmessage(
+    ctx: &LampoDaemon,
+    request: &json::Value,
+) -> Result<json::Value, Error> {
+    log::info!("calling `signmessage` with request `{:?}`", request);
+    let request: request::SignMessage = json::from_value(request.clone())?;
+    let zbase = ctx
+        .wallet_manager()
+        .ldk_keys()
+        .keys_manager
+        .sign_message(request.message.as_bytes())
+        .map_err(|_| error::anyhow!("signing the message failed"))?;
+    Ok(json::to_value(response::SignMessage { zbase })?)
+}
+
+pub async fn json_checkmessage(
+    ctx: &LampoDaemon,
+    request: &json::Value,
+) -> Result<json::Value, Error> {
+    log::info!("calling `checkmessage` with request `{:?}`", request);
+    let request: request::CheckMessage = json::from_value(request.clone())?;
+    let message = request.message.as_bytes();
+    let recovered = message_signing::recover_pk(message, &request.zbase)
+        .map_err(|err| error::anyhow!("invalid signature: {err}"))?;
+    let verified = match &request.pubkey {
+        Some(pubkey) => {
+            let pubkey = PublicKey::from_str(pubkey)?;
+            message_signing::verify(message, &request.zbase, &pubkey)
+        }
+        None => {
+            let our_id = ctx.channel_manager().manager().get_our_node_id();
+            let graph = ctx.channel_manager().graph();
+            let known = graph
+                .read_only()
+                .node(&NodeId::from_pubkey(&recovered))
+                .is_some();
+            recovered == our_id || known
+        }
+    };
+    Ok(json::to_value(response::CheckMessage {
+        pubkey: request.pubkey.unwrap_or_else(|| recovered.to_string()),
+        verified,
+    })?)
+}
@@ -6,82 +6,160 @@ use lampo_common::event::ln::LightningEvent;
 use lampo_common::event::Event;
 use lampo_common::handler::Handler;
 use lampo_common::hex;
+use lampo_common::hex::FromHex;
 use lampo_common::jsonrpc::{Error, RpcError};
 use lampo_common::ldk;
+use lampo_common::ldk::offers::invoice::Bolt12Invoice;
+use lampo_common::ldk::offers::invoice_request::InvoiceRequest;
 use lampo_common::ldk::offers::offer;
+use lampo_common::ldk::offers::payer_proof::PayerProof;
+use lampo_common::ldk::types::payment::{PaymentHash, PaymentPreimage};
 use lampo_common::model::request::GenerateInvoice;
 use lampo_common::model::request::GenerateOffer;
 use lampo_common::model::request::KeySend;
 use lampo_common::model::request::Pay;
+use lampo_common::model::request::{self, ListForwards};
+use lampo_common::model::request::{CancelHoldInvoice, GenerateHoldInvoice, SettleHoldInvoice};
+use lampo_common::model::request::{DisableOffer, ListInvoices, ListOffers, ListPays};
+use lampo_common::model::request::{GenerateRefund, RequestRefundPayment};
+use lampo_common::model::request::{GetPayerProof, VerifyPayerProof};
+use lampo_common::model::request::{GetRoute, Probe, ProbeInvoice};
+use lampo_common::model::request::{PayStatus, WaitPay};
 use lampo_common::model::response::PayResult;
 use lampo_common::model::response::{self, Decode};
 use lampo_common::model::response::{Bolt11InvoiceInfo, Bolt12InvoiceInfo, Invoice};
+use lampo_common::model::response::{Bolt12PaymentInvoiceInfo, InvoiceRequestInfo, PayerProofInfo};
+use lampo_common::model::response::{Bolt12RefundInfo, RefundInvoice, RefundState};
+use lampo_common::model::response::{Forwards, Invoices, Offers, Pays};
+use lampo_common::model::response::{PaymentInfo, PaymentState};
+use lampo_common::model::response::{ProbePath, ProbeResult, Route};
 use lampo_common::{json, model::request::DecodeInvoice};
 use tokio::time::Instant;
 
+use crate::ln::{ledger, payer_proof};
 use crate::LampoDaemon;
 
 pub async fn json_invoice(ctx: &LampoDaemon, request: &json::Value) -> Result<json::Value, Error> {
     log::info!("call for `invoice` with request `{:?}`", request);
     let request: GenerateInvoice = json::from_value(request.clone())?;
-    let invoice = ctx.offchain_manager().generate_invoice(
+    let invoice = ctx.offchain_manager().generate_invoice(&request)?;
+    let invoice = Invoice {
+        bolt11: invoice.to_string(),
+    };
+    Ok(json::to_value(&invoice)?)
+}
+
+pub async fn json_holdinvoice(
+    ctx: &LampoDaemon,
+    request: &json::Value,
+) -> Result<json::Value, Error> {
+    log::info!("call for `holdinvoice` with request `{:?}`", request);
+    let request: GenerateHoldInvoice = json::from_value(request.clone())?;
+    let payment_hash = <[u8; 32]>::from_hex(&request.payment_hash)
+        .map_err(|err| crate::rpc_error!("invalid payment hash: {err}"))?;
+    let payment_hash = PaymentHash(payment_hash);
+    let invoice = ctx.offchain_manager().hold_invoice(
+        payment_hash,
         request.amount_msat,
         &request.description,
         request.expiring_in.unwrap_or(10000),
     )?;
-    let invoice = Invoice {
-        bolt11: invoice.to_string(),
-    };
+    Ok(json::to_value(&invoice)?)
+}
+
+pub async fn json_holdinvoice_settle(
+    ctx: &LampoDaemon,
+    request: &json::Value,
+) -> Result<json::Value, Error> {
+    log::info!("call for `holdinvoice_settle` with request `{:?}`", request);
+    let request: SettleHoldInvoice = json::from_value(request.clone())?;
+    let preimage = <[u8; 32]>::from_hex(&request.payment_preimage)
+        .map_err(|err| crate::rpc_error!("invalid payment preimage: {err}"))?;
+    let preimage = PaymentPreimage(preimage);
+    let invoice = ctx.offchain_manager().settle_hold_invoice(preimage)?;
+    Ok(json::to_value(&invoice)?)
+}
+
+pub async fn json_holdinvoice_cancel(
+    ctx: &LampoDaemon,
+    request: &json::Value,
+) -> Result<json::Value, Error> {
+    log::info!("call for `holdinvoice_cancel` with request `{:?}`", request);
+    let request: CancelHoldInvoice = json::from_value(request.clone())?;
+    let payment_hash = <[u8; 32]>::from_hex(&request.payment_hash)
+        .map_err(|err| crate::rpc_error!("invalid payment hash: {err}"))?;
+    let payment_hash = PaymentHash(payment_hash);
+    let invoice = ctx.offchain_manager().cancel_hold_invoice(payment_hash)?;
     Ok(json::to_value(&invoice)?)
 }
 
 pub async fn json_offer(ctx: &LampoDaemon, request: &json::Value) -> Result<json::Value, Error> {
     log::info!("call for `offer` with request `{:?}`", request);
     let request: GenerateOffer = json::from_value(request.clone())?;
-    let manager = ctx.channel_manager().manager();
-    let mut offer_builder = manager
-        .create_offer_builder()
-        .map_err(|err| crate::rpc_error!("{:?}", err))?;
-
-    if let Some(description) = request.description {
-        offer_builder = offer_builder.description(description);
-    }
-
-    if let Some(amount_msat) = request.amount_msat {
-        offer_builder = offer_builder.amount_msats(amount_msat);
-    }
-
-    let offer: response::Offer = offer_builder
-        .build()
-        // FIXME: implement display error on top of the bolt12 error
-        .map_err(|err| crate::rpc_error!("{:?}", err))?
-        .into();
+    let offer = ctx.offchain_manager().generate_offer(&request)?;
+    let offer: response::Offer = offer.into();
     log::debug!("Generated offer: {:?}", offer);
     Ok(json::to_value(&offer)?)
 }
 
+pub async fn json_refund(ctx: &LampoDaemon, request: &json::Value) -> Result<json::Value, Error> {
+    log::info!("call for `refund` with request `{:?}`", request);
+    let request: GenerateRefund = json::from_value(request.clone())?;
+    let expiring_in = request.expiring_in.unwrap_or(24 * 60 * 60);
+    let (payment_id, refund) = ctx.offchain_manager().create_refund(
+        request.amount_msat,
+        request.description,
+        request.payer_note,
+        expiring_in,
+    )?;
+    let refund = response::Refund {
+        bolt12: refund.to_string(),
+        payment_id: hex::encode(payment_id.0),
+        expires_at: refund
+            .absolute_expiry()
+            .map(|expiry| expiry.as_secs())
+            .unwrap_or_default(),
+    };
+    Ok(json::to_value(&refund)?)
+}
+
+pub async fn json_requestrefundpayment(
+    ctx: &LampoDaemon,
+    request: &json::Value,
+) -> Result<json::Value, Error> {
+    log::info!(
+        "call for `requestrefundpayment` with request `{:?}`",
+        request
+    );
+    let request: RequestRefundPayment = json::from_value(request.clone())?;
+    let invoice = ctx
+        .offchain_manager()
+        .request_refund_payment(&request.refund)?;
+    let invoice = RefundInvoice {
+        payment_hash: hex::encode(invoice.payment_hash().0),
+        amount_msat: invoice.amount_msats(),
+        expires_at: (invoice.created_at() + invoice.relative_expiry()).as_secs(),
+    };
+    ctx.handler()
+        .emit(Event::Lightning(LightningEvent::RefundEvent {
+            payment_id: None,
+            payment_hash: Some(invoice.payment_hash.clone()),
+            state: RefundState::InvoiceSent,
+            amount_msat: Some(invoice.amount_msat),
+        }));
+    Ok(json::to_value(&invoice)?)
+}
+
 pub async fn json_decode(ctx: &LampoDaemon, request: &json::Value) -> Result<json::Value, Error> {
-    log::info!("call for `invoice` with request `{:?}`", request);
+    log::info!("call for `decode` with request `{:?}`", request);
     let request: DecodeInvoice = json::from_value(request.clone())?;
+    // Decoded from the raw invoice, so a bad signature is reported instead
+    // of refused.
     if let Ok(invoice) = ctx
         .offchain_manager()
-        .decode::<ldk::invoice::Bolt11Invoice>(&request.invoice_str)
+        .decode::<ldk::invoice::SignedRawBolt11Invoice>(&request.invoice_str)
     {
-        let bolt11_invoice = Bolt11InvoiceInfo {
-            issuer_id: invoice.payee_pub_key().map(|id| id.to_string()),
-            amount_msat: invoice.amount_milli_satoshis(),
-            network: invoice.network().to_string(),
-            description: match invoice.description() {
-                ldk::invoice::Bolt11InvoiceDescriptionRef::Direct(dec) => Some(dec.to_string()),
-                ldk::invoice::Bolt11InvoiceDescriptionRef::Hash(_) => {
-                    Some("description hash provided".to_string())
-                }
-            },
-            routes: Vec::new(),
-            hints: Vec::new(),
-            expiry_time: Some(invoice.expiry_time().as_millis() as u64),
-        };
-
+        let bolt11_invoice = Bolt11InvoiceInfo::from(&invoice);
         return Ok(json::to_value(&Decode::from(bolt11_invoice))?);
     }
 
@@ -91,9 +169,42 @@ pub async fn json_decode(ctx: &LampoDaemon, request: &json::Value) -> Result<jso
     {
         let bolt12_invoice: Bolt12InvoiceInfo = offer.into();
         return Ok(json::to_value(&Decode::from(bolt12_invoice))?);
-    } else {
-        Err(crate::rpc_error!("Not able to decode invoice"))
     }
+
+    if let Ok(refund) = ctx
+        .offchain_manager()
+        .decode::<ldk::offers::refund::Refund>(&request.invoice_str)
+    {
+        let refund: Bolt12RefundInfo = refund.into();
+        return Ok(json::to_value(&Decode::from(refund))?);
+    }
+
+    // Refunds and invoice requests share the `lnr` prefix, so the request
+    // is only tried once the string is known not to be a refund.
+    if let Ok(invoice_request) = ctx
+        .offchain_manager()
+        .decode_bolt12::<InvoiceRequest>(&request.invoice_str, "lnr")
+    {
+        let invoice_request: InvoiceRequestInfo = invoice_request.into();
+        return Ok(json::to_value(&Decode::from(invoice_request))?);
+    }
+
+    if let Ok(invoice) = ctx
+        .offchain_manager()
+        .decode_bolt12::<Bolt12Invoice>(&request.invoice_str, "lni")
+    {
+        let invoice: Bolt12PaymentInvoiceInfo = invoice.into();
+        return Ok(json::to_value(&Decode::from(invoice))?);
+    }
+
+    if let Ok(proof) = ctx
+        .offchain_manager()
+        .decode::<PayerProof>(&request.invoice_str)
+    {
+        let proof: PayerProofInfo = proof.into();
+        return Ok(json::to_value(&Decode::from(proof))?);
+    }
+    Err(crate::rpc_error!("Not able to decode invoice"))
 }
 
 pub async fn json_pay(ctx: &LampoDaemon, request: &json::Value) -> Result<json::Value, Error> {
@@ -104,21 +215,71 @@ pub async fn json_pay(ctx: &LampoDaemon, request: &json::Value) -> Result<json::
     let payment_id = if let Ok(_) = offer::Offer::from_str(&request.invoice_str) {
         log::debug!("Paying offer with bolt12 invoice: {}", request.invoice_str);
         let payer_note = request.bolt12.and_then(|x| x.payer_note);
-        ctx.offchain_manager()
-            .pay_offer(&request.invoice_str, request.amount, payer_note)?
+        ctx.offchain_manager().pay_offer(
+            &request.invoice_str,
+            request.amount,
+            payer_note,
+            &request.limits,
+        )?
     } else {
         log::debug!(
             "Paying invoice with bolt11 invoice: {}",
             request.invoice_str
         );
         ctx.offchain_manager()
-            .pay_invoice(&request.invoice_str, request.amount)?
+            .pay_invoice(&request.invoice_str, request.amount, &request.limits)?
     };
     // The event bus broadcasts to every subscriber, so a concurrent `pay` would
     // otherwise see this payment's result -- and now its preimage and payer
     // proof too. Only accept events carrying our own payment id.
     let payment_id = hex::encode(payment_id.0);
-    wait_for_payment_result(events, &payment_id, request.timeout.duration()).await
+    if request.r#async {
+        return Ok(json::to_value(sent_result(ctx, &payment_id))?);
+    }
+    let result = wait_for_payment_result(events, &payment_id, request.timeout.duration()).await?;
+    Ok(json::to_value(result)?)
+}
+
+/// The response of an `async` payment: whatever the ledger knows right
+/// after sending, which is usually still `Pending`.
+fn sent_result(ctx: &LampoDaemon, payment_id: &str) -> PayResult {
+    match ctx.offchain_manager().payment_status(payment_id) {
+        Ok(entry) => ledger_result(entry),
+        Err(err) => {
+            // The payment is out either way, only the details are missing.
+            log::warn!(target: "lampod::jsonrpc::offchain", "no ledger entry for payment `{payment_id}`: {err}");
+            PayResult {
+                payment_id: payment_id.to_owned(),
+                path: vec![],
+                payment_hash: None,
+                state: PaymentState::Pending,
+                payment_preimage: None,
+                payer_proof: None,
+                custom_tlvs: vec![],
+            }
+        }
+    }
+}
+
+/// Build the `pay` response out of a ledger entry. The path is the first
+/// one that succeeded. The payer proof is only handed out on the event
+/// bus, so it is missing here.
+fn ledger_result(entry: PaymentInfo) -> PayResult {
+    let path = entry
+        .attempts
+        .into_iter()
+        .find(|attempt| attempt.state == PaymentState::Success)
+        .map(|attempt| attempt.path)
+        .unwrap_or_default();
+    PayResult {
+        payment_id: entry.payment_id,
+        path,
+        payment_hash: entry.payment_hash,
+        state: entry.status,
+        payment_preimage: entry.payment_preimage,
+        payer_proof: None,
+        custom_tlvs: entry.custom_tlvs,
+    }
 }
 
 /// Hold the `PaymentReceipt` (preimage, payer proof) until the terminal
@@ -130,7 +291,7 @@ async fn wait_for_payment_result(
     mut events: lampo_common::chan::UnboundedReceiver<Event>,
     payment_id: &str,
     timeout: Duration,
-) -> Result<json::Value, Error> {
+) -> Result<PayResult, Error> {
     // Single deadline for the whole RPC wait. The event bus is broadcast, so
     // unrelated events must not reset the timer — only the terminal
     // `PaymentEvent` for `payment_id` completes the call (success or failure).
@@ -183,13 +344,15 @@ async fn wait_for_payment_result(
                     Some((preimage, proof)) => (Some(preimage), proof),
                     None => (None, None),
                 };
-                return Ok(json::to_value(PayResult {
+                return Ok(PayResult {
+                    payment_id: payment_id.to_owned(),
                     state,
                     path,
                     payment_hash,
                     payment_preimage,
                     payer_proof,
-                })?);
+                    custom_tlvs: vec![],
+                });
             }
             _ => {}
         }
@@ -201,11 +364,236 @@ pub async fn json_keysend(ctx: &LampoDaemon, request: &json::Value) -> Result<js
     let request: KeySend = json::from_value(request.clone())?;
     let destination = request.destination()?;
     let mut events = ctx.handler().events();
-    let payment_id = ctx
-        .offchain_manager()
-        .keysend(destination, request.amount_msat)?;
+    let payment_id = ctx.offchain_manager().keysend(
+        destination,
+        request.amount_msat,
+        request.custom_tlvs()?,
+        request.mpp,
+        &request.limits,
+    )?;
     // Same id semantics as `pay`: the hex payment hash identifies the
     // payment on the event bus.
     let payment_id = hex::encode(payment_id.0);
-    wait_for_payment_result(events, &payment_id, request.timeout.duration()).await
+    if request.r#async {
+        return Ok(json::to_value(sent_result(ctx, &payment_id))?);
+    }
+    let mut result =
+        wait_for_payment_result(events, &payment_id, request.timeout.duration()).await?;
+    result.custom_tlvs = request.custom_tlvs;
+    Ok(json::to_value(result)?)
+}
+
+pub async fn json_probe(ctx: &LampoDaemon, request: &json::Value) -> Result<json::Value, Error> {
+    log::info!("call for `probe` with request `{:?}`", request);
+    let request: Probe = json::from_value(request.clone())?;
+    let destination = request.destination()?;
+    let events = ctx.handler().events();
+    let probes = ctx
+        .offchain_manager()
+        .probe(destination, request.amount_msat)?;
+    let probes = probes.iter().map(|id| hex::encode(id.0)).collect();
+    let result = wait_for_probe_results(events, probes, request.timeout.duration()).await?;
+    Ok(json::to_value(result)?)
+}
+
+pub async fn json_probeinvoice(
+    ctx: &LampoDaemon,
+    request: &json::Value,
+) -> Result<json::Value, Error> {
+    log::info!("call for `probeinvoice` with request `{:?}`", request);
+    let request: ProbeInvoice = json::from_value(request.clone())?;
+    let events = ctx.handler().events();
+    let probes = ctx
+        .offchain_manager()
+        .probe_invoice(&request.invoice_str, request.amount_msat)?;
+    let probes = probes.iter().map(|id| hex::encode(id.0)).collect();
+    let result = wait_for_probe_results(events, probes, request.timeout.duration()).await?;
+    Ok(json::to_value(result)?)
+}
+
+pub async fn json_getroute(ctx: &LampoDaemon, request: &json::Value) -> Result<json::Value, Error> {
+    log::info!("call for `getroute` with request `{:?}`", request);
+    let request: GetRoute = json::from_value(request.clone())?;
+    let route = ctx.offchain_manager().find_route(&request)?;
+    Ok(json::to_value(Route::from(route))?)
+}
+
+/// Collect the outcome of every probe in `probes`. Unlike a payment, a
+/// probe that does not come back in time is not an error: it is reported
+/// as `Pending` next to the ones that did.
+async fn wait_for_probe_results(
+    mut events: lampo_common::chan::UnboundedReceiver<Event>,
+    probes: Vec<String>,
+    timeout: Duration,
+) -> Result<ProbeResult, Error> {
+    let deadline = Instant::now() + timeout;
+    let mut results: Vec<Option<ProbePath>> = vec![None; probes.len()];
+    while results.iter().any(Option::is_none) {
+        let Ok(event) = tokio::time::timeout_at(deadline, events.recv()).await else {
+            break;
+        };
+        let Some(event) = event else {
+            return Err(crate::rpc_error!(
+                "No event received, communication channel dropped"
+            ));
+        };
+        let (payment_id, path, state, short_channel_id) = match event {
+            Event::Lightning(LightningEvent::ProbeSuccessful { payment_id, path }) => {
+                (payment_id, path, PaymentState::Success, None)
+            }
+            Event::Lightning(LightningEvent::ProbeFailed {
+                payment_id,
+                path,
+                short_channel_id,
+            }) => (payment_id, path, PaymentState::Failure, short_channel_id),
+            _ => continue,
+        };
+        let Some(index) = probes.iter().position(|id| *id == payment_id) else {
+            continue;
+        };
+        // A hop names the channel used to reach it, so the node that
+        // failed to forward over `scid` is the hop before it.
+        let failing_node_id = short_channel_id.and_then(|scid| {
+            let index = path.iter().position(|hop| hop.short_channel_id == scid)?;
+            index.checked_sub(1).map(|prev| path[prev].node_id.clone())
+        });
+        results[index] = Some(ProbePath {
+            payment_id,
+            path,
+            state,
+            failed_short_channel_id: short_channel_id,
+            failing_node_id,
+        });
+    }
+    let probes = probes
+        .into_iter()
+        .zip(results)
+        .map(|(payment_id, result)| {
+            result.unwrap_or(ProbePath {
+                payment_id,
+                path: Vec::new(),
+                state: PaymentState::Pending,
+                failed_short_channel_id: None,
+                failing_node_id: None,
+            })
+        })
+        .collect();
+    Ok(ProbeResult { probes })
+}
+
+pub async fn json_paystatus(
+    ctx: &LampoDaemon,
+    request: &json::Value,
+) -> Result<json::Value, Error> {
+    log::info!("call for `paystatus` with request `{:?}`", request);
+    let request: PayStatus = json::from_value(request.clone())?;
+    let status = ctx.offchain_manager().payment_status(&request.payment_id)?;
+    Ok(json::to_value(status)?)
+}
+
+pub async fn json_waitpay(ctx: &LampoDaemon, request: &json::Value) -> Result<json::Value, Error> {
+    log::info!("call for `waitpay` with request `{:?}`", request);
+    let request: WaitPay = json::from_value(request.clone())?;
+    // Subscribe before reading the ledger. The handler writes the ledger
+    // before it emits, so the outcome is either in the entry or still
+    // ahead of us on the bus.
+    let events = ctx.handler().events();
+    let entry = ctx.offchain_manager().payment_status(&request.payment_id)?;
+    // `completed_at` is only set from the LDK event, unlike `status`, which
+    // may be ahead of the ledger and missing the preimage.
+    if entry.completed_at.is_some() {
+        return Ok(json::to_value(ledger_result(entry))?);
+    }
+    let result =
+        wait_for_payment_result(events, &request.payment_id, request.timeout.duration()).await?;
+    Ok(json::to_value(result)?)
+}
+
+pub async fn json_listpays(ctx: &LampoDaemon, request: &json::Value) -> Result<json::Value, Error> {
+    log::info!("call for `listpays` with request `{:?}`", request);
+    let request: ListPays = json::from_value(request.clone())?;
+    let pays = ledger::list_payments(&ctx.persister(), &request)?;
+    Ok(json::to_value(Pays { pays })?)
+}
+
+pub async fn json_listinvoices(
+    ctx: &LampoDaemon,
+    request: &json::Value,
+) -> Result<json::Value, Error> {
+    log::info!("call for `listinvoices` with request `{:?}`", request);
+    let request: ListInvoices = json::from_value(request.clone())?;
+    let invoices = ledger::list_invoices(&ctx.persister(), &request)?;
+    Ok(json::to_value(Invoices { invoices })?)
+}
+
+pub async fn json_listoffers(
+    ctx: &LampoDaemon,
+    request: &json::Value,
+) -> Result<json::Value, Error> {
+    log::info!("call for `listoffers` with request `{:?}`", request);
+    let request: ListOffers = json::from_value(request.clone())?;
+    let offers = ledger::list_offers(&ctx.persister(), &request)?;
+    Ok(json::to_value(Offers { offers })?)
+}
+
+pub async fn json_disableoffer(
+    ctx: &LampoDaemon,
+    request: &json::Value,
+) -> Result<json::Value, Error> {
+    log::info!("call for `disableoffer` with request `{:?}`", request);
+    let request: DisableOffer = json::from_value(request.clone())?;
+    let offer = ledger::disable_offer(&ctx.persister(), &request.offer_id)?;
+    Ok(json::to_value(offer)?)
+}
+
+pub async fn json_getpayerproof(
+    ctx: &LampoDaemon,
+    request: &json::Value,
+) -> Result<json::Value, Error> {
+    log::info!("call for `getpayerproof` with request `{:?}`", request);
+    let request: GetPayerProof = json::from_value(request.clone())?;
+    let payer_proof = ctx.offchain_manager().payer_proof(&request)?;
+    Ok(json::to_value(response::PayerProof {
+        payment_hash: request.payment_hash,
+        payer_proof,
+    })?)
+}
+
+pub async fn json_verifypayerproof(
+    _ctx: &LampoDaemon,
+    request: &json::Value,
+) -> Result<json::Value, Error> {
+    log::info!("call for `verifypayerproof` with request `{:?}`", request);
+    let request: VerifyPayerProof = json::from_value(request.clone())?;
+    let verification = payer_proof::verify(
+        &request.payer_proof,
+        &request.offer,
+        request.payment_hash.as_deref(),
+    )?;
+    Ok(json::to_value(verification)?)
+}
+
+pub async fn json_listforwards(
+    ctx: &LampoDaemon,
+    request: &json::Value,
+) -> Result<json::Value, Error> {
+    log::info!("call for `listforwards` with request `{:?}`", request);
+    let request: ListForwards = json::from_value(request.clone())?;
+    let forwards = ledger::list_forwards(&ctx.persister(), &request)?;
+    Ok(json::to_value(Forwards { forwards })?)
+}
+
+pub async fn json_forwardstats(
+    ctx: &LampoDaemon,
+    request: &json::Value,
+) -> Result<json::Value, Error> {
+    log::info!("call for `forwardstats` with request `{:?}`", request);
+    let request: request::ForwardStats = json::from_value(request.clone())?;
+    let filter = ListForwards {
+        since: request.since,
+        until: request.until,
+        ..Default::default()
+    };
+    let forwards = ledger::list_forwards(&ctx.persister(), &filter)?;
+    Ok(json::to_value(response::ForwardStats::new(&forwards))?)
 }
@@ -1,6 +1,8 @@
 //! On Chain RPC methods
 use lampo_common::json;
 use lampo_common::jsonrpc::Error;
+use lampo_common::model::request;
+use lampo_common::model::response::{self, Balances};
 
 use crate::LampoDaemon;
 
@@ -18,6 +20,35 @@ pub async fn json_funds(ctx: &LampoDaemon, request: &json::Value) -> Result<json
     }))
 }
 
+pub async fn json_balances(ctx: &LampoDaemon, request: &json::Value) -> Result<json::Value, Error> {
+    log::info!("call for `balances` with request `{:?}`", request);
+    let wallet = ctx.wallet_manager().get_wallet_balance().await?;
+    let channel_manager = ctx.channel_manager();
+    let balances = Balances::new(
+        wallet,
+        channel_manager.claimable_balances(),
+        channel_manager.pending_sweeps(),
+    );
+    Ok(json::to_value(balances)?)
+}
+
+pub async fn json_listsweeps(
+    ctx: &LampoDaemon,
+    request: &json::Value,
+) -> Result<json::Value, Error> {
+    log::info!("call for `listsweeps` with request `{:?}`", request);
+    let request: request::ListSweeps = json::from_value(request.clone())?;
+    let sweeps = ctx.channel_manager().list_sweeps(&request);
+    Ok(json::to_value(response::Sweeps { sweeps })?)
+}
+
+pub async fn json_sweep(ctx: &LampoDaemon, request: &json::Value) -> Result<json::Value, Error> {
+    log::info!("call for `sweep` with request `{:?}`", request);
+    let request: request::Sweep = json::from_value(request.clone())?;
+    let sweep = ctx.channel_manager().sweep_outputs(request).await?;
+    Ok(json::to_value(sweep)?)
+}
+
 pub async fn json_estimate_fees(
     ctx: &LampoDaemon,
     request: &json::Value,
@@ -37,3 +37,66 @@ pub async fn json_fundchannel(
     let resp = ctx.channel_manager().open_channel(request).await?;
     Ok(json::to_value(resp)?)
 }
+
+pub async fn json_multifundchannel(
+    ctx: &LampoDaemon,
+    request: &json::Value,
+) -> Result<json::Value, Error> {
+    log::info!("call for `multifundchannel` with request {:?}", request);
+    let request: request::MultiFundChannel = json::from_value(request.clone())?;
+    for open_channel in request.channels.iter() {
+        let node_id = open_channel
+            .node_id()
+            .map_err(|err| crate::rpc_error!("invalid `node_id` provided: {err}"))?;
+        if !ctx.peer_manager().is_connected_with(node_id) {
+            log::trace!(
+                "we are not connected with the peer {}",
+                open_channel.node_id
+            );
+            let conn = request::Connect::try_from(open_channel.clone())?;
+            let conn = json::to_value(conn)?;
+            ctx.call("connect", conn).await?;
+        }
+    }
+    let resp = ctx.channel_manager().multi_open_channel(request).await?;
+    Ok(json::to_value(resp)?)
+}
+
+pub async fn json_fundchannel_start(
+    ctx: &LampoDaemon,
+    request: &json::Value,
+) -> Result<json::Value, Error> {
+    log::info!("call for `fundchannel_start` with request {:?}", request);
+    let request: request::OpenChannel = json::from_value(request.clone())?;
+    let node_id = request
+        .node_id()
+        .map_err(|err| crate::rpc_error!("invalid `node_id` provided: {err}"))?;
+    if !ctx.peer_manager().is_connected_with(node_id) {
+        log::trace!("we are not connected with the peer {}", request.node_id);
+        let conn = request::Connect::try_from(request.clone())?;
+        let conn = json::to_value(conn)?;
+        ctx.call("connect", conn).await?;
+    }
+    let resp = ctx.channel_manager().fund_channel_start(request).await?;
+    Ok(json::to_value(resp)?)
+}
+
+pub async fn json_fundchannel_complete(
+    ctx: &LampoDaemon,
+    request: &json::Value,
+) -> Result<json::Value, Error> {
+    log::info!("call for `fundchannel_complete` with request {:?}", request);
+    let request: request::FundChannelComplete = json::from_value(request.clone())?;
+    let resp = ctx.channel_manager().fund_channel_complete(request).await?;
+    Ok(json::to_value(resp)?)
+}
+
+pub async fn json_fundchannel_cancel(
+    ctx: &LampoDaemon,
+    request: &json::Value,
+) -> Result<json::Value, Error> {
+    log::info!("call for `fundchannel_cancel` with request {:?}", request);
+    let request: request::FundChannelCancel = json::from_value(request.clone())?;
+    let resp = ctx.channel_manager().fund_channel_cancel(request)?;
+    Ok(json::to_value(resp)?)
+}
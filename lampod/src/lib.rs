@@ -20,14 +20,17 @@ pub mod persistence;
 
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 
 use tokio::task::JoinHandle;
 
 use lampo_common::backend::Backend;
 use lampo_common::chainsync::ChainSyncCoordinator;
 use lampo_common::conf::LampoConf;
-use lampo_common::handler::ExternalHandler;
+use lampo_common::event::ln::LightningEvent;
+use lampo_common::event::Event as LampoEvent;
+use lampo_common::handler::Handler as EventHandler;
+use lampo_common::handler::{ChannelHook, ExternalHandler, PaymentHook};
 use lampo_common::json;
 use lampo_common::ldk::events::{Event, ReplayEvent};
 use lampo_common::ldk::io;
@@ -40,6 +43,7 @@ use lampo_common::{error, ldk};
 use crate::actions::handler::LampoHandler;
 use crate::actions::Handler;
 use crate::chain::LampoChainManager;
+use crate::ln::ledger;
 use crate::ln::OffchainManager;
 use crate::ln::{LampoChannelManager, LampoInventoryManager, LampoPeerManager};
 use crate::persistence::LampoPersistence;
@@ -175,6 +179,7 @@ impl LampoDaemon {
             self.logger.clone(),
             self.conf.clone(),
             self.onchain_manager(),
+            self.persister(),
         )?;
         self.offchain_manager = Some(Arc::new(manager));
         Ok(())
@@ -237,6 +242,7 @@ impl LampoDaemon {
         log::debug!(target: "lampod", "init lampod ...");
         self.init_onchaind(client.clone())?;
         self.init_channeld().await?;
+        ledger::build_indexes(&self.persister)?;
         self.init_offchain_manager()?;
         self.init_peer_manager()?;
         self.init_inventory_manager()?;
@@ -271,6 +277,26 @@ impl LampoDaemon {
         Ok(())
     }
 
+    /// Registers a hook consulted before an inbound payment is claimed,
+    /// see [`PaymentHook`].
+    pub async fn add_payment_hook(&self, hook: Arc<dyn PaymentHook>) -> error::Result<()> {
+        let Some(ref handler) = self.handler else {
+            error::bail!("Initial handler is None");
+        };
+        handler.add_payment_hook(hook).await?;
+        Ok(())
+    }
+
+    /// Registers a hook consulted before an inbound channel is accepted,
+    /// see [`ChannelHook`].
+    pub async fn add_channel_hook(&self, hook: Arc<dyn ChannelHook>) -> error::Result<()> {
+        let Some(ref handler) = self.handler else {
+            error::bail!("Initial handler is None");
+        };
+        handler.add_channel_hook(hook).await?;
+        Ok(())
+    }
+
     pub fn listen(self: Arc<Self>) -> JoinHandle<Result<(), io::Error>> {
         log::info!(target: "lampod", "Starting lightning node version `{}`", env!("CARGO_PKG_VERSION"));
         let gossip_sync: Arc<P2PGossipSync> = Arc::new(ldk::routing::gossip::P2PGossipSync::new(
@@ -300,6 +326,7 @@ impl LampoDaemon {
         }
         log::info!(target: "lampo", "Starting channel manager");
         let _ = self.channel_manager().listen();
+        self.watch_hold_invoices();
 
         tokio::spawn(async move {
             process_events_async(
@@ -350,6 +377,40 @@ impl LampoDaemon {
         })
     }
 
+    /// Fail back parked hold invoice payments a few blocks before their
+    /// claim deadline, so a caller that never settles or cancels one
+    /// cannot get the channel force closed.
+    fn watch_hold_invoices(&self) {
+        let offchain_manager = self.offchain_manager();
+        let handler = self.handler();
+        let shutdown = self.shutdown.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(30));
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+            loop {
+                interval.tick().await;
+                if shutdown.load(Ordering::Acquire) {
+                    break;
+                }
+                let cancelled = match offchain_manager.cancel_due_hold_invoices() {
+                    Ok(cancelled) => cancelled,
+                    Err(err) => {
+                        log::error!(target: "lampod", "checking hold invoices deadlines: {err}");
+                        continue;
+                    }
+                };
+                for invoice in cancelled {
+                    handler.emit(LampoEvent::Lightning(LightningEvent::HoldInvoiceEvent {
+                        payment_hash: invoice.payment_hash,
+                        state: invoice.state,
+                        amount_msat: invoice.amount_received_msat,
+                        claim_deadline: invoice.claim_deadline,
+                    }));
+                }
+            }
+        });
+    }
+
     // Only `SpendableOutputs` is replayed on failure: dropping it would
     // lose the descriptors needed to claim closed-channel funds on-chain.
     // Other handlers can fail permanently (peer disconnected, wallet
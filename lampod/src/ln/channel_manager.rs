@@ -3,11 +3,15 @@ use std::collections::HashMap;
 use std::fs::File;
 use std::io::BufReader;
 use std::path::Path;
+use std::str::FromStr;
 use std::sync::{Arc, Mutex, OnceLock};
 use std::time::{Duration, SystemTime};
 
 use lampo_common::backend::Backend;
-use lampo_common::bitcoin::{BlockHash, Transaction};
+use lampo_common::bitcoin::absolute::Height;
+use lampo_common::bitcoin::consensus::encode::serialize_hex;
+use lampo_common::bitcoin::secp256k1::{PublicKey, Secp256k1};
+use lampo_common::bitcoin::{Address, Amount, BlockHash, OutPoint, ScriptBuf, Transaction, TxOut};
 use lampo_common::conf::LampoConf;
 use lampo_common::error;
 use lampo_common::event::ln::LightningEvent;
@@ -17,26 +21,34 @@ use lampo_common::handler::Handler;
 use lampo_common::json::de;
 use lampo_common::keys::LampoKeysManager;
 use lampo_common::ldk::block_sync::BlockSource;
-use lampo_common::ldk::chain::chaininterface::{BroadcasterInterface, FeeEstimator};
+use lampo_common::ldk::chain::chaininterface::{
+    BroadcasterInterface, ConfirmationTarget, FeeEstimator,
+};
 use lampo_common::ldk::chain::chainmonitor::ChainMonitor;
-use lampo_common::ldk::chain::channelmonitor::ChannelMonitor;
+use lampo_common::ldk::chain::channelmonitor::{Balance, ChannelMonitor};
 use lampo_common::ldk::chain::{BlockLocator, Watch};
+use lampo_common::ldk::ln::channel_state::ChannelDetails;
 use lampo_common::ldk::ln::channelmanager::{ChainParameters, ChannelManagerReadArgs};
+use lampo_common::ldk::ln::funding::{FundingTxInput, SpliceContribution};
+use lampo_common::ldk::ln::script::ShutdownScript;
 use lampo_common::ldk::onion_message::messenger::DefaultMessageRouter;
 use lampo_common::ldk::routing::gossip::NetworkGraph;
 use lampo_common::ldk::routing::router::DefaultRouter;
 use lampo_common::ldk::routing::scoring::{
     ProbabilisticScorer, ProbabilisticScoringDecayParameters, ProbabilisticScoringFeeParameters,
 };
-use lampo_common::ldk::sign::{InMemorySigner, NodeSigner};
+use lampo_common::ldk::sign::{
+    EntropySource, InMemorySigner, NodeSigner, OutputSpender, SpendableOutputDescriptor,
+};
+use lampo_common::ldk::util::config::ChannelConfigUpdate;
 use lampo_common::ldk::util::persist::{
     read_channel_monitors, KVStoreSync, OUTPUT_SWEEPER_PERSISTENCE_KEY,
     OUTPUT_SWEEPER_PERSISTENCE_PRIMARY_NAMESPACE, OUTPUT_SWEEPER_PERSISTENCE_SECONDARY_NAMESPACE,
 };
 use lampo_common::ldk::util::ser::ReadableArgs;
-use lampo_common::ldk::util::sweep::OutputSweeper;
+use lampo_common::ldk::util::sweep::{OutputSpendStatus, OutputSweeper, TrackedSpendableOutput};
 use lampo_common::model::request;
-use lampo_common::model::response::{self, Channel, Channels};
+use lampo_common::model::response::{self, Channel, Channels, ClaimableKind};
 use lampo_common::types::LampoChannel;
 use lampo_common::types::LampoGraph;
 use lampo_common::types::LampoRouter;
@@ -46,7 +58,7 @@ use lampo_common::types::{ChannelId, LampoArcChannelManager, LampoChainMonitor};
 
 use crate::actions::handler::LampoHandler;
 use crate::async_run;
-use crate::chain::{LampoChainManager, WalletManager};
+use crate::chain::{FeeTarget, LampoChainManager, WalletManager};
 use crate::persistence::LampoPersistence;
 use crate::utils::logger::LampoLogger;
 
@@ -56,17 +68,35 @@ use crate::utils::logger::LampoLogger;
 /// returns even if the peer stalls in a state that emits no terminal event.
 const FUNDING_WAIT_TIMEOUT_SECS: u64 = 120;
 
+/// Weight of what a splice initiator pays for besides its own inputs: the
+/// common transaction fields, the shared funding input with its 2-of-2
+/// witness, the new funding output and our change.
+const SPLICE_BASE_WEIGHT: u64 = 4 * (10 + 41 + 43 + 31) + 222;
+/// Weight of a P2WPKH input, witness included.
+const P2WPKH_INPUT_WEIGHT: u64 = 272;
+
 /// Coordinates the `open_channel` waiter with `FundingGenerationReady` so a
 /// timeout cannot force-close a channel whose funding LDK already accepted
 /// (and vice versa: the producer must not hand off after the waiter abandoned).
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 enum FundingWaitState {
     /// Waiter timed out; producer must not call `funding_transaction_generated`.
     Abandoned,
     /// Producer handed funding to LDK; waiter must not force-close.
     Accepted,
+    /// Opened by `fundchannel_start`: an external wallet pays
+    /// `output_script` and hands the transaction to `fundchannel_complete`.
+    External {
+        counterparty_node_id: PublicKey,
+        output_script: ScriptBuf,
+        amount_sat: u64,
+    },
 }
 
+/// Set in the `user_channel_id` of channels funded by an external wallet,
+/// so the event handler leaves their funding to `fundchannel_complete`.
+pub(crate) const EXTERNAL_FUNDING_TAG: u128 = 1 << 127;
+
 pub struct LampoChannelManager {
     monitor: OnceLock<Arc<LampoChainMonitor>>,
     wallet_manager: Arc<dyn WalletManager>,
@@ -77,6 +107,14 @@ pub struct LampoChannelManager {
     router: OnceLock<Arc<LampoRouter>>,
     /// Shared with the event handler; see [`FundingWaitState`].
     funding_wait_state: Mutex<HashMap<ChannelId, FundingWaitState>>,
+    /// New funding output of splices signed but not locked yet.
+    pending_splices: Mutex<HashMap<ChannelId, OutPoint>>,
+    /// Splices we started that did not fail or lock in yet, with the
+    /// wallet coins each one locked. Splice outs lock none.
+    splicing: Mutex<HashMap<ChannelId, Vec<OutPoint>>>,
+    /// Funding scripts of the channels of each `multifundchannel` batch,
+    /// keyed by the `user_channel_id` the batch opened them with.
+    batch_fundings: Mutex<HashMap<u128, HashMap<ChannelId, ScriptBuf>>>,
     /// Restored (or freshly created) output sweeper, paired with the best
     /// block its persisted state was last synced to so the chain backend can
     /// catch it up independently of the channel manager.
@@ -109,6 +147,9 @@ impl LampoChannelManager {
             score: OnceLock::new(),
             router: OnceLock::new(),
             funding_wait_state: Mutex::new(HashMap::new()),
+            pending_splices: Mutex::new(HashMap::new()),
+            splicing: Mutex::new(HashMap::new()),
+            batch_fundings: Mutex::new(HashMap::new()),
             sweeper: OnceLock::new(),
         }
     }
@@ -160,6 +201,28 @@ impl LampoChannelManager {
         true
     }
 
+    /// Park the funding of an externally funded channel until
+    /// `fundchannel_complete` provides it.
+    pub(crate) fn external_funding_ready(
+        &self,
+        temporary_channel_id: ChannelId,
+        counterparty_node_id: PublicKey,
+        output_script: ScriptBuf,
+        amount_sat: u64,
+    ) {
+        self.funding_wait_state
+            .lock()
+            .expect("funding wait state poisoned")
+            .insert(
+                temporary_channel_id,
+                FundingWaitState::External {
+                    counterparty_node_id,
+                    output_script,
+                    amount_sat,
+                },
+            );
+    }
+
     pub(crate) fn clear_funding_wait(&self, temporary_channel_id: &ChannelId) {
         self.funding_wait_state
             .lock()
@@ -167,6 +230,98 @@ impl LampoChannelManager {
             .remove(temporary_channel_id);
     }
 
+    /// Forget a channel closed while waiting for `fundchannel_complete`.
+    pub(crate) fn external_funding_closed(&self, temporary_channel_id: &ChannelId) {
+        let mut state = self
+            .funding_wait_state
+            .lock()
+            .expect("funding wait state poisoned");
+        if matches!(
+            state.get(temporary_channel_id),
+            Some(FundingWaitState::External { .. })
+        ) {
+            state.remove(temporary_channel_id);
+        }
+    }
+
+    /// Hand the funding script of a batched channel to its batch. Returns
+    /// `false` when the channel is not part of a batch, so the caller funds
+    /// it on its own.
+    pub(crate) fn batch_funding_ready(
+        &self,
+        user_channel_id: u128,
+        temporary_channel_id: ChannelId,
+        output_script: ScriptBuf,
+    ) -> bool {
+        let mut batches = self.batch_fundings.lock().expect("batch fundings poisoned");
+        let Some(batch) = batches.get_mut(&user_channel_id) else {
+            return false;
+        };
+        batch.insert(temporary_channel_id, output_script);
+        true
+    }
+
+    pub(crate) fn splice_pending(&self, channel_id: ChannelId, new_funding_txo: OutPoint) {
+        self.pending_splices
+            .lock()
+            .expect("pending splices poisoned")
+            .insert(channel_id, new_funding_txo);
+    }
+
+    /// Whether a splice of `channel_id` is under way.
+    pub(crate) fn is_splicing(&self, channel_id: &ChannelId) -> bool {
+        self.splicing
+            .lock()
+            .expect("splicing poisoned")
+            .contains_key(channel_id)
+            || self
+                .pending_splices
+                .lock()
+                .expect("pending splices poisoned")
+                .contains_key(channel_id)
+    }
+
+    /// Forget the splice of `channel_id`, if any, and give its coins back
+    /// to the wallet. Also used when the channel closes mid-splice.
+    pub(crate) fn splice_failed(&self, channel_id: &ChannelId) {
+        self.pending_splices
+            .lock()
+            .expect("pending splices poisoned")
+            .remove(channel_id);
+        self.unlock_splice_utxos(channel_id);
+    }
+
+    /// The channel became ready on `funding_txo`: when that is the output
+    /// of its pending splice, the splice is over.
+    pub(crate) fn splice_locked(&self, channel_id: &ChannelId, funding_txo: Option<OutPoint>) {
+        let locked = {
+            let mut pending_splices = self
+                .pending_splices
+                .lock()
+                .expect("pending splices poisoned");
+            let locked =
+                funding_txo.is_some_and(|txo| pending_splices.get(channel_id) == Some(&txo));
+            if locked {
+                pending_splices.remove(channel_id);
+            }
+            locked
+        };
+        if locked {
+            self.unlock_splice_utxos(channel_id);
+        }
+    }
+
+    fn unlock_splice_utxos(&self, channel_id: &ChannelId) {
+        let outpoints = self
+            .splicing
+            .lock()
+            .expect("splicing poisoned")
+            .remove(channel_id);
+        if let Some(outpoints) = outpoints {
+            self.wallet_manager.unlock_utxos(&outpoints);
+        }
+    }
+
     pub fn set_handler(&self, handler: Arc<LampoHandler>) {
         self.handler
             .set(handler)
@@ -314,11 +469,21 @@ impl LampoChannelManager {
     }
 
     pub fn list_channels(&self) -> Channels {
+        let pending_splices = self
+            .pending_splices
+            .lock()
+            .expect("pending splices poisoned");
         let channels: Vec<Channel> = self
             .manager()
             .list_channels()
             .into_iter()
             .map(|channel| Channel {
+                // A splice is no longer pending once the channel moved to
+                // its funding output, even before `splice_locked` ran.
+                pending_splice_txid: pending_splices
+                    .get(&channel.channel_id)
+                    .filter(|txo| channel.funding_txo.map(|f| f.txid) != Some(txo.txid))
+                    .map(|txo| txo.txid.to_string()),
                 channel_id: channel.channel_id.to_string(),
                 short_channel_id: channel.short_channel_id,
                 peer_id: channel.counterparty.node_id.to_string(),
@@ -329,11 +494,326 @@ impl LampoChannelManager {
                 public: channel.is_announced,
                 available_balance_for_send_msat: channel.outbound_capacity_msat,
                 available_balance_for_recv_msat: channel.inbound_capacity_msat,
+                fee_base_msat: channel.config.map(|config| config.forwarding_fee_base_msat),
+                fee_proportional_millionths: channel
+                    .config
+                    .map(|config| config.forwarding_fee_proportional_millionths),
+                cltv_expiry_delta: channel.config.map(|config| config.cltv_expiry_delta),
+                htlc_minimum_msat: channel.inbound_htlc_minimum_msat,
+                htlc_maximum_msat: channel.inbound_htlc_maximum_msat,
             })
             .collect();
         Channels { channels }
     }
 
+    /// What every channel monitor can still pay us, closed channels
+    /// included until their outputs reach the sweeper.
+    pub fn claimable_balances(&self) -> Vec<response::ClaimableBalance> {
+        let chain_monitor = self.chain_monitor();
+        let mut balances = Vec::new();
+        for channel_id in chain_monitor.list_monitors() {
+            let Ok(monitor) = chain_monitor.get_monitor(channel_id) else {
+                continue;
+            };
+            let peer_id = monitor.get_counterparty_node_id().to_string();
+            for balance in monitor.get_claimable_balances() {
+                let (kind, height) = match balance {
+                    Balance::ClaimableOnChannelClose { .. } => (ClaimableKind::OnChannel, None),
+                    Balance::ClaimableAwaitingConfirmations {
+                        confirmation_height,
+                        ..
+                    } => (
+                        ClaimableKind::AwaitingConfirmations,
+                        Some(confirmation_height),
+                    ),
+                    Balance::ContentiousClaimable { timeout_height, .. } => {
+                        (ClaimableKind::Contentious, Some(timeout_height))
+                    }
+                    Balance::MaybeTimeoutClaimableHTLC {
+                        claimable_height, ..
+                    } => (ClaimableKind::MaybeTimeout, Some(claimable_height)),
+                    Balance::MaybePreimageClaimableHTLC { expiry_height, .. } => {
+                        (ClaimableKind::MaybePreimage, Some(expiry_height))
+                    }
+                    Balance::CounterpartyRevokedOutputClaimable { .. } => {
+                        (ClaimableKind::CounterpartyRevoked, None)
+                    }
+                };
+                balances.push(response::ClaimableBalance {
+                    channel_id: channel_id.to_string(),
+                    peer_id: peer_id.clone(),
+                    kind,
+                    amount_sat: balance.claimable_amount_satoshis(),
+                    height,
+                });
+            }
+        }
+        balances
+    }
+
+    /// Outputs the sweeper tracks until their sweep is buried deep enough.
+    pub fn list_sweeps(&self, filter: &request::ListSweeps) -> Vec<response::TrackedSweep> {
+        let current_feerate_estimate_sat_per_kw = self
+            .onchain
+            .estimate_fee_rate(FeeTarget::Lightning(ConfirmationTarget::OutputSpendingFee))
+            .to_sat_per_kwu() as u32;
+        self.sweeper()
+            .tracked_spendable_outputs()
+            .into_iter()
+            .map(|tracked| {
+                let (descriptor, outpoint, output) = match &tracked.descriptor {
+                    SpendableOutputDescriptor::StaticOutput {
+                        outpoint, output, ..
+                    } => (response::SweepDescriptor::StaticOutput, outpoint, output),
+                    SpendableOutputDescriptor::DelayedPaymentOutput(descriptor) => (
+                        response::SweepDescriptor::DelayedPaymentOutput,
+                        &descriptor.outpoint,
+                        &descriptor.output,
+                    ),
+                    SpendableOutputDescriptor::StaticPaymentOutput(descriptor) => (
+                        response::SweepDescriptor::StaticPaymentOutput,
+                        &descriptor.outpoint,
+                        &descriptor.output,
+                    ),
+                };
+                let mut sweep = response::TrackedSweep {
+                    outpoint: outpoint.to_string(),
+                    descriptor,
+                    channel_id: tracked.channel_id.map(|id| id.to_string()),
+                    amount_sat: output.value.to_sat(),
+                    status: response::SweepStatus::Pending,
+                    delayed_until_height: None,
+                    broadcast_height: None,
+                    confirmation_height: None,
+                    spending_txid: None,
+                    current_feerate_estimate_sat_per_kw,
+                };
+                match &tracked.status {
+                    OutputSpendStatus::PendingInitialBroadcast {
+                        delayed_until_height,
+                    } => {
+                        sweep.delayed_until_height = *delayed_until_height;
+                    }
+                    OutputSpendStatus::PendingFirstConfirmation {
+                        latest_broadcast_height,
+                        latest_spending_tx,
+                        ..
+                    } => {
+                        sweep.status = response::SweepStatus::Broadcast;
+                        sweep.broadcast_height = Some(*latest_broadcast_height);
+                        sweep.spending_txid = Some(latest_spending_tx.compute_txid().to_string());
+                    }
+                    OutputSpendStatus::PendingThresholdConfirmations {
+                        latest_broadcast_height,
+                        latest_spending_tx,
+                        confirmation_height,
+                        ..
+                    } => {
+                        sweep.status = response::SweepStatus::Confirmed;
+                        sweep.broadcast_height = Some(*latest_broadcast_height);
+                        sweep.confirmation_height = Some(*confirmation_height);
+                        sweep.spending_txid = Some(latest_spending_tx.compute_txid().to_string());
+                    }
+                }
+                sweep
+            })
+            .filter(|sweep| filter.channel_id.is_none() || sweep.channel_id == filter.channel_id)
+            .collect()
+    }
+
+    /// [`Self::list_sweeps`] reduced to what `balances` adds up.
+    pub fn pending_sweeps(&self) -> Vec<response::PendingSweep> {
+        self.list_sweeps(&request::ListSweeps::default())
+            .into_iter()
+            .map(|sweep| response::PendingSweep {
+                channel_id: sweep.channel_id,
+                amount_sat: sweep.amount_sat,
+                spending_txid: sweep.spending_txid,
+                confirmed: sweep.status == response::SweepStatus::Confirmed,
+            })
+            .collect()
+    }
+
+    /// Spend tracked outputs to an address of our choice and broadcast the
+    /// transaction. Only outputs the sweeper has not broadcast a spend of
+    /// yet can be swept, a second spend would just race the first. The
+    /// sweeper keeps tracking the outputs, and may still replace the spend
+    /// with its own to the wallet while it is unconfirmed.
+    pub async fn sweep_outputs(
+        &self,
+        sweep: request::Sweep,
+    ) -> error::Result<response::SweepTransaction> {
+        let address = Address::from_str(&sweep.address)?.require_network(self.conf.network)?;
+        let tracked = self.sweeper().tracked_spendable_outputs();
+        let unspent = |output: &&TrackedSpendableOutput| {
+            matches!(
+                output.status,
+                OutputSpendStatus::PendingInitialBroadcast { .. }
+            )
+        };
+        for outpoint in &sweep.outpoints {
+            let Some(output) = tracked
+                .iter()
+                .find(|output| output.descriptor.outpoint().to_string() == *outpoint)
+            else {
+                error::bail!("output `{outpoint}` is not tracked by the sweeper");
+            };
+            if !unspent(&output) {
+                error::bail!("output `{outpoint}` already has a spend in flight");
+            }
+        }
+        let descriptors = tracked
+            .iter()
+            .filter(unspent)
+            .filter(|output| {
+                sweep.outpoints.is_empty()
+                    || sweep
+                        .outpoints
+                        .contains(&output.descriptor.outpoint().to_string())
+            })
+            .map(|output| &output.descriptor)
+            .collect::<Vec<_>>();
+        if descriptors.is_empty() {
+            error::bail!("no output is waiting for its first spend");
+        }
+        let outpoints = descriptors
+            .iter()
+            .map(|descriptor| descriptor.outpoint().to_string())
+            .collect();
+        let feerate = sweep.feerate_sat_per_kw.unwrap_or_else(|| {
+            self.onchain
+                .estimate_fee_rate(FeeTarget::Lightning(ConfirmationTarget::OutputSpendingFee))
+                .to_sat_per_kwu() as u32
+        });
+        let tx = self
+            .wallet_manager
+            .ldk_keys()
+            .keys_manager
+            .spend_spendable_outputs(
+                &descriptors,
+                Vec::new(),
+                address.script_pubkey(),
+                feerate,
+                None,
+                &Secp256k1::new(),
+            )
+            .map_err(|_| error::anyhow!("the outputs cannot pay a sweep at {feerate} sat/kw"))?;
+
+        let txid = tx.compute_txid();
+        let mut events = self.handler().events();
+        self.onchain.brodcast_tx(&tx).await;
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(FUNDING_WAIT_TIMEOUT_SECS);
+        loop {
+            let event = match tokio::time::timeout_at(deadline, events.recv()).await {
+                Ok(Some(event)) => event,
+                Ok(None) => error::bail!("sweep `{txid}`: no event received"),
+                Err(_) => {
+                    error::bail!("sweep `{txid}` not broadcast after {FUNDING_WAIT_TIMEOUT_SECS}s")
+                }
+            };
+            match event {
+                Event::OnChain(OnChainEvent::SendRawTransaction(sent))
+                    if sent.compute_txid() == txid =>
+                {
+                    break;
+                }
+                Event::OnChain(OnChainEvent::FundingChannelFailed {
+                    txid: Some(failed),
+                    reason,
+                    ..
+                }) if failed == txid => {
+                    error::bail!("sweep `{txid}` was not broadcast: {reason}");
+                }
+                _ => {}
+            }
+        }
+        Ok(response::SweepTransaction {
+            txid: txid.to_string(),
+            tx: serialize_hex(&tx),
+            outpoints,
+        })
+    }
+
+    /// Apply a partial forwarding policy to the selected channels, and
+    /// return their policy after the change.
+    pub fn update_channel_config(
+        &self,
+        request: &request::SetChannelConfig,
+    ) -> error::Result<response::ChannelConfigs> {
+        if request.is_empty() {
+            error::bail!("nothing to change, set at least one config field");
+        }
+        let manager = self.manager();
+        let selected = manager
+            .list_channels()
+            .into_iter()
+            .filter(|channel| {
+                request.matches(
+                    &channel.channel_id.to_string(),
+                    &channel.counterparty.node_id.to_string(),
+                )
+            })
+            .collect::<Vec<_>>();
+        if let Some(unknown) = request
+            .channel_ids
+            .iter()
+            .find(|id| !selected.iter().any(|c| c.channel_id.to_string() == **id))
+        {
+            error::bail!("channel `{unknown}` not found");
+        }
+        if selected.is_empty() {
+            error::bail!("no channel matches the request");
+        }
+
+        let update = ChannelConfigUpdate {
+            forwarding_fee_base_msat: request.fee_base_msat,
+            forwarding_fee_proportional_millionths: request.fee_proportional_millionths,
+            cltv_expiry_delta: request.cltv_expiry_delta,
+            force_close_avoidance_max_fee_satoshis: request.force_close_avoidance_max_fee_sat,
+            accept_underpaying_htlcs: request.accept_underpaying_htlcs,
+            ..Default::default()
+        };
+        // LDK updates the channels of one counterparty at a time.
+        let mut by_peer = HashMap::<_, Vec<ChannelId>>::new();
+        for channel in selected.iter() {
+            by_peer
+                .entry(channel.counterparty.node_id)
+                .or_default()
+                .push(channel.channel_id);
+        }
+        for (peer_id, channel_ids) in by_peer.iter() {
+            manager
+                .update_partial_channel_config(peer_id, channel_ids, &update)
+                .map_err(|err| error::anyhow!("{:?}", err))?;
+        }
+
+        let channels = manager
+            .list_channels()
+            .into_iter()
+            .filter(|channel| {
+                selected
+                    .iter()
+                    .any(|selected| selected.channel_id == channel.channel_id)
+            })
+            .filter_map(|channel| {
+                let config = channel.config?;
+                Some(response::ChannelConfig {
+                    channel_id: channel.channel_id.to_string(),
+                    peer_id: channel.counterparty.node_id.to_string(),
+                    fee_base_msat: config.forwarding_fee_base_msat,
+                    fee_proportional_millionths: config.forwarding_fee_proportional_millionths,
+                    cltv_expiry_delta: config.cltv_expiry_delta,
+                    force_close_avoidance_max_fee_sat: config
+                        .force_close_avoidance_max_fee_satoshis,
+                    accept_underpaying_htlcs: config.accept_underpaying_htlcs,
+                    htlc_minimum_msat: channel.inbound_htlc_minimum_msat,
+                    htlc_maximum_msat: channel.inbound_htlc_maximum_msat,
+                })
+            })
+            .collect();
+        Ok(response::ChannelConfigs { channels })
+    }
+
     pub fn get_channel_monitors(&self) -> error::Result<Vec<ChannelMonitor<InMemorySigner>>> {
         let keys = self.wallet_manager.ldk_keys().inner();
         let mut monitors = read_channel_monitors(self.persister.clone(), keys.clone(), keys)?;
@@ -611,12 +1091,646 @@ impl LampoChannelManager {
         })
     }
 
+    /// First step of an externally funded open: negotiate the channel and
+    /// return the output the external wallet has to pay.
+    pub async fn fund_channel_start(
+        &self,
+        open_channel: request::OpenChannel,
+    ) -> error::Result<response::FundChannelStart> {
+        let mut config = self.conf.ldk_conf.clone();
+        config.channel_handshake_config.announce_for_forwarding = open_channel.public;
+        let peer_id = open_channel.node_id()?;
+        let mut events = self.handler().events();
+        let temp_channel_id = self
+            .manager()
+            .create_channel(
+                peer_id,
+                open_channel.amount,
+                open_channel.push_msat.unwrap_or(0),
+                EXTERNAL_FUNDING_TAG,
+                None,
+                Some(config),
+            )
+            .map_err(|err| error::anyhow!("{:?}", err))?;
+
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(FUNDING_WAIT_TIMEOUT_SECS);
+        loop {
+            let event = match tokio::time::timeout_at(deadline, events.recv()).await {
+                Ok(Some(event)) => event,
+                Ok(None) => error::bail!("Channel funding: no event received"),
+                Err(_) => {
+                    let reason = format!("no funding output after {FUNDING_WAIT_TIMEOUT_SECS}s");
+                    let _ = self.force_close_channel(&temp_channel_id, &peer_id, &reason);
+                    self.clear_funding_wait(&temp_channel_id);
+                    error::bail!("channel with `{peer_id}` {reason}");
+                }
+            };
+            match event {
+                Event::Lightning(LightningEvent::FundingChannelStart {
+                    temporary_channel_id,
+                    ..
+                }) if temporary_channel_id == temp_channel_id => break,
+                Event::Lightning(LightningEvent::CloseChannelEvent {
+                    channel_id,
+                    message,
+                    ..
+                }) if channel_id == temp_channel_id.to_string() => {
+                    error::bail!("channel closed before funding: {message}")
+                }
+                _ => {}
+            }
+        }
+
+        let state = self
+            .funding_wait_state
+            .lock()
+            .expect("funding wait state poisoned")
+            .get(&temp_channel_id)
+            .cloned();
+        let Some(FundingWaitState::External {
+            output_script,
+            amount_sat,
+            ..
+        }) = state
+        else {
+            error::bail!("channel `{temp_channel_id}` is not waiting for external funding");
+        };
+        let funding_address = Address::from_script(&output_script, self.conf.network)?;
+        Ok(response::FundChannelStart {
+            temporary_channel_id: temp_channel_id.to_string(),
+            node_id: peer_id.to_string(),
+            funding_address: funding_address.to_string(),
+            scriptpubkey: output_script.to_hex_string(),
+            amount: amount_sat,
+        })
+    }
+
+    /// Second step of an externally funded open: hand the signed funding
+    /// transaction to LDK, which broadcasts it once the peer signed our
+    /// first commitment.
+    pub async fn fund_channel_complete(
+        &self,
+        complete: request::FundChannelComplete,
+    ) -> error::Result<response::FundChannelComplete> {
+        let transaction = complete.transaction()?;
+        let temp_channel_id = complete.temporary_channel_id()?;
+        let mut events = self.handler().events();
+        let funding_outnum = {
+            let mut state = self
+                .funding_wait_state
+                .lock()
+                .expect("funding wait state poisoned");
+            let Some(FundingWaitState::External {
+                counterparty_node_id,
+                output_script,
+                amount_sat,
+            }) = state.get(&temp_channel_id).cloned()
+            else {
+                error::bail!("channel `{temp_channel_id}` is not waiting for external funding");
+            };
+            let Some(funding_outnum) = transaction.output.iter().position(|output| {
+                output.script_pubkey == output_script && output.value.to_sat() == amount_sat
+            }) else {
+                error::bail!(
+                    "transaction `{}` does not pay {amount_sat} sat to the channel",
+                    transaction.compute_txid()
+                );
+            };
+            self.manager()
+                .funding_transaction_generated(
+                    temp_channel_id,
+                    counterparty_node_id,
+                    transaction.clone(),
+                )
+                .map_err(|err| error::anyhow!("{:?}", err))?;
+            state.insert(temp_channel_id, FundingWaitState::Accepted);
+            funding_outnum as u32
+        };
+
+        let txid = transaction.compute_txid();
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(FUNDING_WAIT_TIMEOUT_SECS);
+        let result = loop {
+            let event = match tokio::time::timeout_at(deadline, events.recv()).await {
+                Ok(Some(event)) => event,
+                Ok(None) => break Err(error::anyhow!("Channel funding: no event received")),
+                Err(_) => {
+                    break Err(error::anyhow!(
+                        "funding `{txid}` handed to LDK, the peer did not sign after {FUNDING_WAIT_TIMEOUT_SECS}s"
+                    ))
+                }
+            };
+            match event {
+                Event::Lightning(LightningEvent::ChannelPending {
+                    funding_transaction,
+                    ..
+                }) if funding_transaction.txid == txid => break Ok(()),
+                // The channel no longer goes by its temporary id, but by
+                // its output of the funding transaction.
+                Event::Lightning(LightningEvent::CloseChannelEvent {
+                    message,
+                    funding_utxo,
+                    ..
+                }) if funding_outpoint(funding_utxo.as_deref())
+                    == Some(OutPoint::new(txid, funding_outnum)) =>
+                {
+                    break Err(error::anyhow!("channel closed before funding: {message}"))
+                }
+                _ => {}
+            }
+        };
+        self.clear_funding_wait(&temp_channel_id);
+        result?;
+        Ok(response::FundChannelComplete {
+            temporary_channel_id: temp_channel_id.to_string(),
+            txid: txid.to_string(),
+            funding_outnum,
+        })
+    }
+
+    /// Drop a channel opened by `fundchannel_start` that was not funded.
+    pub fn fund_channel_cancel(
+        &self,
+        cancel: request::FundChannelCancel,
+    ) -> error::Result<response::FundChannelCancel> {
+        let temp_channel_id = cancel.temporary_channel_id()?;
+        let mut state = self
+            .funding_wait_state
+            .lock()
+            .expect("funding wait state poisoned");
+        let Some(FundingWaitState::External {
+            counterparty_node_id,
+            ..
+        }) = state.get(&temp_channel_id).cloned()
+        else {
+            error::bail!("channel `{temp_channel_id}` is not waiting for external funding");
+        };
+        self.force_close_channel(
+            &temp_channel_id,
+            &counterparty_node_id,
+            "channel funding cancelled",
+        )?;
+        state.remove(&temp_channel_id);
+        Ok(response::FundChannelCancel {
+            temporary_channel_id: temp_channel_id.to_string(),
+            node_id: counterparty_node_id.to_string(),
+        })
+    }
+
+    /// Open a channel to every peer of `request` and fund them all with a
+    /// single wallet transaction. If one channel fails before the funding
+    /// is handed to LDK, the others are dropped too.
+    pub async fn multi_open_channel(
+        &self,
+        request: request::MultiFundChannel,
+    ) -> error::Result<response::MultiFundChannel> {
+        if request.channels.is_empty() {
+            error::bail!("no channel to open");
+        }
+        let mut peers = Vec::new();
+        for open_channel in request.channels.iter() {
+            let peer_id = open_channel.node_id()?;
+            // LDK refuses a batch with two channels to the same peer.
+            if peers.contains(&peer_id) {
+                error::bail!("more than one channel to `{peer_id}` in the batch");
+            }
+            peers.push(peer_id);
+        }
+        let mut results = request
+            .channels
+            .iter()
+            .map(|open_channel| response::BatchChannel {
+                node_id: open_channel.node_id.clone(),
+                amount: open_channel.amount,
+                funding_outnum: None,
+                error: None,
+            })
+            .collect::<Vec<_>>();
+
+        // The batch is registered before the first `create_channel`, so no
+        // `FundingGenerationReady` of ours can be funded on its own.
+        let bytes = self
+            .wallet_manager
+            .ldk_keys()
+            .keys_manager
+            .get_secure_random_bytes();
+        let batch_id = (u128::from_be_bytes(bytes[..16].try_into()?) | 1) & !EXTERNAL_FUNDING_TAG;
+        self.batch_fundings
+            .lock()
+            .expect("batch fundings poisoned")
+            .insert(batch_id, HashMap::new());
+        let mut events = self.handler().events();
+
+        let mut opened = Vec::new();
+        for (index, (open_channel, peer_id)) in request.channels.iter().zip(peers).enumerate() {
+            let mut config = self.conf.ldk_conf.clone();
+            config.channel_handshake_config.announce_for_forwarding = open_channel.public;
+            match self.manager().create_channel(
+                peer_id,
+                open_channel.amount,
+                open_channel.push_msat.unwrap_or(0),
+                batch_id,
+                None,
+                Some(config),
+            ) {
+                Ok(temp_channel_id) => opened.push((temp_channel_id, peer_id)),
+                Err(err) => {
+                    results[index].error = Some(format!("{err:?}"));
+                    return Ok(self.abort_batch(batch_id, &opened, results));
+                }
+            }
+        }
+
+        // Wait for the funding script of every channel.
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(FUNDING_WAIT_TIMEOUT_SECS);
+        let scripts = loop {
+            if let Some(scripts) = self.batch_scripts(batch_id, &opened) {
+                break scripts;
+            }
+            let event = match tokio::time::timeout_at(deadline, events.recv()).await {
+                Ok(Some(event)) => event,
+                Ok(None) => {
+                    fail_pending(&mut results, "Channel funding: no event received");
+                    return Ok(self.abort_batch(batch_id, &opened, results));
+                }
+                Err(_) => {
+                    let reason = format!("no funding script after {FUNDING_WAIT_TIMEOUT_SECS}s");
+                    fail_pending(&mut results, &reason);
+                    return Ok(self.abort_batch(batch_id, &opened, results));
+                }
+            };
+            if let Event::Lightning(LightningEvent::CloseChannelEvent {
+                channel_id,
+                message,
+                ..
+            }) = event
+            {
+                if let Some(index) = opened
+                    .iter()
+                    .position(|(id, _)| id.to_string() == channel_id)
+                {
+                    results[index].error = Some(message);
+                    return Ok(self.abort_batch(batch_id, &opened, results));
+                }
+            }
+        };
+        self.batch_fundings
+            .lock()
+            .expect("batch fundings poisoned")
+            .remove(&batch_id);
+
+        let outputs = request
+            .channels
+            .iter()
+            .zip(scripts.iter())
+            .map(|(open_channel, script)| (script.clone(), Amount::from_sat(open_channel.amount)))
+            .collect::<Vec<_>>();
+        let fee_rate = self.onchain.estimate_fee_rate(FeeTarget::ChannelFunding);
+        let best_block = self.manager().current_best_block().height;
+        let transaction = match self
+            .wallet_manager
+            .create_batch_transaction(outputs, fee_rate, Height::from_consensus(best_block)?)
+            .await
+        {
+            Ok(transaction) => transaction,
+            Err(err) => {
+                let reason = format!("Failed to create funding transaction: {err}");
+                fail_pending(&mut results, &reason);
+                return Ok(self.abort_batch(batch_id, &opened, results));
+            }
+        };
+        let channels = opened
+            .iter()
+            .map(|(id, peer)| (id, peer))
+            .collect::<Vec<_>>();
+        if let Err(err) = self
+            .manager()
+            .batch_funding_transaction_generated(&channels, transaction.clone())
+        {
+            let reason = format!("batch_funding_transaction_generated failed: {err:?}");
+            fail_pending(&mut results, &reason);
+            return Ok(self.abort_batch(batch_id, &opened, results));
+        }
+        for (result, script) in results.iter_mut().zip(scripts.iter()) {
+            result.funding_outnum = transaction
+                .output
+                .iter()
+                .position(|output| output.script_pubkey == *script)
+                .map(|outnum| outnum as u32);
+        }
+
+        // LDK broadcasts once every peer signed, and closes the whole batch
+        // if one of them fails from now on.
+        let txid = transaction.compute_txid();
+        loop {
+            let event = match tokio::time::timeout_at(deadline, events.recv()).await {
+                Ok(Some(event)) => event,
+                Ok(None) => error::bail!("Channel funding: no event received"),
+                Err(_) => error::bail!(
+                    "batch funding `{txid}` still pending after {FUNDING_WAIT_TIMEOUT_SECS}s"
+                ),
+            };
+            match event {
+                Event::OnChain(OnChainEvent::SendRawTransaction(tx))
+                    if tx.compute_txid() == txid =>
+                {
+                    break;
+                }
+                // The channels no longer go by their temporary ids, but by
+                // their output of the funding transaction.
+                Event::Lightning(LightningEvent::CloseChannelEvent {
+                    message,
+                    funding_utxo,
+                    ..
+                }) => {
+                    let Some(outpoint) = funding_outpoint(funding_utxo.as_deref())
+                        .filter(|outpoint| outpoint.txid == txid)
+                    else {
+                        continue;
+                    };
+                    if let Some(result) = results
+                        .iter_mut()
+                        .find(|result| result.funding_outnum == Some(outpoint.vout))
+                    {
+                        result.error = Some(message);
+                    }
+                    fail_pending(&mut results, "the batch was closed");
+                    for result in results.iter_mut() {
+                        result.funding_outnum = None;
+                    }
+                    return Ok(response::MultiFundChannel {
+                        tx: None,
+                        txid: None,
+                        channels: results,
+                    });
+                }
+                _ => {}
+            }
+        }
+        Ok(response::MultiFundChannel {
+            tx: Some(transaction),
+            txid: Some(txid),
+            channels: results,
+        })
+    }
+
+    /// The funding scripts of `opened`, in order, once all are known.
+    fn batch_scripts(
+        &self,
+        batch_id: u128,
+        opened: &[(ChannelId, PublicKey)],
+    ) -> Option<Vec<ScriptBuf>> {
+        let batches = self.batch_fundings.lock().expect("batch fundings poisoned");
+        let batch = batches.get(&batch_id)?;
+        opened
+            .iter()
+            .map(|(id, _)| batch.get(id).cloned())
+            .collect()
+    }
+
+    /// Drop the channels of a batch that cannot be funded.
+    fn abort_batch(
+        &self,
+        batch_id: u128,
+        opened: &[(ChannelId, PublicKey)],
+        mut results: Vec<response::BatchChannel>,
+    ) -> response::MultiFundChannel {
+        self.batch_fundings
+            .lock()
+            .expect("batch fundings poisoned")
+            .remove(&batch_id);
+        for (temp_channel_id, peer_id) in opened {
+            if let Err(err) = self.manager().force_close_broadcasting_latest_txn(
+                temp_channel_id,
+                peer_id,
+                "channel batch rolled back".to_owned(),
+            ) {
+                log::warn!(
+                    target: "lampo",
+                    "failed to drop channel `{temp_channel_id}` of a rolled back batch: {err:?}"
+                );
+            }
+        }
+        for result in results.iter_mut().filter(|result| result.error.is_none()) {
+            result.error = Some("rolled back, another channel of the batch failed".to_owned());
+        }
+        response::MultiFundChannel {
+            tx: None,
+            txid: None,
+            channels: results,
+        }
+    }
+
+    /// Add wallet coins to a channel. The coins are picked largest first,
+    /// LDK checks they also cover our share of the splice fees.
+    pub async fn splice_in(&self, splice: request::SpliceIn) -> error::Result<response::Splice> {
+        let channel = self.splice_channel_details(&splice.channel_id)?;
+        let feerate = self.splice_feerate(splice.feerate_sat_per_kw);
+
+        let mut utxos = self
+            .wallet_manager
+            .confirmed_utxos()?
+            .into_iter()
+            .filter(|(_, output)| output.script_pubkey.is_p2wpkh())
+            .collect::<Vec<_>>();
+        utxos.sort_by(|(_, a), (_, b)| b.value.cmp(&a.value));
+        let amount = Amount::from_sat(splice.amount_sat);
+        let needed = |inputs: usize| {
+            let weight = SPLICE_BASE_WEIGHT + P2WPKH_INPUT_WEIGHT * inputs as u64;
+            amount + Amount::from_sat(feerate as u64 * weight / 1000)
+        };
+        let mut inputs = Vec::new();
+        let mut outpoints = Vec::new();
+        let mut selected = Amount::ZERO;
+        for (outpoint, output) in utxos {
+            if !inputs.is_empty() && selected >= needed(inputs.len()) {
+                break;
+            }
+            let prevtx =
+                self.wallet_manager
+                    .get_transaction(outpoint.txid)?
+                    .ok_or(error::anyhow!(
+                        "wallet lost the transaction of `{outpoint}`"
+                    ))?;
+            let input = FundingTxInput::new_p2wpkh(prevtx, outpoint.vout)
+                .map_err(|_| error::anyhow!("`{outpoint}` cannot fund a splice"))?;
+            inputs.push(input);
+            outpoints.push(outpoint);
+            selected += output.value;
+        }
+        if selected < needed(inputs.len()) {
+            error::bail!(
+                "wallet has {} sat confirmed, not enough to splice in {} sat and pay the fees",
+                selected.to_sat(),
+                splice.amount_sat
+            );
+        }
+
+        let change_script = self.wallet_manager.next_wallet_script()?;
+        // Locked until the splice fails or locks in, so neither another
+        // splice nor a wallet spend picks the same coins meanwhile.
+        self.wallet_manager.lock_utxos(&outpoints)?;
+        let contribution = SpliceContribution::SpliceIn {
+            value: amount,
+            inputs,
+            change_script: Some(change_script),
+        };
+        self.splice(channel, contribution, feerate, outpoints).await
+    }
+
+    /// Move funds out of a channel to `address`.
+    pub async fn splice_out(&self, splice: request::SpliceOut) -> error::Result<response::Splice> {
+        let channel = self.splice_channel_details(&splice.channel_id)?;
+        let feerate = self.splice_feerate(splice.feerate_sat_per_kw);
+        let address = Address::from_str(&splice.address)?.require_network(self.conf.network)?;
+        let contribution = SpliceContribution::SpliceOut {
+            outputs: vec![TxOut {
+                value: Amount::from_sat(splice.amount_sat),
+                script_pubkey: address.script_pubkey(),
+            }],
+        };
+        self.splice(channel, contribution, feerate, Vec::new())
+            .await
+    }
+
+    fn splice_channel_details(&self, channel_id: &str) -> error::Result<ChannelDetails> {
+        let channel = self
+            .manager()
+            .list_channels()
+            .into_iter()
+            .find(|channel| channel.channel_id.to_string() == channel_id)
+            .ok_or(error::anyhow!("channel `{channel_id}` not found"))?;
+        if !channel.is_channel_ready {
+            error::bail!("channel `{channel_id}` is not ready, it cannot be spliced");
+        }
+        Ok(channel)
+    }
+
+    fn splice_feerate(&self, feerate_sat_per_kw: Option<u32>) -> u32 {
+        feerate_sat_per_kw.unwrap_or_else(|| {
+            self.onchain
+                .estimate_fee_rate(FeeTarget::ChannelFunding)
+                .to_sat_per_kwu() as u32
+        })
+    }
+
+    /// Start the splice and wait for its transaction to be broadcast, the
+    /// same way `open_channel` waits for the funding transaction.
+    async fn splice(
+        &self,
+        channel: ChannelDetails,
+        contribution: SpliceContribution,
+        feerate_sat_per_kw: u32,
+        locked_utxos: Vec<OutPoint>,
+    ) -> error::Result<response::Splice> {
+        let channel_id = channel.channel_id;
+        let peer_id = channel.counterparty.node_id;
+        {
+            let mut splicing = self.splicing.lock().expect("splicing poisoned");
+            if splicing.contains_key(&channel_id) {
+                self.wallet_manager.unlock_utxos(&locked_utxos);
+                error::bail!("channel `{channel_id}` is already splicing");
+            }
+            splicing.insert(channel_id, locked_utxos);
+        }
+        // Subscribe before starting, for the same reason as `open_channel`.
+        let mut events = self.handler().events();
+        // The splice ends, and its coins go back to the wallet, when it
+        // fails here or on `SpliceFailed`, when the channel closes, or when
+        // it locks in. Past the timeout it may still complete, so it stays.
+        if let Err(err) = self.manager().splice_channel(
+            &channel_id,
+            &peer_id,
+            contribution,
+            feerate_sat_per_kw,
+            None,
+        ) {
+            self.unlock_splice_utxos(&channel_id);
+            error::bail!("{:?}", err);
+        }
+
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(FUNDING_WAIT_TIMEOUT_SECS);
+        let mut new_funding_txo: Option<OutPoint> = None;
+        let mut broadcasted = Vec::new();
+        loop {
+            let event = match tokio::time::timeout_at(deadline, events.recv()).await {
+                Ok(Some(event)) => event,
+                Ok(None) => error::bail!("splice of `{channel_id}`: no event received"),
+                Err(_) => error::bail!(
+                    "splice of `{channel_id}` still pending after {FUNDING_WAIT_TIMEOUT_SECS}s"
+                ),
+            };
+            match event {
+                Event::Lightning(LightningEvent::SplicePending {
+                    channel_id: spliced,
+                    new_funding_txo: txo,
+                    ..
+                }) if spliced == channel_id => {
+                    new_funding_txo = Some(txo);
+                }
+                Event::Lightning(LightningEvent::SpliceFailed {
+                    channel_id: spliced,
+                    reason,
+                    ..
+                }) if spliced == channel_id => {
+                    error::bail!("splice of `{channel_id}` failed: {reason}");
+                }
+                // The broadcast can race ahead of `SplicePending`.
+                Event::OnChain(OnChainEvent::SendRawTransaction(tx)) => {
+                    broadcasted.push(tx.compute_txid());
+                }
+                _ => {}
+            }
+            if let Some(txo) = new_funding_txo {
+                if broadcasted.contains(&txo.txid) {
+                    return Ok(response::Splice {
+                        channel_id: channel_id.to_string(),
+                        peer_id: peer_id.to_string(),
+                        new_funding_txo: txo.to_string(),
+                        txid: txo.txid.to_string(),
+                    });
+                }
+            }
+        }
+    }
+
     pub fn close_channel(&self, channel: request::CloseChannel) -> error::Result<()> {
+        channel.validate()?;
         let channel_id = channel.channel_id()?;
         let node_id = channel.counterpart_node_id()?;
+        if channel.force {
+            return self.force_close_channel(&channel_id, &node_id, "closed on request");
+        }
+
+        let shutdown_script = channel
+            .destination
+            .as_deref()
+            .map(|address| -> error::Result<ShutdownScript> {
+                let address = Address::from_str(address)?.require_network(self.conf.network)?;
+                ShutdownScript::try_from(address.script_pubkey())
+                    .map_err(|err| error::anyhow!("`{address}` cannot receive a close: {err:?}"))
+            })
+            .transpose()?;
+        self.manager()
+            .close_channel_with_feerate_and_script(
+                &channel_id,
+                &node_id,
+                channel.feerate_sat_per_kw,
+                shutdown_script,
+            )
+            .map_err(|err| error::anyhow!("{:?}", err))?;
+        Ok(())
+    }
 
+    /// Broadcast our latest commitment transaction, telling the peer
+    /// `reason`.
+    pub fn force_close_channel(
+        &self,
+        channel_id: &ChannelId,
+        node_id: &PublicKey,
+        reason: &str,
+    ) -> error::Result<()> {
         self.manager()
-            .close_channel(&channel_id, &node_id)
+            .force_close_broadcasting_latest_txn(channel_id, node_id, reason.to_owned())
             .map_err(|err| error::anyhow!("{:?}", err))?;
         Ok(())
     }
@@ -732,3 +1846,15 @@ impl LampoChannelManager {
         Ok(())
     }
 }
+
+/// The funding outpoint of a `CloseChannelEvent`.
+fn funding_outpoint(funding_utxo: Option<&str>) -> Option<OutPoint> {
+    OutPoint::from_str(funding_utxo?).ok()
+}
+
+/// Give every channel of a batch without an error of its own `reason`.
+fn fail_pending(results: &mut [response::BatchChannel], reason: &str) {
+    for result in results.iter_mut().filter(|result| result.error.is_none()) {
+        result.error = Some(reason.to_owned());
+    }
+}
@@ -0,0 +1,161 @@
+//! Hold invoices.
+//!
+//! A hold invoice is built from a payment hash alone, so LDK cannot claim
+//! it: the HTLC(s) are parked when they arrive, and the caller later
+//! settles them with the preimage or cancels them. The state is stored
+//! because a parked payment outlives a restart (LDK replays its
+//! `PaymentClaimable`).
+use std::sync::Arc;
+
+use lampo_common::error;
+use lampo_common::ldk::types::payment::PaymentHash;
+use lampo_common::model::response::{HoldInvoice, HoldInvoiceState};
+
+use super::ledger;
+use crate::persistence::LampoPersistence;
+
+/// Hold invoices, keyed by hex payment hash.
+pub const HOLD_INVOICES_NAMESPACE: &str = "hold_invoices";
+
+/// How many blocks before `claim_deadline` a parked payment is failed back.
+/// LDK force closes the channel when an HTLC is still pending at the
+/// deadline, so we give up a few blocks before it rather than racing it.
+pub const CANCEL_MARGIN_BLOCKS: u32 = 3;
+
+fn key(payment_hash: &PaymentHash) -> String {
+    lampo_common::hex::encode(payment_hash.0)
+}
+
+/// Store a new hold invoice. A payment hash can back one hold invoice only.
+pub fn create(
+    persister: &Arc<LampoPersistence>,
+    invoice: HoldInvoice,
+) -> error::Result<HoldInvoice> {
+    let key = invoice.payment_hash.clone();
+    ledger::try_update(
+        persister,
+        HOLD_INVOICES_NAMESPACE,
+        &key,
+        |entry| match entry {
+            Some(_) => error::bail!("a hold invoice for payment hash `{key}` already exists"),
+            None => Ok(invoice),
+        },
+    )
+}
+
+pub fn load(
+    persister: &Arc<LampoPersistence>,
+    payment_hash: &PaymentHash,
+) -> error::Result<Option<HoldInvoice>> {
+    ledger::read(persister, HOLD_INVOICES_NAMESPACE, &key(payment_hash))
+}
+
+pub fn list(persister: &Arc<LampoPersistence>) -> error::Result<Vec<HoldInvoice>> {
+    ledger::read_all(persister, HOLD_INVOICES_NAMESPACE)
+}
+
+/// Apply `f` to the hold invoice of `payment_hash` and store the result,
+/// unless it refuses the change. Settle, cancel and the HTLC
+/// arrival all go through here, so two of them cannot both win.
+pub fn transition<F>(
+    persister: &Arc<LampoPersistence>,
+    payment_hash: &PaymentHash,
+    f: F,
+) -> error::Result<HoldInvoice>
+where
+    F: FnOnce(&mut HoldInvoice) -> error::Result<()>,
+{
+    let key = key(payment_hash);
+    ledger::try_update(persister, HOLD_INVOICES_NAMESPACE, &key, |entry| {
+        let Some(mut invoice) = entry else {
+            error::bail!("no hold invoice for payment hash `{key}`");
+        };
+        f(&mut invoice)?;
+        Ok(invoice)
+    })
+}
+
+/// Park a payment that arrived for `invoice`. The same security rules as
+/// for every other claim apply: an underpaid payment is refused, since the
+/// preimage released on settle would prove a payment we did not get.
+pub fn accept(
+    invoice: &mut HoldInvoice,
+    amount_msat: u64,
+    counterparty_skimmed_fee_msat: u64,
+    claim_deadline: Option<u32>,
+) -> error::Result<()> {
+    match invoice.state {
+        // `Accepted` again is LDK replaying the event after a restart.
+        HoldInvoiceState::Open | HoldInvoiceState::Accepted => {}
+        state => error::bail!("hold invoice is {state:?}"),
+    }
+    if counterparty_skimmed_fee_msat > 0 {
+        error::bail!("payment is underpaid: the counterparty skimmed an extra fee");
+    }
+    if invoice
+        .amount_msat
+        .is_some_and(|amount| amount_msat < amount)
+    {
+        error::bail!("payment is underpaid: received {amount_msat} msat");
+    }
+    invoice.state = HoldInvoiceState::Accepted;
+    invoice.amount_received_msat = Some(amount_msat);
+    invoice.claim_deadline = claim_deadline;
+    Ok(())
+}
+
+/// Whether a parked payment must be failed back at block `height`.
+pub fn is_due(invoice: &HoldInvoice, height: u32) -> bool {
+    invoice.state == HoldInvoiceState::Accepted
+        && invoice
+            .claim_deadline
+            .is_some_and(|deadline| height + CANCEL_MARGIN_BLOCKS >= deadline)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invoice(amount_msat: Option<u64>) -> HoldInvoice {
+        HoldInvoice {
+            payment_hash: "aa".to_owned(),
+            bolt11: String::new(),
+            state: HoldInvoiceState::Open,
+            amount_msat,
+            amount_received_msat: None,
+            claim_deadline: None,
+            payment_preimage: None,
+        }
+    }
+
+    #[test]
+    fn accepts_a_full_payment() {
+        let mut hold = invoice(Some(1_000));
+        accept(&mut hold, 1_000, 0, Some(200)).unwrap();
+        assert_eq!(hold.state, HoldInvoiceState::Accepted);
+        assert_eq!(hold.amount_received_msat, Some(1_000));
+        // LDK replays the event after a restart.
+        accept(&mut hold, 1_000, 0, Some(200)).unwrap();
+    }
+
+    #[test]
+    fn refuses_underpaid_or_closed_invoices() {
+        assert!(accept(&mut invoice(Some(1_000)), 999, 0, None).is_err());
+        assert!(accept(&mut invoice(None), 1_000, 1, None).is_err());
+
+        let mut hold = invoice(None);
+        hold.state = HoldInvoiceState::Canceled;
+        assert!(accept(&mut hold, 1_000, 0, None).is_err());
+    }
+
+    #[test]
+    fn due_before_the_claim_deadline() {
+        let mut hold = invoice(None);
+        accept(&mut hold, 1_000, 0, Some(100)).unwrap();
+        assert!(!is_due(&hold, 100 - CANCEL_MARGIN_BLOCKS - 1));
+        assert!(is_due(&hold, 100 - CANCEL_MARGIN_BLOCKS));
+
+        hold.state = HoldInvoiceState::Settled;
+        assert!(!is_due(&hold, 100));
+    }
+}
@@ -0,0 +1,533 @@
+//! Payment ledger.
+//!
+//! Every outgoing payment, every invoice and offer we generate, every
+//! payment we claim, every HTLC we forward and every channel we close is
+//! written here, so the history survives a restart. Entries are JSON
+//! encoded [`PaymentInfo`], [`InvoiceInfo`], [`OfferInfo`], [`ForwardInfo`]
+//! and [`ClosedChannel`] records, one key per entry. Payments are also
+//! indexed by payment hash, so a lookup by hash reads only its entries.
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use lampo_common::bitcoin::hashes::sha256::Hash as Sha256;
+use lampo_common::bitcoin::hashes::Hash;
+use lampo_common::error;
+use lampo_common::json;
+use lampo_common::ldk::io::ErrorKind;
+use lampo_common::ldk::ln::channelmanager::PaymentId;
+use lampo_common::ldk::util::persist::KVStoreSync;
+use lampo_common::model::request::{
+    ListClosedChannels, ListForwards, ListInvoices, ListOffers, ListPays,
+};
+use lampo_common::model::response::{
+    ClosedChannel, ForwardInfo, InvoiceInfo, OfferInfo, PaymentInfo, PaymentKind,
+};
+
+use crate::persistence::LampoPersistence;
+
+/// Outgoing payments, keyed by hex payment id.
+pub const PAYMENTS_NAMESPACE: &str = "payments";
+/// Incoming payments, keyed by hex payment hash.
+pub const INVOICES_NAMESPACE: &str = "invoices";
+/// Generated offers, keyed by hex offer id.
+pub const OFFERS_NAMESPACE: &str = "offers";
+/// Forwarded HTLCs, keyed by [`forward_key`], so a time window skips the
+/// entries outside of it without reading them.
+pub const FORWARDS_NAMESPACE: &str = "forwards";
+/// Channel history, keyed by hex channel id.
+pub const CHANNELS_NAMESPACE: &str = "channels";
+/// Ids of the outgoing payments of each payment hash, keyed by hex payment
+/// hash. A hash has more than one id when an invoice is paid again after a
+/// failed attempt.
+pub const PAYMENT_HASHES_NAMESPACE: &str = "payment_hashes";
+/// Labels of our invoices, keyed by the hex sha256 of the label: labels
+/// are free text, store keys are not.
+pub const INVOICE_LABELS_NAMESPACE: &str = "invoice_labels";
+/// Labels of our offers, keyed like [`INVOICE_LABELS_NAMESPACE`].
+pub const OFFER_LABELS_NAMESPACE: &str = "offer_labels";
+/// Bookkeeping of the ledger itself.
+const LEDGER_NAMESPACE: &str = "ledger";
+/// Written once [`PAYMENT_HASHES_NAMESPACE`] covers every payment.
+const PAYMENT_HASHES_INDEXED_KEY: &str = "payment_hashes_indexed";
+/// Written once [`INVOICE_LABELS_NAMESPACE`] covers every invoice.
+const INVOICE_LABELS_INDEXED_KEY: &str = "invoice_labels_indexed";
+/// Written once [`OFFER_LABELS_NAMESPACE`] covers every offer.
+const OFFER_LABELS_INDEXED_KEY: &str = "offer_labels_indexed";
+/// Written once every forward is stored under its [`forward_key`].
+const FORWARD_KEYS_INDEXED_KEY: &str = "forward_keys_indexed";
+
+/// One lock per entry being updated, keyed by namespace and key. It
+/// serializes read-modify-write cycles of the same entry: the `pay` call
+/// and the LDK event handler both update the same payment entry, and the
+/// event can land before the RPC has written its own view. Updates of
+/// different entries do not wait on each other.
+static UPDATE_LOCKS: Mutex<BTreeMap<(String, String), Arc<Mutex<()>>>> =
+    Mutex::new(BTreeMap::new());
+
+/// Current unix time in seconds.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+fn write<T: json::Serialize>(
+    persister: &Arc<LampoPersistence>,
+    namespace: &str,
+    key: &str,
+    value: &T,
+) -> error::Result<()> {
+    persister.write(namespace, "", key, json::to_vec(value)?)?;
+    Ok(())
+}
+
+pub(crate) fn read<T: json::DeserializeOwned>(
+    persister: &Arc<LampoPersistence>,
+    namespace: &str,
+    key: &str,
+) -> error::Result<Option<T>> {
+    match persister.read(namespace, "", key) {
+        Ok(buf) => Ok(Some(json::from_slice(&buf)?)),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Read every entry of `namespace`. An entry that no longer decodes is
+/// skipped with a warning rather than hiding the rest of the history.
+pub(crate) fn read_all<T: json::DeserializeOwned>(
+    persister: &Arc<LampoPersistence>,
+    namespace: &str,
+) -> error::Result<Vec<T>> {
+    let mut entries = Vec::new();
+    for key in persister.list(namespace, "")? {
+        match read(persister, namespace, &key) {
+            Ok(Some(entry)) => entries.push(entry),
+            Ok(None) => {}
+            Err(err) => {
+                log::warn!(target: "lampo::ledger", "skipping unreadable `{namespace}` entry `{key}`: {err}");
+            }
+        }
+    }
+    Ok(entries)
+}
+
+/// Apply `update` to the entry stored under `key`, or to `default()` when
+/// there is none yet, and write the result back.
+fn update<T, D, F>(
+    persister: &Arc<LampoPersistence>,
+    namespace: &str,
+    key: &str,
+    default: D,
+    update: F,
+) -> error::Result<T>
+where
+    T: json::Serialize + json::DeserializeOwned,
+    D: FnOnce() -> T,
+    F: FnOnce(&mut T),
+{
+    try_update(persister, namespace, key, |entry| {
+        let mut entry = entry.unwrap_or_else(default);
+        update(&mut entry);
+        Ok(entry)
+    })
+}
+
+/// Like [`update`], but `update` sees whether an entry exists and can
+/// refuse the change: on error nothing is written.
+pub(crate) fn try_update<T, F>(
+    persister: &Arc<LampoPersistence>,
+    namespace: &str,
+    key: &str,
+    update: F,
+) -> error::Result<T>
+where
+    T: json::Serialize + json::DeserializeOwned,
+    F: FnOnce(Option<T>) -> error::Result<T>,
+{
+    let lock = UPDATE_LOCKS
+        .lock()
+        .expect("ledger update locks poisoned")
+        .entry((namespace.to_owned(), key.to_owned()))
+        .or_default()
+        .clone();
+    let result = {
+        let _guard = lock.lock().expect("ledger update lock poisoned");
+        read(persister, namespace, key)
+            .and_then(update)
+            .and_then(|entry| {
+                write(persister, namespace, key, &entry)?;
+                Ok(entry)
+            })
+    };
+    // Forget the lock once nobody else waits on it, the map would
+    // otherwise keep one per entry ever updated.
+    let mut locks = UPDATE_LOCKS.lock().expect("ledger update locks poisoned");
+    if Arc::strong_count(&lock) == 2 {
+        locks.remove(&(namespace.to_owned(), key.to_owned()));
+    }
+    result
+}
+
+pub fn update_payment<D, F>(
+    persister: &Arc<LampoPersistence>,
+    payment_id: &str,
+    default: D,
+    f: F,
+) -> error::Result<PaymentInfo>
+where
+    D: FnOnce() -> PaymentInfo,
+    F: FnOnce(&mut PaymentInfo),
+{
+    let entry = update(persister, PAYMENTS_NAMESPACE, payment_id, default, f)?;
+    if let Some(payment_hash) = &entry.payment_hash {
+        index_payment_hash(persister, payment_hash, &entry.payment_id)?;
+    }
+    Ok(entry)
+}
+
+/// Add `payment_id` to the payments of `payment_hash`.
+fn index_payment_hash(
+    persister: &Arc<LampoPersistence>,
+    payment_hash: &str,
+    payment_id: &str,
+) -> error::Result<()> {
+    let known: Option<Vec<String>> = read(persister, PAYMENT_HASHES_NAMESPACE, payment_hash)?;
+    if known.is_some_and(|ids| ids.iter().any(|id| id == payment_id)) {
+        return Ok(());
+    }
+    update(
+        persister,
+        PAYMENT_HASHES_NAMESPACE,
+        payment_hash,
+        Vec::new,
+        |ids: &mut Vec<String>| {
+            if !ids.iter().any(|id| id == payment_id) {
+                ids.push(payment_id.to_owned());
+            }
+        },
+    )?;
+    Ok(())
+}
+
+/// Index the entries stored before the indexes existed. Runs at startup
+/// and does the work once, later entries are indexed as they are written.
+pub fn build_indexes(persister: &Arc<LampoPersistence>) -> error::Result<()> {
+    index_once(persister, PAYMENT_HASHES_INDEXED_KEY, || {
+        let pays: Vec<PaymentInfo> = read_all(persister, PAYMENTS_NAMESPACE)?;
+        for pay in &pays {
+            if let Some(payment_hash) = &pay.payment_hash {
+                index_payment_hash(persister, payment_hash, &pay.payment_id)?;
+            }
+        }
+        Ok(())
+    })?;
+    index_once(persister, INVOICE_LABELS_INDEXED_KEY, || {
+        let invoices: Vec<InvoiceInfo> = read_all(persister, INVOICES_NAMESPACE)?;
+        for label in invoices.iter().filter_map(|invoice| invoice.label.as_ref()) {
+            write(
+                persister,
+                INVOICE_LABELS_NAMESPACE,
+                &label_key(label),
+                label,
+            )?;
+        }
+        Ok(())
+    })?;
+    index_once(persister, OFFER_LABELS_INDEXED_KEY, || {
+        let offers: Vec<OfferInfo> = read_all(persister, OFFERS_NAMESPACE)?;
+        for label in offers.iter().filter_map(|offer| offer.label.as_ref()) {
+            write(persister, OFFER_LABELS_NAMESPACE, &label_key(label), label)?;
+        }
+        Ok(())
+    })?;
+    // Forwards used to be keyed by their id alone.
+    index_once(persister, FORWARD_KEYS_INDEXED_KEY, || {
+        for key in persister.list(FORWARDS_NAMESPACE, "")? {
+            if forward_key_time(&key).is_some() {
+                continue;
+            }
+            let Some(forward) = read::<ForwardInfo>(persister, FORWARDS_NAMESPACE, &key)? else {
+                continue;
+            };
+            write(
+                persister,
+                FORWARDS_NAMESPACE,
+                &forward_key(&forward),
+                &forward,
+            )?;
+            persister.remove(FORWARDS_NAMESPACE, "", &key, false)?;
+        }
+        Ok(())
+    })
+}
+
+fn index_once<F>(persister: &Arc<LampoPersistence>, marker: &str, index: F) -> error::Result<()>
+where
+    F: FnOnce() -> error::Result<()>,
+{
+    if read::<bool>(persister, LEDGER_NAMESPACE, marker)?.is_some() {
+        return Ok(());
+    }
+    index()?;
+    write(persister, LEDGER_NAMESPACE, marker, &true)
+}
+
+fn label_key(label: &str) -> String {
+    lampo_common::hex::encode(Sha256::hash(label.as_bytes()).to_byte_array())
+}
+
+/// Claim `label` in `namespace`, refusing one already taken. The check and
+/// the write happen under the entry lock, so of two concurrent callers
+/// only one gets the label. `what` names the labelled thing in the error.
+pub fn reserve_label(
+    persister: &Arc<LampoPersistence>,
+    namespace: &str,
+    label: &str,
+    what: &str,
+) -> error::Result<()> {
+    try_update(
+        persister,
+        namespace,
+        &label_key(label),
+        |taken: Option<String>| {
+            if taken.is_some() {
+                error::bail!("{what} labelled `{label}` already exists");
+            }
+            Ok(label.to_owned())
+        },
+    )?;
+    Ok(())
+}
+
+/// Give back a label [`reserve_label`] claimed for something that was not
+/// created after all.
+pub fn release_label(persister: &Arc<LampoPersistence>, namespace: &str, label: &str) {
+    if let Err(err) = persister.remove(namespace, "", &label_key(label), false) {
+        log::error!(target: "lampo::ledger", "releasing label `{label}`: {err}");
+    }
+}
+
+/// Outgoing payments of `payment_hash`, oldest first.
+pub fn load_payments_by_hash(
+    persister: &Arc<LampoPersistence>,
+    payment_hash: &str,
+) -> error::Result<Vec<PaymentInfo>> {
+    let ids: Vec<String> =
+        read(persister, PAYMENT_HASHES_NAMESPACE, payment_hash)?.unwrap_or_default();
+    let mut pays = Vec::with_capacity(ids.len());
+    for id in ids {
+        if let Some(pay) = load_payment(persister, &id)? {
+            pays.push(pay);
+        }
+    }
+    pays.sort_by_key(|pay| pay.created_at);
+    Ok(pays)
+}
+
+/// Merge `f` into the entry of `payment_id`, creating it when the LDK event
+/// beats the RPC that sent the payment. The payment is already in flight
+/// whenever we get here, so a storage failure is logged and not returned:
+/// failing the caller would only invite a retry of a payment that may
+/// still succeed. The updated entry is returned when it was stored.
+pub fn record_payment<F>(
+    persister: &Arc<LampoPersistence>,
+    payment_id: &PaymentId,
+    f: F,
+) -> Option<PaymentInfo>
+where
+    F: FnOnce(&mut PaymentInfo),
+{
+    let payment_id = lampo_common::hex::encode(payment_id.0);
+    let result = update_payment(
+        persister,
+        &payment_id,
+        || PaymentInfo::new(payment_id.clone(), PaymentKind::Bolt11, now()),
+        f,
+    );
+    result
+        .map_err(|err| {
+            log::error!(target: "lampo::ledger", "storing payment `{payment_id}`: {err}");
+        })
+        .ok()
+}
+
+pub fn load_payment(
+    persister: &Arc<LampoPersistence>,
+    payment_id: &str,
+) -> error::Result<Option<PaymentInfo>> {
+    read(persister, PAYMENTS_NAMESPACE, payment_id)
+}
+
+/// Outgoing payments matching `filter`, oldest first.
+pub fn list_payments(
+    persister: &Arc<LampoPersistence>,
+    filter: &ListPays,
+) -> error::Result<Vec<PaymentInfo>> {
+    let mut pays: Vec<PaymentInfo> = match &filter.payment_hash {
+        Some(payment_hash) => load_payments_by_hash(persister, payment_hash)?,
+        None => read_all(persister, PAYMENTS_NAMESPACE)?,
+    };
+    pays.retain(|pay| filter.matches(pay));
+    pays.sort_by_key(|pay| pay.created_at);
+    Ok(pays)
+}
+
+pub fn update_invoice<D, F>(
+    persister: &Arc<LampoPersistence>,
+    payment_hash: &str,
+    default: D,
+    f: F,
+) -> error::Result<InvoiceInfo>
+where
+    D: FnOnce() -> InvoiceInfo,
+    F: FnOnce(&mut InvoiceInfo),
+{
+    update(persister, INVOICES_NAMESPACE, payment_hash, default, f)
+}
+
+pub fn load_invoice(
+    persister: &Arc<LampoPersistence>,
+    payment_hash: &str,
+) -> error::Result<Option<InvoiceInfo>> {
+    read(persister, INVOICES_NAMESPACE, payment_hash)
+}
+
+/// Incoming payments matching `filter`, oldest first. Expiry is applied
+/// before filtering, so `status: expired` finds unpaid invoices past due.
+pub fn list_invoices(
+    persister: &Arc<LampoPersistence>,
+    filter: &ListInvoices,
+) -> error::Result<Vec<InvoiceInfo>> {
+    let now = now();
+    let mut invoices: Vec<InvoiceInfo> = match &filter.payment_hash {
+        Some(payment_hash) => load_invoice(persister, payment_hash)?.into_iter().collect(),
+        None => read_all(persister, INVOICES_NAMESPACE)?,
+    };
+    invoices
+        .iter_mut()
+        .for_each(|invoice| invoice.refresh_status(now));
+    invoices.retain(|invoice| filter.matches(invoice));
+    invoices.sort_by_key(|invoice| invoice.created_at);
+    Ok(invoices)
+}
+
+pub fn store_offer(persister: &Arc<LampoPersistence>, offer: &OfferInfo) -> error::Result<()> {
+    write(persister, OFFERS_NAMESPACE, &offer.offer_id, offer)
+}
+
+pub fn load_offer(
+    persister: &Arc<LampoPersistence>,
+    offer_id: &str,
+) -> error::Result<Option<OfferInfo>> {
+    read(persister, OFFERS_NAMESPACE, offer_id)
+}
+
+/// Offers matching `filter`, oldest first.
+pub fn list_offers(
+    persister: &Arc<LampoPersistence>,
+    filter: &ListOffers,
+) -> error::Result<Vec<OfferInfo>> {
+    let now = now();
+    let mut offers: Vec<OfferInfo> = read_all(persister, OFFERS_NAMESPACE)?;
+    offers.retain(|offer| filter.matches(offer, now));
+    offers.sort_by_key(|offer| offer.created_at);
+    Ok(offers)
+}
+
+/// Mark `offer_id` disabled. Only offers we generated can be disabled.
+pub fn disable_offer(
+    persister: &Arc<LampoPersistence>,
+    offer_id: &str,
+) -> error::Result<OfferInfo> {
+    try_update(persister, OFFERS_NAMESPACE, offer_id, |offer| {
+        let Some(mut offer) = offer else {
+            error::bail!("offer `{offer_id}` not found");
+        };
+        offer.disabled = true;
+        Ok(offer)
+    })
+}
+
+/// Store a resolved forward. Like [`record_payment`] the HTLC is already
+/// settled or failed, so a storage failure is only logged.
+pub fn record_forward(persister: &Arc<LampoPersistence>, forward: &ForwardInfo) {
+    if let Err(err) = write(
+        persister,
+        FORWARDS_NAMESPACE,
+        &forward_key(forward),
+        forward,
+    ) {
+        log::error!(target: "lampo::ledger", "storing forward `{}`: {err}", forward.forward_id);
+    }
+}
+
+/// Forwards matching `filter`, oldest first. Only the entries resolved
+/// between `since` and `until` are read.
+pub fn list_forwards(
+    persister: &Arc<LampoPersistence>,
+    filter: &ListForwards,
+) -> error::Result<Vec<ForwardInfo>> {
+    let mut forwards = Vec::new();
+    for key in persister.list(FORWARDS_NAMESPACE, "")? {
+        let in_window = forward_key_time(&key).map_or(true, |resolved_at| {
+            filter.since.map_or(true, |since| resolved_at >= since)
+                && filter.until.map_or(true, |until| resolved_at <= until)
+        });
+        if !in_window {
+            continue;
+        }
+        match read::<ForwardInfo>(persister, FORWARDS_NAMESPACE, &key) {
+            Ok(Some(forward)) if filter.matches(&forward) => forwards.push(forward),
+            Ok(_) => {}
+            Err(err) => {
+                log::warn!(target: "lampo::ledger", "skipping unreadable `{FORWARDS_NAMESPACE}` entry `{key}`: {err}");
+            }
+        }
+    }
+    forwards.sort_by_key(|forward| forward.resolved_at);
+    Ok(forwards)
+}
+
+/// The store key of a forward: its zero padded resolution time, so keys
+/// sort by time, then its id.
+fn forward_key(forward: &ForwardInfo) -> String {
+    format!("{:020}_{}", forward.resolved_at, forward.forward_id)
+}
+
+/// The resolution time in a [`forward_key`].
+fn forward_key_time(key: &str) -> Option<u64> {
+    key.split_once('_')?.0.parse().ok()
+}
+
+/// Apply `f` to the history entry of `channel_id`, creating it when the
+/// channel predates the history. Errors are logged: losing a record must
+/// not stop the event handler.
+pub fn record_channel<F>(persister: &Arc<LampoPersistence>, channel_id: &str, f: F)
+where
+    F: FnOnce(&mut ClosedChannel),
+{
+    let result = update(
+        persister,
+        CHANNELS_NAMESPACE,
+        channel_id,
+        || ClosedChannel::new(channel_id.to_owned()),
+        f,
+    );
+    if let Err(err) = result {
+        log::error!(target: "lampo::ledger", "storing channel `{channel_id}`: {err}");
+    }
+}
+
+/// Closed channels matching `filter`, oldest closure first.
+pub fn list_closed_channels(
+    persister: &Arc<LampoPersistence>,
+    filter: &ListClosedChannels,
+) -> error::Result<Vec<ClosedChannel>> {
+    let mut channels: Vec<ClosedChannel> = read_all(persister, CHANNELS_NAMESPACE)?;
+    channels.retain(|channel| filter.matches(channel));
+    channels.sort_by_key(|channel| channel.closed_at);
+    Ok(channels)
+}
@@ -4,10 +4,13 @@ mod inventory_manager;
 mod offchain_manager;
 mod peer_manager;
 
+pub mod hold_invoice;
+pub mod ledger;
 pub mod payer_proof;
 pub mod peer_event;
 
 pub use channel_manager::LampoChannelManager;
+pub(crate) use channel_manager::EXTERNAL_FUNDING_TAG;
 pub use inventory_manager::LampoInventoryManager;
 pub use offchain_manager::OffchainManager;
 pub use peer_manager::LampoPeerManager;
@@ -10,29 +10,60 @@
 //! with the network graph. But this is not so clear yet.
 //!
 //! Author: Vincenzo Palazzo <vincenzopalazzo@member.fsf.org>
+use std::collections::HashSet;
+use std::num::NonZeroU64;
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
 
+use lampo_common::bitcoin::address::AddressData;
+use lampo_common::bitcoin::bech32::primitives::decode::CheckedHrpstring;
+use lampo_common::bitcoin::bech32::NoChecksum;
 use lampo_common::bitcoin::hashes::sha256::Hash as Sha256;
 use lampo_common::bitcoin::hashes::Hash;
 use lampo_common::bitcoin::secp256k1::PublicKey as pubkey;
+use lampo_common::bitcoin::secp256k1::{Secp256k1, Signing, Verification};
+use lampo_common::bitcoin::{Address, Network};
 use lampo_common::conf::LampoConf;
 use lampo_common::error;
+use lampo_common::hex::FromHex;
 use lampo_common::keys::LampoKeysManager;
 use lampo_common::ldk;
+use lampo_common::ldk::blinded_path::message::{
+    BlindedMessagePath, MessageContext, MessageForwardNode,
+};
+use lampo_common::ldk::invoice::{Fallback, PrivateRoute, RawTaggedField, TaggedField};
 use lampo_common::ldk::ln::channelmanager::{
     Bolt11InvoiceParameters, OptionalBolt11PaymentParams, OptionalOfferPaymentParams, PaymentId,
+    RecentPaymentDetails,
 };
 use lampo_common::ldk::ln::outbound_payment::{RecipientOnionFields, Retry};
+use lampo_common::ldk::offers::invoice::Bolt12Invoice;
 use lampo_common::ldk::offers::offer::Amount;
-use lampo_common::ldk::offers::offer::Offer;
-use lampo_common::ldk::routing::router::{PaymentParameters, RouteParameters};
-use lampo_common::ldk::sign::EntropySource;
+use lampo_common::ldk::offers::offer::{Offer, Quantity};
+use lampo_common::ldk::offers::refund::Refund;
+use lampo_common::ldk::onion_message::messenger::{
+    DefaultMessageRouter, Destination, MessageRouter, OnionMessagePath,
+};
+use lampo_common::ldk::routing::gossip::{NodeId, RoutingFees};
+use lampo_common::ldk::routing::router::{
+    PaymentParameters, Route, RouteHint, RouteHintHop, RouteParameters, RouteParametersConfig,
+    Router,
+};
+use lampo_common::ldk::sign::{EntropySource, NodeSigner, ReceiveAuthKey, Recipient};
 use lampo_common::ldk::types::payment::{PaymentHash, PaymentPreimage};
+use lampo_common::model::request::{GenerateInvoice, GenerateOffer, GetRoute};
+use lampo_common::model::request::{GetPayerProof, PaymentLimits};
+use lampo_common::model::response::PaymentState;
+use lampo_common::model::response::{CustomTlv, HoldInvoice, HoldInvoiceState};
+use lampo_common::model::response::{InvoiceInfo, OfferInfo, PaymentInfo, PaymentKind};
 
+use super::hold_invoice;
+use super::ledger;
+use super::payer_proof;
 use super::LampoChannelManager;
 use crate::chain::LampoChainManager;
+use crate::persistence::LampoPersistence;
 use crate::utils::logger::LampoLogger;
 
 pub struct OffchainManager {
@@ -41,6 +72,7 @@ pub struct OffchainManager {
     logger: Arc<LampoLogger>,
     lampo_conf: Arc<LampoConf>,
     chain_manager: Arc<LampoChainManager>,
+    persister: Arc<LampoPersistence>,
 }
 
 impl OffchainManager {
@@ -51,6 +83,7 @@ impl OffchainManager {
         logger: Arc<LampoLogger>,
         lampo_conf: Arc<LampoConf>,
         chain_manager: Arc<LampoChainManager>,
+        persister: Arc<LampoPersistence>,
     ) -> error::Result<Self> {
         Ok(Self {
             channel_manager,
@@ -58,6 +91,7 @@ impl OffchainManager {
             logger,
             lampo_conf,
             chain_manager,
+            persister,
         })
     }
 
@@ -65,27 +99,322 @@ impl OffchainManager {
     /// description.
     pub fn generate_invoice(
         &self,
-        amount_msat: Option<u64>,
-        description: &str,
-        expiring_in: u32,
+        request: &GenerateInvoice,
     ) -> error::Result<ldk::invoice::Bolt11Invoice> {
-        let description = ldk::invoice::Bolt11InvoiceDescription::Direct(
-            ldk::invoice::Description::new(description.to_string())
-                .map_err(|err| error::anyhow!("{:?}", err))?,
+        // LDK cannot claim a payment for a hash it did not derive, so the
+        // preimage goes to the ledger, where the handler finds it.
+        let payment_hash = match &request.preimage {
+            Some(preimage) => {
+                let preimage = <[u8; 32]>::from_hex(preimage)?;
+                let payment_hash = PaymentHash(Sha256::hash(&preimage).to_byte_array());
+                let key = lampo_common::hex::encode(payment_hash.0);
+                if ledger::load_invoice(&self.persister, &key)?.is_some() {
+                    error::bail!("an invoice for payment hash `{key}` already exists");
+                }
+                Some(payment_hash)
+            }
+            None => None,
+        };
+        self.create_invoice(request, payment_hash)
+    }
+
+    /// Build a bolt11 invoice and record it in the ledger. Without a
+    /// `payment_hash` LDK derives the preimage and claims on its own.
+    fn create_invoice(
+        &self,
+        request: &GenerateInvoice,
+        payment_hash: Option<PaymentHash>,
+    ) -> error::Result<ldk::invoice::Bolt11Invoice> {
+        if let Some(label) = &request.label {
+            ledger::reserve_label(
+                &self.persister,
+                ledger::INVOICE_LABELS_NAMESPACE,
+                label,
+                "an invoice",
+            )?;
+        }
+        let invoice = self.build_invoice(request, payment_hash);
+        if let (Err(_), Some(label)) = (&invoice, &request.label) {
+            ledger::release_label(&self.persister, ledger::INVOICE_LABELS_NAMESPACE, label);
+        }
+        let invoice = invoice?;
+
+        let description = &request.description;
+        let payment_hash = lampo_common::hex::encode(invoice.payment_hash().0);
+        let created_at = invoice.duration_since_epoch().as_secs();
+        let result = ledger::update_invoice(
+            &self.persister,
+            &payment_hash,
+            || InvoiceInfo::new(payment_hash.clone(), PaymentKind::Bolt11, created_at),
+            |entry| {
+                entry.bolt11 = Some(invoice.to_string());
+                entry.description = Some(description.to_owned());
+                entry.amount_msat = request.amount_msat;
+                entry.expires_at = Some(created_at + invoice.expiry_time().as_secs());
+                entry.label = request.label.clone();
+                entry.payment_preimage = request.preimage.clone();
+            },
         );
-        let invoice = self
+        if let Err(err) = result {
+            log::error!(target: "lampo::offchain", "storing invoice `{payment_hash}` in the ledger: {err}");
+        }
+        Ok(invoice)
+    }
+
+    /// Ask LDK for the invoice and add what it does not set itself.
+    fn build_invoice(
+        &self,
+        request: &GenerateInvoice,
+        payment_hash: Option<PaymentHash>,
+    ) -> error::Result<ldk::invoice::Bolt11Invoice> {
+        let description = &request.description;
+        let invoice_description = if request.description_hash_only {
+            ldk::invoice::Bolt11InvoiceDescription::Hash(ldk::invoice::Sha256(Sha256::hash(
+                description.as_bytes(),
+            )))
+        } else {
+            ldk::invoice::Bolt11InvoiceDescription::Direct(
+                ldk::invoice::Description::new(description.to_string())
+                    .map_err(|err| error::anyhow!("{:?}", err))?,
+            )
+        };
+        let fallback = request
+            .fallback_address
+            .as_deref()
+            .map(|address| self.fallback(address))
+            .transpose()?;
+        let mut invoice = self
             .channel_manager
             .manager()
             .create_bolt11_invoice(Bolt11InvoiceParameters {
-                amount_msats: amount_msat,
-                description,
-                invoice_expiry_delta_secs: Some(expiring_in),
+                amount_msats: request.amount_msat,
+                description: invoice_description,
+                invoice_expiry_delta_secs: Some(request.expiring_in.unwrap_or(10000)),
+                min_final_cltv_expiry_delta: request.min_final_cltv_expiry_delta,
+                payment_hash,
                 ..Default::default()
             })
             .map_err(|err| error::anyhow!("{:?}", err))?;
+        let route_hints = if request.expose_private_channels {
+            self.private_route_hints(&invoice)
+        } else {
+            Vec::new()
+        };
+        if fallback.is_some() || !route_hints.is_empty() {
+            invoice = self.extend_invoice(invoice, fallback, route_hints)?;
+        }
         Ok(invoice)
     }
 
+    /// The fallback field for an on-chain `address` of our network.
+    fn fallback(&self, address: &str) -> error::Result<Fallback> {
+        let address = Address::from_str(address)?.require_network(self.lampo_conf.network)?;
+        let fallback = match address.to_address_data() {
+            AddressData::P2pkh { pubkey_hash } => Fallback::PubKeyHash(pubkey_hash),
+            AddressData::P2sh { script_hash } => Fallback::ScriptHash(script_hash),
+            AddressData::Segwit { witness_program } => Fallback::SegWitProgram {
+                version: witness_program.version(),
+                program: witness_program.program().as_bytes().to_vec(),
+            },
+            _ => error::bail!("unsupported fallback address `{address}`"),
+        };
+        Ok(fallback)
+    }
+
+    /// A route hint for every usable unannounced channel `invoice` does
+    /// not already carry one for.
+    fn private_route_hints(&self, invoice: &ldk::invoice::Bolt11Invoice) -> Vec<RouteHint> {
+        let hinted = invoice
+            .route_hints()
+            .iter()
+            .flat_map(|hint| hint.0.iter().map(|hop| hop.short_channel_id))
+            .collect::<HashSet<u64>>();
+        self.channel_manager
+            .manager()
+            .list_usable_channels()
+            .into_iter()
+            .filter(|channel| !channel.is_announced)
+            .filter_map(|channel| {
+                let short_channel_id = channel.get_inbound_payment_scid()?;
+                if hinted.contains(&short_channel_id) {
+                    return None;
+                }
+                // Without the peer's forwarding policy the hint would be
+                // wrong, the payer learns it once the channel is ready.
+                let forwarding = channel.counterparty.forwarding_info?;
+                Some(RouteHint(vec![RouteHintHop {
+                    src_node_id: channel.counterparty.node_id,
+                    short_channel_id,
+                    fees: RoutingFees {
+                        base_msat: forwarding.fee_base_msat,
+                        proportional_millionths: forwarding.fee_proportional_millionths,
+                    },
+                    cltv_expiry_delta: forwarding.cltv_expiry_delta,
+                    htlc_minimum_msat: channel.inbound_htlc_minimum_msat,
+                    htlc_maximum_msat: channel.inbound_htlc_maximum_msat,
+                }]))
+            })
+            .collect()
+    }
+
+    /// Add the fields `create_bolt11_invoice` has no parameter for to
+    /// `invoice`, and sign it again with the node key.
+    fn extend_invoice(
+        &self,
+        invoice: ldk::invoice::Bolt11Invoice,
+        fallback: Option<Fallback>,
+        route_hints: Vec<RouteHint>,
+    ) -> error::Result<ldk::invoice::Bolt11Invoice> {
+        let (mut raw, _, _) = invoice.into_signed_raw().into_parts();
+        let fields = &mut raw.data.tagged_fields;
+        if let Some(fallback) = fallback {
+            fields.push(RawTaggedField::KnownSemantics(TaggedField::Fallback(
+                fallback,
+            )));
+        }
+        for hint in route_hints {
+            let route = PrivateRoute::new(hint).map_err(|err| error::anyhow!("{err:?}"))?;
+            fields.push(RawTaggedField::KnownSemantics(TaggedField::PrivateRoute(
+                route,
+            )));
+        }
+        let signature = self
+            .keys_manager
+            .sign_invoice(&raw, Recipient::Node)
+            .map_err(|_| error::anyhow!("signing the invoice failed"))?;
+        let signed = raw
+            .sign::<_, ()>(|_| Ok(signature))
+            .expect("signature is given");
+        ldk::invoice::Bolt11Invoice::from_signed(signed).map_err(|err| error::anyhow!("{err:?}"))
+    }
+
+    /// Create a hold invoice for `payment_hash`. LDK accepts payments for
+    /// it without knowing the preimage, and the handler parks them until
+    /// [`Self::settle_hold_invoice`] or [`Self::cancel_hold_invoice`].
+    pub fn hold_invoice(
+        &self,
+        payment_hash: PaymentHash,
+        amount_msat: Option<u64>,
+        description: &str,
+        expiring_in: u32,
+    ) -> error::Result<HoldInvoice> {
+        // Checked before building the invoice too, so a duplicate does not
+        // overwrite the ledger entry of the first one.
+        if hold_invoice::load(&self.persister, &payment_hash)?.is_some() {
+            error::bail!(
+                "a hold invoice for payment hash `{}` already exists",
+                lampo_common::hex::encode(payment_hash.0)
+            );
+        }
+        let request = GenerateInvoice {
+            amount_msat,
+            description: description.to_owned(),
+            expiring_in: Some(expiring_in),
+            ..Default::default()
+        };
+        let invoice = self.create_invoice(&request, Some(payment_hash))?;
+        hold_invoice::create(
+            &self.persister,
+            HoldInvoice {
+                payment_hash: lampo_common::hex::encode(payment_hash.0),
+                bolt11: invoice.to_string(),
+                state: HoldInvoiceState::Open,
+                amount_msat,
+                amount_received_msat: None,
+                claim_deadline: None,
+                payment_preimage: None,
+            },
+        )
+    }
+
+    /// Claim the parked payment of a hold invoice with its preimage.
+    pub fn settle_hold_invoice(
+        &self,
+        payment_preimage: PaymentPreimage,
+    ) -> error::Result<HoldInvoice> {
+        let payment_hash = PaymentHash(Sha256::hash(&payment_preimage.0).to_byte_array());
+        let preimage = lampo_common::hex::encode(payment_preimage.0);
+        let invoice = hold_invoice::transition(&self.persister, &payment_hash, |invoice| {
+            match invoice.state {
+                // Settling twice is allowed, so a caller that lost the
+                // response can retry.
+                HoldInvoiceState::Accepted | HoldInvoiceState::Settled => {}
+                state => error::bail!("cannot settle a hold invoice that is {state:?}"),
+            }
+            invoice.state = HoldInvoiceState::Settled;
+            invoice.payment_preimage = Some(preimage.clone());
+            Ok(())
+        })?;
+        self.channel_manager.manager().claim_funds(payment_preimage);
+
+        // `PaymentClaimed` carries no preimage for a hold invoice.
+        let hash = invoice.payment_hash.clone();
+        let result = ledger::update_invoice(
+            &self.persister,
+            &hash,
+            || InvoiceInfo::new(hash.clone(), PaymentKind::Bolt11, ledger::now()),
+            |entry| entry.payment_preimage = Some(preimage),
+        );
+        if let Err(err) = result {
+            log::error!(target: "lampo::offchain", "storing preimage of `{hash}` in the ledger: {err}");
+        }
+        Ok(invoice)
+    }
+
+    /// Cancel a hold invoice, failing back its payment if one is parked.
+    pub fn cancel_hold_invoice(&self, payment_hash: PaymentHash) -> error::Result<HoldInvoice> {
+        let invoice = hold_invoice::transition(&self.persister, &payment_hash, |invoice| {
+            if invoice.state == HoldInvoiceState::Settled {
+                error::bail!("cannot cancel a settled hold invoice");
+            }
+            invoice.state = HoldInvoiceState::Canceled;
+            Ok(())
+        })?;
+        self.channel_manager
+            .manager()
+            .fail_htlc_backwards(&payment_hash);
+        Ok(invoice)
+    }
+
+    /// Cancel every parked hold invoice payment that is close to its claim
+    /// deadline, and return the invoices cancelled.
+    pub fn cancel_due_hold_invoices(&self) -> error::Result<Vec<HoldInvoice>> {
+        let height = self.channel_manager.manager().current_best_block().height;
+        let mut cancelled = Vec::new();
+        for invoice in hold_invoice::list(&self.persister)? {
+            if !hold_invoice::is_due(&invoice, height) {
+                continue;
+            }
+            let payment_hash = PaymentHash(<[u8; 32]>::from_hex(&invoice.payment_hash)?);
+            // Checked again under the lock: a settle may have won meanwhile.
+            let result = hold_invoice::transition(&self.persister, &payment_hash, |invoice| {
+                if !hold_invoice::is_due(invoice, height) {
+                    error::bail!("hold invoice is {:?}", invoice.state);
+                }
+                invoice.state = HoldInvoiceState::Canceled;
+                Ok(())
+            });
+            match result {
+                Ok(invoice) => {
+                    log::warn!(
+                        target: "lampo::offchain",
+                        "hold invoice `{}` reached its claim deadline {:?} at height {height}, failing it back",
+                        invoice.payment_hash,
+                        invoice.claim_deadline
+                    );
+                    self.channel_manager
+                        .manager()
+                        .fail_htlc_backwards(&payment_hash);
+                    cancelled.push(invoice);
+                }
+                Err(err) => {
+                    log::debug!(target: "lampo::offchain", "not cancelling hold invoice `{}`: {err}", invoice.payment_hash);
+                }
+            }
+        }
+        Ok(cancelled)
+    }
+
     pub fn decode_invoice(&self, invoice_str: &str) -> error::Result<ldk::invoice::Bolt11Invoice> {
         // FIXME: we should be able to `?` on the error right?
         let invoice = invoice_str
@@ -101,12 +430,32 @@ impl OffchainManager {
         Ok(invoice)
     }
 
+    /// Decode a bech32 encoded BOLT 12 message LDK only reads from bytes,
+    /// such as an invoice (`lni`) or an invoice request (`lnr`).
+    pub fn decode_bolt12<T: TryFrom<Vec<u8>>>(&self, encoded: &str, hrp: &str) -> error::Result<T> {
+        // BOLT 12 strings carry no checksum, and may be split with `+`.
+        let encoded = encoded.split('+').map(str::trim).collect::<String>();
+        let parsed = CheckedHrpstring::new::<NoChecksum>(&encoded)
+            .map_err(|err| error::anyhow!("invalid bech32 string: {err}"))?;
+        if parsed.hrp().as_str() != hrp {
+            error::bail!("expected a `{hrp}` string, got `{}`", parsed.hrp());
+        }
+        T::try_from(parsed.byte_iter().collect())
+            .map_err(|_| error::anyhow!("Impossible decode the `{hrp}` string `{encoded}`"))
+    }
+
     pub fn pay_offer(
         &self,
         offer_str: &str,
         amount_msat: Option<u64>,
         payer_note: Option<String>,
+        limits: &PaymentLimits,
     ) -> error::Result<PaymentId> {
+        // The route is only computed once the BOLT 12 invoice comes back,
+        // inside LDK, where there is no way to exclude a channel.
+        if !limits.exclude.is_empty() {
+            error::bail!("`exclude` is not supported when paying an offer");
+        }
         // check if it is an invoice or an offer
         let offer_hash = Sha256::hash(offer_str.as_bytes());
         let payment_id = PaymentId(*offer_hash.as_ref());
@@ -130,18 +479,203 @@ impl OffchainManager {
                 payment_id,
                 OptionalOfferPaymentParams {
                     payer_note,
-                    retry_strategy: Retry::Timeout(std::time::Duration::from_secs(1)),
+                    retry_strategy: limits.retry(Retry::Timeout(Duration::from_secs(1))),
+                    route_params_config: limits
+                        .route_params_config(RouteParametersConfig::default(), Some(amount))?,
                     ..Default::default()
                 },
             )
             .map_err(|err| error::anyhow!("{:?}", err))?;
+        ledger::record_payment(&self.persister, &payment_id, |entry| {
+            entry.kind = PaymentKind::Bolt12;
+            entry.bolt = Some(offer_str.to_owned());
+            entry.destination = offer.issuer_signing_pubkey().map(|id| id.to_string());
+            entry.amount_msat = Some(amount);
+        });
         Ok(payment_id)
     }
 
+    /// Build an offer and record it in the ledger, so it can be listed and
+    /// disabled later.
+    pub fn generate_offer(&self, request: &GenerateOffer) -> error::Result<Offer> {
+        if let Some(label) = &request.label {
+            ledger::reserve_label(
+                &self.persister,
+                ledger::OFFER_LABELS_NAMESPACE,
+                label,
+                "an offer",
+            )?;
+        }
+        let offer = self.build_offer(request).and_then(|offer| {
+            // Nothing is at stake yet, so unlike a payment a storage failure
+            // fails the call: handing out an offer the ledger does not know
+            // about is worse than a retry.
+            ledger::store_offer(
+                &self.persister,
+                &OfferInfo {
+                    offer_id: lampo_common::hex::encode(offer.id().0),
+                    bolt12: offer.to_string(),
+                    description: request.description.clone(),
+                    amount_msat: request.amount_msat,
+                    created_at: ledger::now(),
+                    label: request.label.clone(),
+                    absolute_expiry: request.absolute_expiry,
+                    disabled: false,
+                },
+            )?;
+            Ok(offer)
+        });
+        if let (Err(_), Some(label)) = (&offer, &request.label) {
+            ledger::release_label(&self.persister, ledger::OFFER_LABELS_NAMESPACE, label);
+        }
+        offer
+    }
+
+    fn build_offer(&self, request: &GenerateOffer) -> error::Result<Offer> {
+        let manager = self.channel_manager.manager();
+        let builder = match request.paths {
+            Some(max_paths) => {
+                let router = LimitedPathsRouter {
+                    inner: DefaultMessageRouter::new(
+                        self.channel_manager.graph(),
+                        self.keys_manager.clone(),
+                    ),
+                    max_paths,
+                };
+                manager.create_offer_builder_using_router(router)
+            }
+            None => manager.create_offer_builder(),
+        };
+        let mut builder = builder.map_err(|err| error::anyhow!("{:?}", err))?;
+        if let Some(description) = request.description.clone() {
+            builder = builder.description(description);
+        }
+        if let Some(amount_msat) = request.amount_msat {
+            builder = builder.amount_msats(amount_msat);
+        }
+        if let Some(absolute_expiry) = request.absolute_expiry {
+            builder = builder.absolute_expiry(Duration::from_secs(absolute_expiry));
+        }
+        if let Some(quantity_max) = request.quantity_max {
+            let quantity = match NonZeroU64::new(quantity_max) {
+                Some(max) => Quantity::Bounded(max),
+                None => Quantity::Unbounded,
+            };
+            builder = builder.supported_quantity(quantity);
+        }
+        if let Some(issuer) = request.issuer.clone() {
+            builder = builder.issuer(issuer);
+        }
+        for chain in &request.chains {
+            builder = builder.chain(Network::from_str(chain)?);
+        }
+        let offer = builder
+            .build()
+            // FIXME: implement display error on top of the bolt12 error
+            .map_err(|err| error::anyhow!("{:?}", err))?;
+        Ok(offer)
+    }
+
+    /// Create a refund of `amount_msat` that expires in `expiring_in`
+    /// seconds. Whoever holds it can request the payment, and LDK pays the
+    /// invoice that comes back on its own.
+    pub fn create_refund(
+        &self,
+        amount_msat: u64,
+        description: Option<String>,
+        payer_note: Option<String>,
+        expiring_in: u32,
+    ) -> error::Result<(PaymentId, Refund)> {
+        let payment_id = PaymentId(self.keys_manager.get_secure_random_bytes());
+        let absolute_expiry = Duration::from_secs(ledger::now() + u64::from(expiring_in));
+        let mut builder = self
+            .channel_manager
+            .manager()
+            .create_refund_builder(
+                amount_msat,
+                absolute_expiry,
+                payment_id,
+                Retry::Timeout(Duration::from_secs(10)),
+                RouteParametersConfig::default(),
+            )
+            .map_err(|err| error::anyhow!("{:?}", err))?;
+        if let Some(description) = description {
+            builder = builder.description(description);
+        }
+        if let Some(payer_note) = payer_note {
+            builder = builder.payer_note(payer_note);
+        }
+        let refund = builder.build().map_err(|err| error::anyhow!("{:?}", err))?;
+        ledger::record_payment(&self.persister, &payment_id, |entry| {
+            entry.kind = PaymentKind::Refund;
+            entry.bolt = Some(refund.to_string());
+            entry.amount_msat = Some(amount_msat);
+        });
+        Ok((payment_id, refund))
+    }
+
+    /// Rebuild the payer proof of the BOLT 12 payment `request.payment_hash`
+    /// from the material stored when it settled.
+    pub fn payer_proof(&self, request: &GetPayerProof) -> error::Result<String> {
+        let payment_hash = PaymentHash(<[u8; 32]>::from_hex(&request.payment_hash)?);
+        let Some(record) = payer_proof::load(&self.persister, &payment_hash)? else {
+            error::bail!("no payer proof material for `{}`", request.payment_hash);
+        };
+        // The payer key is derived from the payment id, which only the
+        // ledger remembers.
+        let Some(payment) =
+            ledger::load_payments_by_hash(&self.persister, &request.payment_hash)?.pop()
+        else {
+            error::bail!("payment `{}` not found in the ledger", request.payment_hash);
+        };
+        let payment_id = PaymentId(<[u8; 32]>::from_hex(&payment.payment_id)?);
+        let expanded_key = self.keys_manager.get_expanded_key();
+        payer_proof::build(
+            &record,
+            &expanded_key,
+            payment_id,
+            &request.disclosed_types(),
+        )
+        .ok_or_else(|| {
+            error::anyhow!(
+                "payment `{}` cannot produce a payer proof",
+                request.payment_hash
+            )
+        })
+    }
+
+    /// Ask the creator of `refund_str` to pay it, by sending it an invoice.
+    pub fn request_refund_payment(&self, refund_str: &str) -> error::Result<Bolt12Invoice> {
+        let refund = Refund::from_str(refund_str).map_err(|err| error::anyhow!("{:?}", err))?;
+        let invoice = self
+            .channel_manager
+            .manager()
+            .request_refund_payment(&refund)
+            .map_err(|err| error::anyhow!("{:?}", err))?;
+
+        let payment_hash = lampo_common::hex::encode(invoice.payment_hash().0);
+        let created_at = invoice.created_at().as_secs();
+        let result = ledger::update_invoice(
+            &self.persister,
+            &payment_hash,
+            || InvoiceInfo::new(payment_hash.clone(), PaymentKind::Refund, created_at),
+            |entry| {
+                entry.description = Some(refund.description().to_string());
+                entry.amount_msat = Some(invoice.amount_msats());
+                entry.expires_at = Some(created_at + invoice.relative_expiry().as_secs());
+            },
+        );
+        if let Err(err) = result {
+            log::error!(target: "lampo::offchain", "storing refund invoice `{payment_hash}` in the ledger: {err}");
+        }
+        Ok(invoice)
+    }
+
     pub fn pay_invoice(
         &self,
         invoice_str: &str,
         amount_msat: Option<u64>,
+        limits: &PaymentLimits,
     ) -> error::Result<PaymentId> {
         // check if it is an invoice or an offer
         let invoice = self.decode_invoice(invoice_str)?;
@@ -154,22 +688,66 @@ impl OffchainManager {
         } else {
             amount_msat
         };
-        self.channel_manager
-            .manager()
-            .pay_for_bolt11_invoice(
-                &invoice,
-                payment_id,
-                amount_msat,
-                OptionalBolt11PaymentParams {
-                    retry_strategy: Retry::Attempts(10),
-                    ..Default::default()
-                },
-            )
-            .map_err(|err| error::anyhow!("{:?}", err))?;
+        let total_msat = invoice.amount_milli_satoshis().or(amount_msat);
+        let retry_strategy = limits.retry(Retry::Attempts(10));
+        if limits.exclude.is_empty() {
+            self.channel_manager
+                .manager()
+                .pay_for_bolt11_invoice(
+                    &invoice,
+                    payment_id,
+                    amount_msat,
+                    OptionalBolt11PaymentParams {
+                        retry_strategy,
+                        route_params_config: limits
+                            .route_params_config(RouteParametersConfig::default(), total_msat)?,
+                        ..Default::default()
+                    },
+                )
+                .map_err(|err| error::anyhow!("{:?}", err))?;
+        } else {
+            // `pay_for_bolt11_invoice` builds the route parameters itself,
+            // so an exclusion needs the payment sent by hand.
+            let Some(total_msat) = total_msat else {
+                error::bail!("the invoice has no amount, `amount` is required");
+            };
+            let mut route_params = self.invoice_route_params(&invoice, total_msat)?;
+            limits.apply(&mut route_params)?;
+            let (nodes, channels) = limits.exclusions()?;
+            route_params.payment_params.previously_failed_channels =
+                self.excluded_channels(&invoice.get_payee_pub_key(), &nodes, channels)?;
+            let mut onion =
+                RecipientOnionFields::secret_only(*invoice.payment_secret(), total_msat);
+            onion.payment_metadata = invoice.payment_metadata().cloned();
+            self.channel_manager
+                .manager()
+                .send_payment(
+                    invoice.payment_hash(),
+                    onion,
+                    payment_id,
+                    route_params,
+                    retry_strategy,
+                )
+                .map_err(|err| error::anyhow!("{:?}", err))?;
+        }
+        ledger::record_payment(&self.persister, &payment_id, |entry| {
+            entry.kind = PaymentKind::Bolt11;
+            entry.payment_hash = Some(lampo_common::hex::encode(invoice.payment_hash().0));
+            entry.bolt = Some(invoice_str.to_owned());
+            entry.destination = Some(invoice.get_payee_pub_key().to_string());
+            entry.amount_msat = invoice.amount_milli_satoshis().or(amount_msat);
+        });
         Ok(payment_id)
     }
 
-    pub fn keysend(&self, destination: pubkey, amount_msat: u64) -> error::Result<PaymentHash> {
+    pub fn keysend(
+        &self,
+        destination: pubkey,
+        amount_msat: u64,
+        custom_tlvs: Vec<(u64, Vec<u8>)>,
+        mpp: bool,
+        limits: &PaymentLimits,
+    ) -> error::Result<PaymentHash> {
         let payment_preimage = PaymentPreimage(
             self.chain_manager
                 .wallet_manager
@@ -181,25 +759,242 @@ impl OffchainManager {
         let PaymentPreimage(bytes) = payment_preimage;
         let payment_hash = PaymentHash(Sha256::hash(&bytes).to_byte_array());
         // The 40 here is the max CheckLockTimeVerify which locks the output of the transaction for a certain
-        // period of time.The false here stands for the allow_mpp, which is to allow the multi part route payments.
-        let route_params = RouteParameters {
-            payment_params: PaymentParameters::for_keysend(destination, 40, false),
+        // period of time. `mpp` allows the multi part route payments.
+        let mut route_params = RouteParameters {
+            payment_params: PaymentParameters::for_keysend(destination, 40, mpp),
             final_value_msat: amount_msat,
             max_total_routing_fee_msat: None,
         };
+        limits.apply(&mut route_params)?;
+        let (nodes, channels) = limits.exclusions()?;
+        route_params.payment_params.previously_failed_channels =
+            self.excluded_channels(&destination, &nodes, channels)?;
+        let sent_tlvs = custom_tlvs.iter().map(CustomTlv::from).collect::<Vec<_>>();
+        let onion = RecipientOnionFields::spontaneous_empty(amount_msat)
+            .with_custom_tlvs(custom_tlvs)
+            .map_err(|_| error::anyhow!("invalid custom TLV records"))?;
         log::info!("Initialised Keysend");
         let payment_result = self
             .channel_manager
             .manager()
             .send_spontaneous_payment(
                 Some(payment_preimage),
-                RecipientOnionFields::spontaneous_empty(amount_msat),
+                onion,
                 PaymentId(payment_hash.0),
                 route_params,
-                Retry::Timeout(Duration::from_secs(10)),
+                limits.retry(Retry::Timeout(Duration::from_secs(10))),
             )
             .map_err(|err| error::anyhow!("{:?}", err))?;
         log::info!("Keysend successfully done!");
+        ledger::record_payment(&self.persister, &PaymentId(payment_hash.0), |entry| {
+            entry.kind = PaymentKind::Keysend;
+            entry.payment_hash = Some(lampo_common::hex::encode(payment_hash.0));
+            entry.destination = Some(destination.to_string());
+            entry.amount_msat = Some(amount_msat);
+            entry.custom_tlvs = sent_tlvs;
+        });
         Ok(payment_result)
     }
+
+    /// Probe every path LDK would use to pay `amount_msat` to
+    /// `destination` by keysend. Returns the id of each probe; their
+    /// outcome comes back as `ProbeSuccessful` / `ProbeFailed` events,
+    /// which also update the scorer.
+    pub fn probe(&self, destination: pubkey, amount_msat: u64) -> error::Result<Vec<PaymentId>> {
+        // Same final CLTV delta as `keysend`, so the probe matches the payment.
+        let probes = self
+            .channel_manager
+            .manager()
+            .send_spontaneous_preflight_probes(destination, amount_msat, 40, None)
+            .map_err(|err| error::anyhow!("{:?}", err))?;
+        Ok(probes.into_iter().map(|(_, id)| id).collect())
+    }
+
+    /// Like [`Self::probe`], but along the routes a payment of the bolt11
+    /// `invoice_str` would take, route hints included.
+    pub fn probe_invoice(
+        &self,
+        invoice_str: &str,
+        amount_msat: Option<u64>,
+    ) -> error::Result<Vec<PaymentId>> {
+        let invoice = self.decode_invoice(invoice_str)?;
+        let Some(amount_msat) = invoice.amount_milli_satoshis().or(amount_msat) else {
+            error::bail!("the invoice has no amount, `amount_msat` is required");
+        };
+        let route_params = self.invoice_route_params(&invoice, amount_msat)?;
+        let probes = self
+            .channel_manager
+            .manager()
+            .send_preflight_probes(route_params, None)
+            .map_err(|err| error::anyhow!("{:?}", err))?;
+        Ok(probes.into_iter().map(|(_, id)| id).collect())
+    }
+
+    /// The route parameters of a payment of `amount_msat` to `invoice`,
+    /// route hints and features included.
+    fn invoice_route_params(
+        &self,
+        invoice: &ldk::invoice::Bolt11Invoice,
+        amount_msat: u64,
+    ) -> error::Result<RouteParameters> {
+        let mut payment_params = PaymentParameters::from_node_id(
+            invoice.get_payee_pub_key(),
+            invoice.min_final_cltv_expiry_delta() as u32,
+        )
+        .with_route_hints(invoice.route_hints())
+        .map_err(|_| error::anyhow!("invalid route hints in the invoice"))?;
+        if let Some(expires_at) = invoice.expires_at() {
+            payment_params = payment_params.with_expiry_time(expires_at.as_secs());
+        }
+        if let Some(features) = invoice.features() {
+            payment_params = payment_params
+                .with_bolt11_features(features.clone())
+                .map_err(|_| error::anyhow!("invalid features in the invoice"))?;
+        }
+        Ok(RouteParameters::from_payment_params_and_value(
+            payment_params,
+            amount_msat,
+        ))
+    }
+
+    /// The channels a payment to `destination` must not use: `channels`,
+    /// plus every channel of `nodes`, since the router has no notion of an
+    /// excluded node.
+    fn excluded_channels(
+        &self,
+        destination: &pubkey,
+        nodes: &[pubkey],
+        mut channels: Vec<u64>,
+    ) -> error::Result<Vec<u64>> {
+        if nodes.is_empty() {
+            return Ok(channels);
+        }
+        // Initializes the graph on first use.
+        self.channel_manager.network_graph();
+        let graph = self.channel_manager.graph();
+        let graph = graph.read_only();
+        for node in nodes {
+            if node == destination {
+                error::bail!("the destination `{node}` cannot be excluded");
+            }
+            if let Some(info) = graph.node(&NodeId::from_pubkey(node)) {
+                channels.extend(info.channels.iter().copied());
+            }
+        }
+        Ok(channels)
+    }
+
+    /// Ask the router for the route a keysend of `request.amount_msat` would
+    /// take right now: same router, scorer, first hops and in flight HTLCs
+    /// as a real payment, but nothing is sent.
+    pub fn find_route(&self, request: &GetRoute) -> error::Result<Route> {
+        let destination = request.destination()?;
+        // Initializes the graph and scorer on first use.
+        let router = self.channel_manager.network_graph();
+        // Same final CLTV delta as `keysend`.
+        let mut payment_params = PaymentParameters::from_node_id(destination, 40);
+        if let Some(max_cltv) = request.max_cltv_expiry_delta {
+            payment_params.max_total_cltv_expiry_delta = max_cltv;
+        }
+        payment_params.previously_failed_channels = self.excluded_channels(
+            &destination,
+            &request.exclude_nodes()?,
+            request.exclude_channels.clone(),
+        )?;
+
+        let route_params = RouteParameters {
+            payment_params,
+            final_value_msat: request.amount_msat,
+            max_total_routing_fee_msat: request.max_fee_msat,
+        };
+        let manager = self.channel_manager.manager();
+        let first_hops = manager.list_usable_channels();
+        let first_hops = first_hops.iter().collect::<Vec<_>>();
+        let route = router
+            .find_route(
+                &manager.get_our_node_id(),
+                &route_params,
+                Some(&first_hops),
+                manager.compute_inflight_htlcs(),
+            )
+            .map_err(|err| error::anyhow!("{}", err.err))?;
+        Ok(route)
+    }
+
+    /// The status of a payment we sent: its ledger entry, with the state
+    /// brought up to date from LDK while the payment is in flight. LDK
+    /// learns the outcome before the event reaches the ledger, and forgets
+    /// the payment a few timer ticks after it resolves, so neither source
+    /// is complete on its own.
+    pub fn payment_status(&self, payment_id: &str) -> error::Result<PaymentInfo> {
+        let id = <[u8; 32]>::from_hex(payment_id)?;
+        let live = self
+            .channel_manager
+            .manager()
+            .list_recent_payments()
+            .into_iter()
+            .find_map(|details| match details {
+                RecentPaymentDetails::AwaitingInvoice { payment_id, .. }
+                | RecentPaymentDetails::Pending { payment_id, .. }
+                    if payment_id.0 == id =>
+                {
+                    Some(PaymentState::Pending)
+                }
+                RecentPaymentDetails::Fulfilled { payment_id, .. } if payment_id.0 == id => {
+                    Some(PaymentState::Success)
+                }
+                // Abandoned payments still wait for their HTLCs to resolve,
+                // but they can no longer succeed.
+                RecentPaymentDetails::Abandoned { payment_id, .. } if payment_id.0 == id => {
+                    Some(PaymentState::Failure)
+                }
+                _ => None,
+            });
+        let Some(mut entry) = ledger::load_payment(&self.persister, payment_id)? else {
+            error::bail!("unknown payment `{payment_id}`");
+        };
+        if entry.status == PaymentState::Pending {
+            if let Some(state) = live {
+                entry.status = state;
+            }
+        }
+        Ok(entry)
+    }
+}
+
+/// Hands the offer builder at most `max_paths` of the blinded paths
+/// `inner` creates.
+struct LimitedPathsRouter<R: MessageRouter> {
+    inner: R,
+    max_paths: usize,
+}
+
+impl<R: MessageRouter> MessageRouter for LimitedPathsRouter<R> {
+    fn find_path(
+        &self,
+        sender: pubkey,
+        peers: Vec<pubkey>,
+        destination: Destination,
+    ) -> Result<OnionMessagePath, ()> {
+        self.inner.find_path(sender, peers, destination)
+    }
+
+    fn create_blinded_paths<T: Signing + Verification>(
+        &self,
+        recipient: pubkey,
+        local_node_receive_key: ReceiveAuthKey,
+        context: MessageContext,
+        peers: Vec<MessageForwardNode>,
+        secp_ctx: &Secp256k1<T>,
+    ) -> Result<Vec<BlindedMessagePath>, ()> {
+        let mut paths = self.inner.create_blinded_paths(
+            recipient,
+            local_node_receive_key,
+            context,
+            peers,
+            secp_ctx,
+        )?;
+        paths.truncate(self.max_paths);
+        Ok(paths)
+    }
 }
@@ -8,16 +8,21 @@
 //! Nothing else needs storing. The payer signing key is re-derived from the
 //! invoice's own payer metadata, and the `ExpandedKey` behind that derivation
 //! comes from the node seed.
+use std::str::FromStr;
 use std::sync::Arc;
 
 use lampo_common::error;
+use lampo_common::hex;
 use lampo_common::ldk::events::PaidBolt12Invoice;
 use lampo_common::ldk::io::{Cursor, ErrorKind};
 use lampo_common::ldk::ln::channelmanager::PaymentId;
 use lampo_common::ldk::ln::inbound_payment::ExpandedKey;
+use lampo_common::ldk::offers::offer::Offer;
+use lampo_common::ldk::offers::payer_proof::PayerProof;
 use lampo_common::ldk::types::payment::{PaymentHash, PaymentPreimage};
 use lampo_common::ldk::util::persist::KVStoreSync;
 use lampo_common::ldk::util::ser::{Readable, Writeable};
+use lampo_common::model::response::PayerProofVerification;
 use lampo_common::secp256k1::Secp256k1;
 
 use crate::persistence::LampoPersistence;
@@ -134,9 +139,9 @@ pub fn load(
     }
 }
 
-/// Build a bech32 payer proof disclosing only the fields LDK includes by
-/// default. Widening the disclosure is a separate, explicit decision: a proof
-/// cannot be narrowed once handed out.
+/// Build a bech32 payer proof disclosing the fields LDK includes by default,
+/// plus the invoice TLV types in `disclosed_types`. Widening the disclosure is
+/// a separate, explicit decision: a proof cannot be narrowed once handed out.
 ///
 /// Returns `None` when the payment cannot produce one (BOLT 11, or an async
 /// payment settled against a static invoice) — that is not an error, the payment
@@ -145,16 +150,25 @@ pub fn build(
     record: &PayerProofRecord,
     expanded_key: &ExpandedKey,
     payment_id: PaymentId,
+    disclosed_types: &[u64],
 ) -> Option<String> {
     let invoice = record.invoice.as_ref()?;
     let secp_ctx = Secp256k1::new();
 
-    let builder = invoice
+    let mut builder = invoice
         .prove_payer_derived(record.preimage, expanded_key, payment_id, &secp_ctx)
         .map_err(|err| {
             log::warn!(target: "lampo::payer_proof", "cannot build payer proof: {err:?}");
         })
         .ok()?;
+    for tlv_type in disclosed_types {
+        builder = builder
+            .include_type(*tlv_type)
+            .map_err(|err| {
+                log::warn!(target: "lampo::payer_proof", "cannot disclose type {tlv_type}: {err:?}");
+            })
+            .ok()?;
+    }
 
     builder
         .build_and_sign()
@@ -165,6 +179,57 @@ pub fn build(
         .ok()
 }
 
+/// Check `proof_str` against `offer_str`, and against `payment_hash` when
+/// given. LDK checks the preimage and both signatures while parsing, so what
+/// is left is that the invoice was signed by the key the offer commits to.
+pub fn verify(
+    proof_str: &str,
+    offer_str: &str,
+    payment_hash: Option<&str>,
+) -> error::Result<PayerProofVerification> {
+    let proof = match PayerProof::from_str(proof_str) {
+        Ok(proof) => proof,
+        Err(err) => return Ok(PayerProofVerification::invalid(format!("{err:?}"))),
+    };
+    let offer = Offer::from_str(offer_str).map_err(|err| error::anyhow!("{err:?}"))?;
+
+    let issuer_signing_pubkey = proof.issuer_signing_pubkey();
+    // Without an issuer id the offer is signed for by the last blinded hop of
+    // one of its paths.
+    let signed_by_offer = match offer.issuer_signing_pubkey() {
+        Some(pubkey) => pubkey == issuer_signing_pubkey,
+        None => offer.paths().iter().any(|path| {
+            path.blinded_hops()
+                .last()
+                .is_some_and(|hop| hop.blinded_node_id == issuer_signing_pubkey)
+        }),
+    };
+    if !signed_by_offer {
+        return Ok(PayerProofVerification::invalid(
+            "the invoice was not signed for this offer",
+        ));
+    }
+    let proof_hash = hex::encode(proof.payment_hash().0);
+    if payment_hash.is_some_and(|hash| hash != proof_hash) {
+        return Ok(PayerProofVerification::invalid(
+            "the proof commits to another payment hash",
+        ));
+    }
+
+    Ok(PayerProofVerification {
+        valid: true,
+        reason: None,
+        payment_hash: Some(proof_hash),
+        payment_preimage: Some(hex::encode(proof.payment_preimage().0)),
+        payer_id: Some(proof.payer_id().to_string()),
+        issuer_signing_pubkey: Some(issuer_signing_pubkey.to_string()),
+        description: proof.offer_description().map(|desc| desc.to_string()),
+        issuer: proof.offer_issuer().map(|issuer| issuer.to_string()),
+        amount_msat: proof.invoice_amount(),
+        created_at: proof.invoice_created_at().map(|at| at.as_secs()),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -195,4 +260,12 @@ mod tests {
         buf[0] = RECORD_VERSION + 1;
         assert!(PayerProofRecord::decode(&buf).is_err());
     }
+
+    #[test]
+    fn verify_rejects_garbage_proof() {
+        // The proof is checked first, so the offer is never parsed.
+        let verification = verify("lnp1garbage", "lno1", None).unwrap();
+        assert!(!verification.valid);
+        assert!(verification.reason.is_some());
+    }
 }
@@ -873,6 +873,10 @@ fn be_able_to_kesend_payments() {
             destination: info_cln.id.clone(),
             amount_msat: 100_00_000,
             timeout: Default::default(),
+            r#async: false,
+            limits: Default::default(),
+            mpp: false,
+            custom_tlvs: vec![],
         },
     );
     assert!(result.is_ok(), "{:?}", result);
@@ -982,6 +986,7 @@ fn test_closing_two_channels_without_channelid_fails() {
         request::CloseChannel {
             node_id: info_cln.id.to_string(),
             channel_id: None,
+            ..Default::default()
         },
     );
 
@@ -1078,6 +1083,7 @@ fn test_lampo_to_cln_close_channel_with_channel_id_success() {
         request::CloseChannel {
             node_id: info_cln.id.to_string(),
             channel_id: Some(channels.channels.first().unwrap().channel_id.to_string()),
+            ..Default::default()
         },
     );
     assert!(result.is_ok(), "{:?}", result);
@@ -1181,6 +1187,7 @@ fn test_lampo_to_cln_close_channel_without_channel_id_success() {
         request::CloseChannel {
             node_id: info_cln.id.to_string(),
             channel_id: None,
+            ..Default::default()
         },
     );
     assert!(result.is_ok(), "{:?}", result);
@@ -1228,6 +1235,7 @@ fn test_close_channel_without_opening_a_channel_fails() {
         request::CloseChannel {
             node_id: info_cln.id.to_string(),
             channel_id: None,
+            ..Default::default()
         },
     );
     assert!(result.is_err(), "{:?}", result);
@@ -4,15 +4,21 @@
 use std::str::FromStr;
 use std::sync::Arc;
 
+use lampo_common::bitcoin::consensus::encode::serialize_hex;
+use lampo_common::bitcoin::hashes::{sha256, Hash};
+use lampo_common::bitcoin::{Amount, FeeRate, ScriptBuf};
 use lampo_common::hex;
 use lampo_common::ldk::offers::payer_proof::PayerProof;
 
+use lampo_common::async_trait;
 use lampo_common::error;
 use lampo_common::event::ln::LightningEvent;
 use lampo_common::event::Event;
 use lampo_common::handler::Handler;
+use lampo_common::handler::{InboundPayment, PaymentHook, PaymentHookDecision};
 use lampo_common::json;
 use lampo_common::model::{request, response};
+use lampo_common::wallet::WalletManager;
 
 use lampo_testing::LampoTesting;
 use lampo_testing::{async_wait, prelude::*};
@@ -40,6 +46,165 @@ pub async fn init_connection_test_between_lampo() -> error::Result<()> {
     Ok(())
 }
 
+#[tokio_test_shutdown_timeout::test(1)]
+pub async fn set_and_get_channel_policy() -> error::Result<()> {
+    init();
+    let node = LampoTesting::tmp().await?;
+    let policy: response::ChannelPolicy = node
+        .lampod()
+        .call("getchannelpolicy", json::json!({}))
+        .await?;
+    assert_eq!(policy, response::ChannelPolicy::default());
+
+    let policy = request::ChannelPolicy {
+        min_channel_size_sat: Some(100_000),
+        denylist: vec![node.info.node_id.clone()],
+        require_anchors: true,
+        ..Default::default()
+    };
+    let _: response::ChannelPolicy = node
+        .lampod()
+        .call("setchannelpolicy", policy.clone())
+        .await?;
+    let current: response::ChannelPolicy = node
+        .lampod()
+        .call("getchannelpolicy", json::json!({}))
+        .await?;
+    assert_eq!(current, policy);
+
+    let inverted = request::ChannelPolicy {
+        min_channel_size_sat: Some(2),
+        max_channel_size_sat: Some(1),
+        ..Default::default()
+    };
+    let result: error::Result<response::ChannelPolicy> =
+        node.lampod().call("setchannelpolicy", inverted).await;
+    assert!(result.is_err(), "{:?}", result);
+
+    let bad_peer = request::ChannelPolicy {
+        allowlist: vec!["not a node id".to_owned()],
+        ..Default::default()
+    };
+    let result: error::Result<response::ChannelPolicy> =
+        node.lampod().call("setchannelpolicy", bad_peer).await;
+    assert!(result.is_err(), "{:?}", result);
+    Ok(())
+}
+
+#[tokio_test_shutdown_timeout::test(5)]
+pub async fn channel_policy_rejects_inbound_channels() -> error::Result<()> {
+    init();
+    let node1 = LampoTesting::tmp().await?;
+    let node2 = LampoTesting::new(node1.btc.clone()).await?;
+    let _: response::Connect = node2
+        .lampod()
+        .call(
+            "connect",
+            request::Connect {
+                node_id: node1.info.node_id.clone(),
+                addr: "127.0.0.1".to_owned(),
+                port: node1.port,
+            },
+        )
+        .await?;
+
+    let open = |amount| request::OpenChannel {
+        node_id: node2.info.node_id.clone(),
+        amount,
+        public: true,
+        port: None,
+        addr: None,
+        push_msat: None,
+    };
+    let policies = [
+        request::ChannelPolicy {
+            denylist: vec![node1.info.node_id.clone()],
+            ..Default::default()
+        },
+        request::ChannelPolicy {
+            min_channel_size_sat: Some(200_000),
+            ..Default::default()
+        },
+    ];
+    for policy in policies {
+        let _: response::ChannelPolicy = node2
+            .lampod()
+            .call("setchannelpolicy", policy.clone())
+            .await?;
+        let result: error::Result<json::Value> =
+            node1.lampod().call("fundchannel", open(100_000)).await;
+        assert!(
+            result.is_err(),
+            "{policy:?} accepted the channel: {result:?}"
+        );
+    }
+
+    let channels: response::Channels = node2.lampod().call("channels", json::json!({})).await?;
+    assert!(channels.channels.is_empty(), "{:?}", channels);
+    Ok(())
+}
+
+#[tokio_test_shutdown_timeout::test(1)]
+pub async fn sign_and_check_message() -> error::Result<()> {
+    init();
+    let node1 = LampoTesting::tmp().await?;
+    let node2 = LampoTesting::new(node1.btc.clone()).await?;
+
+    let message = "lampo owns this node";
+    let signed: response::SignMessage = node1
+        .lampod()
+        .call(
+            "signmessage",
+            request::SignMessage {
+                message: message.to_owned(),
+            },
+        )
+        .await?;
+
+    let checked: response::CheckMessage = node2
+        .lampod()
+        .call(
+            "checkmessage",
+            request::CheckMessage {
+                message: message.to_owned(),
+                zbase: signed.zbase.clone(),
+                pubkey: Some(node1.info.node_id.clone()),
+            },
+        )
+        .await?;
+    assert!(checked.verified);
+
+    // The signer is recovered from the signature alone, and node1 is
+    // trusted when it checks its own message.
+    let checked: response::CheckMessage = node1
+        .lampod()
+        .call(
+            "checkmessage",
+            request::CheckMessage {
+                message: message.to_owned(),
+                zbase: signed.zbase.clone(),
+                pubkey: None,
+            },
+        )
+        .await?;
+    assert_eq!(checked.pubkey, node1.info.node_id);
+    assert!(checked.verified);
+
+    let checked: response::CheckMessage = node2
+        .lampod()
+        .call(
+            "checkmessage",
+            request::CheckMessage {
+                message: "lampo owns another node".to_owned(),
+                zbase: signed.zbase,
+                pubkey: Some(node1.info.node_id.clone()),
+            },
+        )
+        .await?;
+    assert!(!checked.verified);
+    Ok(())
+}
+
 #[tokio_test_shutdown_timeout::test(5)]
 pub async fn fund_a_simple_channel_from() -> error::Result<()> {
     init();
@@ -244,6 +409,67 @@ pub async fn channel_peer_reconnects_after_disconnect() -> error::Result<()> {
     Ok(())
 }
 
+#[tokio_test_shutdown_timeout::test(5)]
+pub async fn set_channel_fee_and_config() -> error::Result<()> {
+    init();
+    let node1 = LampoTesting::tmp().await?;
+    let node2 = Arc::new(LampoTesting::new(node1.btc.clone()).await?);
+    node1.fund_channel_with(node2.clone(), 1_000_000).await?;
+
+    let configs: response::ChannelConfigs = node1
+        .lampod()
+        .call(
+            "setchannelfee",
+            request::SetChannelFee {
+                peer_ids: vec![node2.info.node_id.clone()],
+                fee_base_msat: Some(10),
+                fee_proportional_millionths: Some(250),
+                ..Default::default()
+            },
+        )
+        .await?;
+    assert_eq!(configs.channels.len(), 1);
+    let channel_id = configs.channels[0].channel_id.clone();
+
+    let configs: response::ChannelConfigs = node1
+        .lampod()
+        .call(
+            "setchannelconfig",
+            request::SetChannelConfig {
+                channel_ids: vec![channel_id.clone()],
+                cltv_expiry_delta: Some(144),
+                ..Default::default()
+            },
+        )
+        .await?;
+    let config = &configs.channels[0];
+    assert_eq!(config.fee_base_msat, 10, "a partial update keeps the fees");
+    assert_eq!(config.cltv_expiry_delta, 144);
+
+    let channels: response::Channels = node1.lampod().call("channels", json::json!({})).await?;
+    let channel = channels
+        .channels
+        .iter()
+        .find(|channel| channel.channel_id == channel_id)
+        .expect("the channel we just configured should be listed");
+    assert_eq!(channel.fee_proportional_millionths, Some(250));
+    assert_eq!(channel.cltv_expiry_delta, Some(144));
+
+    let unknown: error::Result<response::ChannelConfigs> = node1
+        .lampod()
+        .call(
+            "setchannelfee",
+            request::SetChannelFee {
+                channel_ids: vec!["00".repeat(32)],
+                fee_base_msat: Some(0),
+                ..Default::default()
+            },
+        )
+        .await;
+    assert!(unknown.is_err(), "{:?}", unknown);
+    Ok(())
+}
+
 #[tokio_test_shutdown_timeout::test(5)]
 pub async fn pay_invoice_simple_case_lampo() -> error::Result<()> {
     init();
@@ -262,6 +488,7 @@ pub async fn pay_invoice_simple_case_lampo() -> error::Result<()> {
                 description: "making sure that we can work betwen lampo version".to_owned(),
                 amount_msat: Some(100_000),
                 expiring_in: None,
+                ..Default::default()
             },
         )
         .await?;
@@ -277,6 +504,8 @@ pub async fn pay_invoice_simple_case_lampo() -> error::Result<()> {
                 amount: None,
                 bolt12: None,
                 timeout: Default::default(),
+                r#async: false,
+                limits: Default::default(),
             },
         )
         .await?;
@@ -295,158 +524,236 @@ pub async fn pay_invoice_simple_case_lampo() -> error::Result<()> {
 }
 
 #[tokio_test_shutdown_timeout::test(5)]
-pub async fn pay_offer_simple_case_lampo() -> error::Result<()> {
+pub async fn payments_are_recorded_in_the_ledger() -> error::Result<()> {
     init();
     let node1 = LampoTesting::tmp().await?;
     let btc = node1.btc.clone();
     let node2 = Arc::new(LampoTesting::new(btc.clone()).await?);
-
-    // There is a channel node1 -> node2
     node1.fund_channel_with(node2.clone(), 1_000_000).await?;
 
-    let offer: response::Offer = node2
+    let invoice: response::Invoice = node2
         .lampod()
         .call(
-            "offer",
-            request::GenerateOffer {
-                description: Some("making sure that we can work betwen lampo version".to_owned()),
+            "invoice",
+            request::GenerateInvoice {
+                description: "ledger".to_owned(),
                 amount_msat: Some(100_000),
+                expiring_in: None,
+                ..Default::default()
             },
         )
         .await?;
 
-    log::info!(target: &node1.info.node_id, "offer generated `{:?}`", offer);
+    let invoices: response::Invoices = node2
+        .lampod()
+        .call("listinvoices", request::ListInvoices::default())
+        .await?;
+    assert_eq!(invoices.invoices.len(), 1);
+    assert_eq!(invoices.invoices[0].status, response::InvoiceStatus::Unpaid);
+    let payment_hash = invoices.invoices[0].payment_hash.clone();
 
     let pay: response::PayResult = node1
         .lampod()
         .call(
             "pay",
             request::Pay {
-                invoice_str: offer.bolt12,
+                invoice_str: invoice.bolt11.clone(),
                 amount: None,
                 bolt12: None,
                 timeout: Default::default(),
+                r#async: false,
+                limits: Default::default(),
             },
         )
         .await?;
-    log::info!(target: &node2.info.node_id, "payment made `{:?}`", pay);
+    assert_eq!(pay.state, response::PaymentState::Success);
 
-    // Paying an offer must hand back a payer proof a third party can check
-    // against the payment we actually made.
-    let preimage = pay
-        .payment_preimage
-        .expect("a settled offer payment must expose its preimage");
-    // There is no separate verify entry point: LDK runs the checks while
-    // parsing, in `TryFrom<Vec<u8>> for PayerProof`. Parsing here is the
-    // verification, and it covers preimage against payment hash, the invoice
-    // signature against the issuer key, and the payer signature over the
-    // merkle root.
-    let proof = PayerProof::from_str(
-        &pay.payer_proof
-            .expect("a settled offer payment must expose a payer proof"),
-    )
-    .expect("the payer proof must verify");
+    let pays: response::Pays = node1
+        .lampod()
+        .call(
+            "listpays",
+            request::ListPays {
+                payment_hash: payment_hash.clone(),
+                ..Default::default()
+            },
+        )
+        .await?;
+    assert_eq!(pays.pays.len(), 1);
+    let paid = &pays.pays[0];
+    assert_eq!(paid.status, response::PaymentState::Success);
+    assert_eq!(paid.bolt.as_deref(), Some(invoice.bolt11.as_str()));
+    assert_eq!(paid.amount_msat, Some(100_000));
+    assert_eq!(paid.payment_preimage, pay.payment_preimage);
 
-    assert_eq!(
-        proof.payment_hash().to_string(),
-        pay.payment_hash.unwrap(),
-        "the proof must commit to the hash of the payment we made"
-    );
-    assert_eq!(
-        hex::encode(proof.payment_preimage().0),
-        preimage,
-        "the proof must carry the same preimage the RPC returned"
+    // The payee learns about the claim on its own event loop, so it can
+    // lag behind the payer's receipt.
+    async_wait!(
+        async {
+            let invoices: response::Invoices = node2
+                .lampod()
+                .call(
+                    "listinvoices",
+                    request::ListInvoices {
+                        status: Some(response::InvoiceStatus::Paid),
+                        ..Default::default()
+                    },
+                )
+                .await
+                .map_err(|_| ())?;
+            match invoices.invoices.first() {
+                Some(invoice) if invoice.payment_hash == payment_hash => Ok(()),
+                _ => Err(()),
+            }
+        },
+        1
     );
+
+    // A payment to a direct peer is not a forward for either node.
+    let forwards: response::Forwards = node2
+        .lampod()
+        .call("listforwards", request::ListForwards::default())
+        .await?;
+    assert!(forwards.forwards.is_empty());
+    let stats: response::ForwardStats = node2
+        .lampod()
+        .call("forwardstats", request::ForwardStats::default())
+        .await?;
+    assert_eq!(stats.settled, 0);
+    assert_eq!(stats.fee_msat, 0);
     Ok(())
 }
 
-#[tokio_test_shutdown_timeout::test(10)]
-pub async fn pay_offer_minimal_offer() -> error::Result<()> {
+#[tokio_test_shutdown_timeout::test(5)]
+pub async fn invoice_with_preimage_and_label() -> error::Result<()> {
     init();
     let node1 = LampoTesting::tmp().await?;
     let btc = node1.btc.clone();
     let node2 = Arc::new(LampoTesting::new(btc.clone()).await?);
-
     node1.fund_channel_with(node2.clone(), 1_000_000).await?;
 
-    let offer: response::Offer = node2
+    let preimage = [7u8; 32];
+    let payment_hash = hex::encode(sha256::Hash::hash(&preimage).to_byte_array());
+    let request = request::GenerateInvoice {
+        description: "a description only its hash travels with".to_owned(),
+        amount_msat: Some(100_000),
+        description_hash_only: true,
+        min_final_cltv_expiry_delta: Some(144),
+        preimage: Some(hex::encode(preimage)),
+        label: Some("order-42".to_owned()),
+        ..Default::default()
+    };
+    let invoice: response::Invoice = node2.lampod().call("invoice", request.clone()).await?;
+
+    let decoded: response::Decode = node1
         .lampod()
         .call(
-            "offer",
-            request::GenerateOffer {
-                description: None,
-                amount_msat: None,
+            "decode",
+            request::DecodeInvoice {
+                invoice_str: invoice.bolt11.clone(),
             },
         )
         .await?;
+    let response::Decode::Bolt11(decoded) = decoded else {
+        error::bail!("expected a bolt11 invoice, got `{decoded:?}`");
+    };
+    assert_eq!(decoded.payment_hash, Some(payment_hash));
+    assert!(decoded.description.is_none());
+    assert!(decoded.description_hash.is_some());
+    assert_eq!(decoded.min_final_cltv_expiry_delta, 144);
 
-    log::info!(target: &node2.info.node_id, "offer generated `{:?}`", offer);
+    // Labels are unique, a second invoice cannot reuse one.
+    let duplicate: error::Result<response::Invoice> = node2
+        .lampod()
+        .call(
+            "invoice",
+            request::GenerateInvoice {
+                preimage: None,
+                ..request
+            },
+        )
+        .await;
+    assert!(duplicate.is_err(), "a label must not be reused");
 
     let pay: response::PayResult = node1
         .lampod()
         .call(
             "pay",
             request::Pay {
-                invoice_str: offer.bolt12,
-                amount: Some(100_000),
+                invoice_str: invoice.bolt11,
+                amount: None,
                 bolt12: None,
                 timeout: Default::default(),
+                r#async: false,
+                limits: Default::default(),
             },
         )
         .await?;
-    log::info!(target: &node1.info.node_id, "payment made `{:?}`", pay);
     assert_eq!(pay.state, response::PaymentState::Success);
-    assert!(pay.payment_hash.is_some(), "Payment hash should be present");
-    assert_eq!(
-        pay.path.last().unwrap().node_id,
-        node2.info.node_id,
-        "Last hop should be to the destination node"
+    assert_eq!(pay.payment_preimage, Some(hex::encode(preimage)));
+
+    async_wait!(
+        async {
+            let invoices: response::Invoices = node2
+                .lampod()
+                .call(
+                    "listinvoices",
+                    request::ListInvoices {
+                        label: Some("order-42".to_owned()),
+                        ..Default::default()
+                    },
+                )
+                .await
+                .map_err(|_| ())?;
+            match invoices.invoices.first() {
+                Some(invoice) if invoice.status == response::InvoiceStatus::Paid => Ok(()),
+                _ => Err(()),
+            }
+        },
+        1
     );
     Ok(())
 }
 
-#[tokio_test_shutdown_timeout::test(10)]
-pub async fn decode_invoice() -> error::Result<()> {
+/// Refuses every payment above `max_msat`.
+struct AmountLimitHook {
+    max_msat: u64,
+}
+
+#[async_trait]
+impl PaymentHook for AmountLimitHook {
+    async fn on_payment(&self, payment: &InboundPayment) -> error::Result<PaymentHookDecision> {
+        if payment.amount_msat > self.max_msat {
+            return Ok(PaymentHookDecision::Fail("over the limit".to_owned()));
+        }
+        Ok(PaymentHookDecision::Defer)
+    }
+}
+
+#[tokio_test_shutdown_timeout::test(5)]
+pub async fn payment_hook_rejects_payment() -> error::Result<()> {
     init();
     let node1 = LampoTesting::tmp().await?;
     let btc = node1.btc.clone();
     let node2 = Arc::new(LampoTesting::new(btc.clone()).await?);
-
     node1.fund_channel_with(node2.clone(), 1_000_000).await?;
+    node2
+        .lampod()
+        .add_payment_hook(Arc::new(AmountLimitHook { max_msat: 50_000 }))
+        .await?;
 
     let invoice: response::Invoice = node2
         .lampod()
         .call(
             "invoice",
             request::GenerateInvoice {
-                description: "test decode".to_owned(),
+                description: "too expensive".to_owned(),
                 amount_msat: Some(100_000),
-                expiring_in: None,
-            },
-        )
-        .await?;
-
-    log::info!(target: &node2.info.node_id, "invoice generated `{:?}`", invoice);
-
-    let decode_result: response::Decode = node2
-        .lampod()
-        .call(
-            "decode",
-            request::DecodeInvoice {
-                invoice_str: invoice.bolt11.clone(),
+                label: Some("rejected".to_owned()),
+                ..Default::default()
             },
         )
         .await?;
-
-    let decode: response::Bolt11InvoiceInfo = match decode_result {
-        response::Decode::Bolt11(x) => x,
-        _ => panic!("Should be a bolt11 invoice"),
-    };
-
-    assert_eq!(decode.issuer_id.clone(), Some(node2.info.node_id.clone()));
-    log::info!(target: &node2.info.node_id, "decode offer `{:?}`", decode);
-
-    let pay: response::PayResult = node1
+    let pay: error::Result<response::PayResult> = node1
         .lampod()
         .call(
             "pay",
@@ -455,95 +762,1174 @@ pub async fn decode_invoice() -> error::Result<()> {
                 amount: None,
                 bolt12: None,
                 timeout: Default::default(),
+                r#async: false,
+                limits: Default::default(),
             },
         )
-        .await?;
-    log::info!(target: &node1.info.node_id, "Payment call result from node1: {:?}", pay);
+        .await;
+    if let Ok(pay) = pay {
+        assert_eq!(pay.state, response::PaymentState::Failure);
+    }
 
-    assert_eq!(pay.state, response::PaymentState::Success);
-    assert!(pay.payment_hash.is_some(), "Payment hash should be present");
-    assert_eq!(
-        pay.path.last().unwrap().node_id,
-        node2.info.node_id,
-        "Last hop should be to the destination node"
-    );
+    let invoices: response::Invoices = node2
+        .lampod()
+        .call(
+            "listinvoices",
+            request::ListInvoices {
+                label: Some("rejected".to_owned()),
+                ..Default::default()
+            },
+        )
+        .await?;
+    assert_eq!(invoices.invoices[0].status, response::InvoiceStatus::Unpaid);
     Ok(())
 }
 
-#[tokio_test_shutdown_timeout::test(10)]
-pub async fn decode_offer_hex() -> error::Result<()> {
+#[tokio_test_shutdown_timeout::test(5)]
+pub async fn async_pay_then_waitpay() -> error::Result<()> {
     init();
     let node1 = LampoTesting::tmp().await?;
     let btc = node1.btc.clone();
     let node2 = Arc::new(LampoTesting::new(btc.clone()).await?);
-
     node1.fund_channel_with(node2.clone(), 1_000_000).await?;
 
-    let offer: response::Offer = node2
+    let invoice: response::Invoice = node2
         .lampod()
         .call(
-            "offer",
-            request::GenerateOffer {
-                description: Some("test offer for decode".to_owned()),
+            "invoice",
+            request::GenerateInvoice {
+                description: "async pay".to_owned(),
+                amount_msat: Some(100_000),
+                expiring_in: None,
+                ..Default::default()
+            },
+        )
+        .await?;
+
+    let sent: response::PayResult = node1
+        .lampod()
+        .call(
+            "pay",
+            request::Pay {
+                invoice_str: invoice.bolt11,
+                amount: None,
+                bolt12: None,
+                timeout: Default::default(),
+                r#async: true,
+                limits: Default::default(),
+            },
+        )
+        .await?;
+    assert!(!sent.payment_id.is_empty());
+
+    let status: response::PaymentInfo = node1
+        .lampod()
+        .call(
+            "paystatus",
+            request::PayStatus {
+                payment_id: sent.payment_id.clone(),
+            },
+        )
+        .await?;
+    assert_eq!(status.payment_id, sent.payment_id);
+
+    let pay: response::PayResult = node1
+        .lampod()
+        .call(
+            "waitpay",
+            request::WaitPay {
+                payment_id: sent.payment_id.clone(),
+                timeout: Default::default(),
+            },
+        )
+        .await?;
+    assert_eq!(pay.state, response::PaymentState::Success);
+    assert!(pay.payment_preimage.is_some());
+
+    // Once settled, `waitpay` answers straight from the ledger.
+    let again: response::PayResult = node1
+        .lampod()
+        .call(
+            "waitpay",
+            request::WaitPay {
+                payment_id: sent.payment_id.clone(),
+                timeout: request::PayTimeout::Fast,
+            },
+        )
+        .await?;
+    assert_eq!(again.state, response::PaymentState::Success);
+    assert_eq!(again.payment_preimage, pay.payment_preimage);
+
+    let status: response::PaymentInfo = node1
+        .lampod()
+        .call(
+            "paystatus",
+            request::PayStatus {
+                payment_id: sent.payment_id,
+            },
+        )
+        .await?;
+    assert_eq!(status.status, response::PaymentState::Success);
+    assert!(!status.attempts.is_empty());
+    Ok(())
+}
+
+#[tokio_test_shutdown_timeout::test(5)]
+pub async fn hold_invoice_settle_and_cancel() -> error::Result<()> {
+    init();
+    let node1 = LampoTesting::tmp().await?;
+    let btc = node1.btc.clone();
+    let node2 = Arc::new(LampoTesting::new(btc.clone()).await?);
+    node1.fund_channel_with(node2.clone(), 1_000_000).await?;
+
+    let preimage = [7u8; 32];
+    let payment_hash = hex::encode(sha256::Hash::hash(&preimage).to_byte_array());
+    let hold: response::HoldInvoice = node2
+        .lampod()
+        .call(
+            "holdinvoice",
+            request::GenerateHoldInvoice {
+                payment_hash: payment_hash.clone(),
+                amount_msat: Some(100_000),
+                description: "escrow".to_owned(),
+                expiring_in: None,
+            },
+        )
+        .await?;
+    assert_eq!(hold.state, response::HoldInvoiceState::Open);
+
+    let mut events = node2.lampod().handler().events();
+    let sent: response::PayResult = node1
+        .lampod()
+        .call(
+            "pay",
+            request::Pay {
+                invoice_str: hold.bolt11,
+                amount: None,
+                bolt12: None,
+                timeout: Default::default(),
+                r#async: true,
+                limits: Default::default(),
+            },
+        )
+        .await?;
+
+    // The HTLC is parked instead of failed back.
+    while let Some(event) = events.recv().await {
+        if let Event::Lightning(LightningEvent::HoldInvoiceEvent {
+            payment_hash: hash,
+            state,
+            ..
+        }) = event
+        {
+            assert_eq!(hash, payment_hash);
+            assert_eq!(state, response::HoldInvoiceState::Accepted);
+            break;
+        }
+    }
+
+    let settled: response::HoldInvoice = node2
+        .lampod()
+        .call(
+            "holdinvoice_settle",
+            request::SettleHoldInvoice {
+                payment_preimage: hex::encode(preimage),
+            },
+        )
+        .await?;
+    assert_eq!(settled.state, response::HoldInvoiceState::Settled);
+
+    let pay: response::PayResult = node1
+        .lampod()
+        .call(
+            "waitpay",
+            request::WaitPay {
+                payment_id: sent.payment_id,
+                timeout: Default::default(),
+            },
+        )
+        .await?;
+    assert_eq!(pay.state, response::PaymentState::Success);
+    assert_eq!(pay.payment_preimage, Some(hex::encode(preimage)));
+
+    // A second hold invoice is cancelled while its payment is parked.
+    let preimage = [8u8; 32];
+    let payment_hash = hex::encode(sha256::Hash::hash(&preimage).to_byte_array());
+    let hold: response::HoldInvoice = node2
+        .lampod()
+        .call(
+            "holdinvoice",
+            request::GenerateHoldInvoice {
+                payment_hash: payment_hash.clone(),
+                amount_msat: Some(100_000),
+                description: "escrow".to_owned(),
+                expiring_in: None,
+            },
+        )
+        .await?;
+    let sent: response::PayResult = node1
+        .lampod()
+        .call(
+            "pay",
+            request::Pay {
+                invoice_str: hold.bolt11,
+                amount: None,
+                bolt12: None,
+                timeout: Default::default(),
+                r#async: true,
+                limits: Default::default(),
+            },
+        )
+        .await?;
+    while let Some(event) = events.recv().await {
+        if let Event::Lightning(LightningEvent::HoldInvoiceEvent {
+            payment_hash: hash, ..
+        }) = event
+        {
+            if hash == payment_hash {
+                break;
+            }
+        }
+    }
+    let cancelled: response::HoldInvoice = node2
+        .lampod()
+        .call(
+            "holdinvoice_cancel",
+            request::CancelHoldInvoice { payment_hash },
+        )
+        .await?;
+    assert_eq!(cancelled.state, response::HoldInvoiceState::Canceled);
+
+    let pay: response::PayResult = node1
+        .lampod()
+        .call(
+            "waitpay",
+            request::WaitPay {
+                payment_id: sent.payment_id,
+                timeout: Default::default(),
+            },
+        )
+        .await?;
+    assert_eq!(pay.state, response::PaymentState::Failure);
+    Ok(())
+}
+
+#[tokio_test_shutdown_timeout::test(5)]
+pub async fn pay_offer_simple_case_lampo() -> error::Result<()> {
+    init();
+    let node1 = LampoTesting::tmp().await?;
+    let btc = node1.btc.clone();
+    let node2 = Arc::new(LampoTesting::new(btc.clone()).await?);
+
+    // There is a channel node1 -> node2
+    node1.fund_channel_with(node2.clone(), 1_000_000).await?;
+
+    let offer: response::Offer = node2
+        .lampod()
+        .call(
+            "offer",
+            request::GenerateOffer {
+                description: Some("making sure that we can work betwen lampo version".to_owned()),
+                amount_msat: Some(100_000),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+    log::info!(target: &node1.info.node_id, "offer generated `{:?}`", offer);
+
+    let pay: response::PayResult = node1
+        .lampod()
+        .call(
+            "pay",
+            request::Pay {
+                invoice_str: offer.bolt12,
+                amount: None,
+                bolt12: None,
+                timeout: Default::default(),
+                r#async: false,
+                limits: Default::default(),
+            },
+        )
+        .await?;
+    log::info!(target: &node2.info.node_id, "payment made `{:?}`", pay);
+
+    // Paying an offer must hand back a payer proof a third party can check
+    // against the payment we actually made.
+    let preimage = pay
+        .payment_preimage
+        .expect("a settled offer payment must expose its preimage");
+    // There is no separate verify entry point: LDK runs the checks while
+    // parsing, in `TryFrom<Vec<u8>> for PayerProof`. Parsing here is the
+    // verification, and it covers preimage against payment hash, the invoice
+    // signature against the issuer key, and the payer signature over the
+    // merkle root.
+    let proof = PayerProof::from_str(
+        &pay.payer_proof
+            .expect("a settled offer payment must expose a payer proof"),
+    )
+    .expect("the payer proof must verify");
+
+    assert_eq!(
+        proof.payment_hash().to_string(),
+        pay.payment_hash.unwrap(),
+        "the proof must commit to the hash of the payment we made"
+    );
+    assert_eq!(
+        hex::encode(proof.payment_preimage().0),
+        preimage,
+        "the proof must carry the same preimage the RPC returned"
+    );
+    Ok(())
+}
+
+#[tokio_test_shutdown_timeout::test(5)]
+pub async fn pay_offer_rebuild_payer_proof() -> error::Result<()> {
+    init();
+    let node1 = LampoTesting::tmp().await?;
+    let btc = node1.btc.clone();
+    let node2 = Arc::new(LampoTesting::new(btc.clone()).await?);
+    node1.fund_channel_with(node2.clone(), 1_000_000).await?;
+
+    let offer: response::Offer = node2
+        .lampod()
+        .call(
+            "offer",
+            request::GenerateOffer {
+                description: Some("making sure that we can work betwen lampo version".to_owned()),
+                amount_msat: Some(100_000),
+                ..Default::default()
+            },
+        )
+        .await?;
+    let pay: response::PayResult = node1
+        .lampod()
+        .call(
+            "pay",
+            request::Pay {
+                invoice_str: offer.bolt12.clone(),
+                amount: None,
+                bolt12: None,
+                timeout: Default::default(),
+                r#async: false,
+                limits: Default::default(),
+            },
+        )
+        .await?;
+    assert_eq!(pay.state, response::PaymentState::Success);
+
+    // The proof can be rebuilt later from the stored material, this time
+    // disclosing the offer description.
+    let payment_hash = pay.payment_hash.unwrap();
+    let rebuilt: response::PayerProof = node1
+        .lampod()
+        .call(
+            "getpayerproof",
+            request::GetPayerProof {
+                payment_hash: payment_hash.clone(),
+                include_description: true,
+                ..Default::default()
+            },
+        )
+        .await?;
+    let verification: response::PayerProofVerification = node1
+        .lampod()
+        .call(
+            "verifypayerproof",
+            request::VerifyPayerProof {
+                payer_proof: rebuilt.payer_proof,
+                offer: offer.bolt12,
+                payment_hash: Some(payment_hash.clone()),
+            },
+        )
+        .await?;
+    assert!(verification.valid, "{:?}", verification.reason);
+    assert_eq!(verification.payment_hash, Some(payment_hash));
+    assert_eq!(
+        verification.description.as_deref(),
+        Some("making sure that we can work betwen lampo version")
+    );
+    Ok(())
+}
+
+#[tokio_test_shutdown_timeout::test(5)]
+pub async fn list_and_disable_offers() -> error::Result<()> {
+    init();
+    let node1 = LampoTesting::tmp().await?;
+    let btc = node1.btc.clone();
+    let node2 = Arc::new(LampoTesting::new(btc.clone()).await?);
+    node1.fund_channel_with(node2.clone(), 1_000_000).await?;
+
+    let expiry = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs()
+        + 3600;
+    let offer: response::Offer = node2
+        .lampod()
+        .call(
+            "offer",
+            request::GenerateOffer {
+                description: Some("coffee".to_owned()),
+                amount_msat: Some(100_000),
+                label: Some("coffee".to_owned()),
+                absolute_expiry: Some(expiry),
+                quantity_max: Some(0),
+                issuer: Some("the shop".to_owned()),
+                paths: Some(1),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+    let offers: response::Offers = node2
+        .lampod()
+        .call(
+            "listoffers",
+            request::ListOffers {
+                label: Some("coffee".to_owned()),
+                active_only: true,
+                ..Default::default()
+            },
+        )
+        .await?;
+    assert_eq!(offers.offers.len(), 1);
+    assert_eq!(offers.offers[0].offer_id, offer.offer_id);
+
+    let disabled: response::OfferInfo = node2
+        .lampod()
+        .call(
+            "disableoffer",
+            request::DisableOffer {
+                offer_id: offer.offer_id.clone(),
+            },
+        )
+        .await?;
+    assert!(disabled.disabled);
+    let offers: response::Offers = node2
+        .lampod()
+        .call(
+            "listoffers",
+            request::ListOffers {
+                active_only: true,
+                ..Default::default()
+            },
+        )
+        .await?;
+    assert!(offers.offers.is_empty());
+
+    // The invoice request is still answered, the payment is what fails.
+    let pay: response::PayResult = node1
+        .lampod()
+        .call(
+            "pay",
+            request::Pay {
+                invoice_str: offer.bolt12,
+                amount: None,
+                bolt12: None,
+                timeout: Default::default(),
+                r#async: false,
+                limits: Default::default(),
+            },
+        )
+        .await?;
+    assert_eq!(pay.state, response::PaymentState::Failure);
+    let status: response::PaymentInfo = node1
+        .lampod()
+        .call(
+            "paystatus",
+            request::PayStatus {
+                payment_id: pay.payment_id,
+            },
+        )
+        .await?;
+    assert_eq!(status.status, response::PaymentState::Failure);
+    let reason = status.failure_reason.unwrap_or_default();
+    assert!(
+        reason.starts_with("Payment was rejected by the recipient"),
+        "{reason}"
+    );
+    Ok(())
+}
+
+#[tokio_test_shutdown_timeout::test(5)]
+pub async fn pay_refund_simple_case_lampo() -> error::Result<()> {
+    init();
+    let node1 = LampoTesting::tmp().await?;
+    let btc = node1.btc.clone();
+    let node2 = Arc::new(LampoTesting::new(btc.clone()).await?);
+
+    // There is a channel node1 -> node2, so node1 is the one refunding.
+    node1.fund_channel_with(node2.clone(), 1_000_000).await?;
+
+    let refund: response::Refund = node1
+        .lampod()
+        .call(
+            "refund",
+            request::GenerateRefund {
+                amount_msat: 100_000,
+                description: Some("refund for order 42".to_owned()),
+                payer_note: None,
+                expiring_in: None,
+            },
+        )
+        .await?;
+    assert!(refund.bolt12.starts_with("lnr"));
+
+    let decoded: json::Value = node2
+        .lampod()
+        .call(
+            "decode",
+            request::DecodeInvoice {
+                invoice_str: refund.bolt12.clone(),
+            },
+        )
+        .await?;
+    assert_eq!(decoded["amount_msat"], 100_000);
+
+    let invoice: response::RefundInvoice = node2
+        .lampod()
+        .call(
+            "requestrefundpayment",
+            request::RequestRefundPayment {
+                refund: refund.bolt12,
+            },
+        )
+        .await?;
+    assert_eq!(invoice.amount_msat, 100_000);
+
+    // node1 pays the invoice it gets back without being asked to.
+    let pay: response::PayResult = node1
+        .lampod()
+        .call(
+            "waitpay",
+            request::WaitPay {
+                payment_id: refund.payment_id,
+                timeout: Default::default(),
+            },
+        )
+        .await?;
+    assert_eq!(pay.state, response::PaymentState::Success);
+    assert_eq!(pay.payment_hash, Some(invoice.payment_hash.clone()));
+
+    async_wait!(
+        async {
+            let invoices: response::Invoices = node2
+                .lampod()
+                .call(
+                    "listinvoices",
+                    request::ListInvoices {
+                        status: Some(response::InvoiceStatus::Paid),
+                        payment_hash: Some(invoice.payment_hash.clone()),
+                        ..Default::default()
+                    },
+                )
+                .await
+                .map_err(|_| ())?;
+            match invoices.invoices.first() {
+                Some(invoice) if invoice.kind == response::PaymentKind::Refund => Ok(()),
+                _ => Err(()),
+            }
+        },
+        1
+    );
+    Ok(())
+}
+
+#[tokio_test_shutdown_timeout::test(10)]
+pub async fn pay_offer_minimal_offer() -> error::Result<()> {
+    init();
+    let node1 = LampoTesting::tmp().await?;
+    let btc = node1.btc.clone();
+    let node2 = Arc::new(LampoTesting::new(btc.clone()).await?);
+
+    node1.fund_channel_with(node2.clone(), 1_000_000).await?;
+
+    let offer: response::Offer = node2
+        .lampod()
+        .call(
+            "offer",
+            request::GenerateOffer {
+                description: None,
+                amount_msat: None,
+                ..Default::default()
+            },
+        )
+        .await?;
+
+    log::info!(target: &node2.info.node_id, "offer generated `{:?}`", offer);
+
+    let pay: response::PayResult = node1
+        .lampod()
+        .call(
+            "pay",
+            request::Pay {
+                invoice_str: offer.bolt12,
+                amount: Some(100_000),
+                bolt12: None,
+                timeout: Default::default(),
+                r#async: false,
+                limits: Default::default(),
+            },
+        )
+        .await?;
+    log::info!(target: &node1.info.node_id, "payment made `{:?}`", pay);
+    assert_eq!(pay.state, response::PaymentState::Success);
+    assert!(pay.payment_hash.is_some(), "Payment hash should be present");
+    assert_eq!(
+        pay.path.last().unwrap().node_id,
+        node2.info.node_id,
+        "Last hop should be to the destination node"
+    );
+    Ok(())
+}
+
+#[tokio_test_shutdown_timeout::test(10)]
+pub async fn decode_invoice() -> error::Result<()> {
+    init();
+    let node1 = LampoTesting::tmp().await?;
+    let btc = node1.btc.clone();
+    let node2 = Arc::new(LampoTesting::new(btc.clone()).await?);
+
+    node1.fund_channel_with(node2.clone(), 1_000_000).await?;
+
+    let invoice: response::Invoice = node2
+        .lampod()
+        .call(
+            "invoice",
+            request::GenerateInvoice {
+                description: "test decode".to_owned(),
+                amount_msat: Some(100_000),
+                expiring_in: None,
+                ..Default::default()
+            },
+        )
+        .await?;
+
+    log::info!(target: &node2.info.node_id, "invoice generated `{:?}`", invoice);
+
+    let decode_result: response::Decode = node2
+        .lampod()
+        .call(
+            "decode",
+            request::DecodeInvoice {
+                invoice_str: invoice.bolt11.clone(),
+            },
+        )
+        .await?;
+
+    let decode: response::Bolt11InvoiceInfo = match decode_result {
+        response::Decode::Bolt11(x) => x,
+        _ => panic!("Should be a bolt11 invoice"),
+    };
+
+    assert_eq!(decode.issuer_id.clone(), Some(node2.info.node_id.clone()));
+    assert_eq!(decode.amount_msat, Some(100_000));
+    assert!(decode.signature_valid);
+    assert!(decode.payment_hash.is_some());
+    assert!(decode.payment_secret.is_some());
+    log::info!(target: &node2.info.node_id, "decode offer `{:?}`", decode);
+
+    let pay: response::PayResult = node1
+        .lampod()
+        .call(
+            "pay",
+            request::Pay {
+                invoice_str: invoice.bolt11,
+                amount: None,
+                bolt12: None,
+                timeout: Default::default(),
+                r#async: false,
+                limits: Default::default(),
+            },
+        )
+        .await?;
+    log::info!(target: &node1.info.node_id, "Payment call result from node1: {:?}", pay);
+
+    assert_eq!(pay.state, response::PaymentState::Success);
+    assert!(pay.payment_hash.is_some(), "Payment hash should be present");
+    assert_eq!(
+        pay.path.last().unwrap().node_id,
+        node2.info.node_id,
+        "Last hop should be to the destination node"
+    );
+    Ok(())
+}
+
+#[tokio_test_shutdown_timeout::test(10)]
+pub async fn decode_offer_hex() -> error::Result<()> {
+    init();
+    let node1 = LampoTesting::tmp().await?;
+    let btc = node1.btc.clone();
+    let node2 = Arc::new(LampoTesting::new(btc.clone()).await?);
+
+    node1.fund_channel_with(node2.clone(), 1_000_000).await?;
+
+    let offer: response::Offer = node2
+        .lampod()
+        .call(
+            "offer",
+            request::GenerateOffer {
+                description: Some("test offer for decode".to_owned()),
                 amount_msat: Some(100_000),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+    log::info!(target: &node2.info.node_id, "offer generated `{:?}`", offer);
+
+    let decode_result: response::Decode = node2
+        .lampod()
+        .call(
+            "decode",
+            request::DecodeInvoice {
+                invoice_str: offer.bolt12.clone(),
+            },
+        )
+        .await?;
+
+    let decode: response::Bolt12InvoiceInfo = match decode_result {
+        response::Decode::Bolt12(x) => x,
+        _ => panic!("Should be a bolt12 invoice"),
+    };
+
+    assert!(!decode.offer_id.is_empty(), "Offer ID should be present");
+    assert_eq!(decode.network, "regtest", "Network should be regtest");
+    assert_eq!(
+        decode.description,
+        Some("test offer for decode".to_owned()),
+        "Description should match"
+    );
+
+    log::info!(target: &node1.info.node_id, "Successfully decoded offer with ID: {}", decode.offer_id);
+
+    let pay: response::PayResult = node1
+        .lampod()
+        .call(
+            "pay",
+            request::Pay {
+                invoice_str: offer.bolt12,
+                amount: None,
+                bolt12: None,
+                timeout: Default::default(),
+                r#async: false,
+                limits: Default::default(),
+            },
+        )
+        .await?;
+
+    assert_eq!(
+        pay.state,
+        response::PaymentState::Success,
+        "Payment should succeed"
+    );
+    assert!(pay.payment_hash.is_some(), "Payment hash should be present");
+    assert!(!pay.path.is_empty(), "Payment path should not be empty");
+    assert_eq!(
+        pay.path.last().unwrap().node_id,
+        node2.info.node_id,
+        "Last hop should be to the destination node"
+    );
+
+    log::info!(target: &node1.info.node_id, "Payment completed successfully: {:?}", pay);
+    Ok(())
+}
+
+#[tokio_test_shutdown_timeout::test(5)]
+pub async fn fundchannel_start_and_cancel() -> error::Result<()> {
+    init();
+    let node1 = LampoTesting::tmp().await?;
+    let node2 = LampoTesting::new(node1.btc.clone()).await?;
+
+    let start: response::FundChannelStart = node1
+        .lampod()
+        .call(
+            "fundchannel_start",
+            request::OpenChannel {
+                node_id: node2.info.node_id.clone(),
+                addr: Some("127.0.0.1".to_owned()),
+                port: Some(node2.port),
+                amount: 500_000,
+                push_msat: None,
+                public: true,
+            },
+        )
+        .await?;
+    assert_eq!(start.amount, 500_000);
+    assert_eq!(start.node_id, node2.info.node_id);
+
+    // Nothing is funded from our wallet while the channel waits.
+    let missing: error::Result<response::FundChannelComplete> = node1
+        .lampod()
+        .call(
+            "fundchannel_complete",
+            request::FundChannelComplete {
+                temporary_channel_id: start.temporary_channel_id.clone(),
+                psbt: None,
+                tx: None,
+            },
+        )
+        .await;
+    assert!(missing.is_err(), "{missing:?}");
+
+    let cancel = request::FundChannelCancel {
+        temporary_channel_id: start.temporary_channel_id.clone(),
+    };
+    let cancelled: response::FundChannelCancel = node1
+        .lampod()
+        .call("fundchannel_cancel", cancel.clone())
+        .await?;
+    assert_eq!(cancelled.temporary_channel_id, cancel.temporary_channel_id);
+    assert_eq!(cancelled.node_id, node2.info.node_id);
+    let again: error::Result<response::FundChannelCancel> =
+        node1.lampod().call("fundchannel_cancel", cancel).await;
+    assert!(
+        again.is_err(),
+        "a cancelled channel cannot be cancelled twice"
+    );
+    Ok(())
+}
+
+#[tokio_test_shutdown_timeout::test(5)]
+pub async fn fundchannel_start_and_complete() -> error::Result<()> {
+    init();
+    let node1 = LampoTesting::tmp().await?;
+    let node2 = LampoTesting::new(node1.btc.clone()).await?;
+    // Stands for the external wallet paying the channel.
+    let funder = LampoTesting::new(node1.btc.clone()).await?;
+
+    let start: response::FundChannelStart = node1
+        .lampod()
+        .call(
+            "fundchannel_start",
+            request::OpenChannel {
+                node_id: node2.info.node_id.clone(),
+                addr: Some("127.0.0.1".to_owned()),
+                port: Some(node2.port),
+                amount: 500_000,
+                push_msat: None,
+                public: true,
+            },
+        )
+        .await?;
+
+    let best_block = funder.wallet.wallet_tips().await?;
+    let transaction = funder
+        .wallet
+        .create_transaction(
+            ScriptBuf::from_hex(&start.scriptpubkey)?,
+            Amount::from_sat(start.amount),
+            FeeRate::from_sat_per_vb(2).unwrap(),
+            best_block,
+        )
+        .await?;
+    let complete: response::FundChannelComplete = node1
+        .lampod()
+        .call(
+            "fundchannel_complete",
+            request::FundChannelComplete {
+                temporary_channel_id: start.temporary_channel_id.clone(),
+                psbt: None,
+                tx: Some(serialize_hex(&transaction)),
+            },
+        )
+        .await?;
+    assert_eq!(complete.temporary_channel_id, start.temporary_channel_id);
+    assert_eq!(complete.txid, transaction.compute_txid().to_string());
+    let output = &transaction.output[complete.funding_outnum as usize];
+    assert_eq!(output.script_pubkey.to_hex_string(), start.scriptpubkey);
+    assert_eq!(output.value.to_sat(), start.amount);
+
+    let channels: response::Channels = node1.lampod().call("channels", json::json!({})).await?;
+    assert!(
+        channels
+            .channels
+            .iter()
+            .any(|channel| channel.peer_id == node2.info.node_id),
+        "the funded channel should be listed: {channels:?}"
+    );
+    Ok(())
+}
+
+#[tokio_test_shutdown_timeout::test(5)]
+pub async fn multifundchannel_uses_one_transaction() -> error::Result<()> {
+    init();
+    let node1 = LampoTesting::tmp().await?;
+    let node2 = LampoTesting::new(node1.btc.clone()).await?;
+    let node3 = LampoTesting::new(node1.btc.clone()).await?;
+
+    let open = |node: &LampoTesting, amount: u64| request::OpenChannel {
+        node_id: node.info.node_id.clone(),
+        addr: Some("127.0.0.1".to_owned()),
+        port: Some(node.port),
+        amount,
+        push_msat: None,
+        public: true,
+    };
+    let batch: response::MultiFundChannel = node1
+        .lampod()
+        .call(
+            "multifundchannel",
+            request::MultiFundChannel {
+                channels: vec![open(&node2, 500_000), open(&node3, 700_000)],
+            },
+        )
+        .await?;
+    assert!(batch.txid.is_some(), "{batch:?}");
+    let tx = batch.tx.expect("a funded batch carries its transaction");
+    for channel in batch.channels.iter() {
+        assert!(channel.error.is_none(), "{channel:?}");
+        let outnum = channel.funding_outnum.expect("every channel has an output") as usize;
+        assert_eq!(tx.output[outnum].value.to_sat(), channel.amount);
+    }
+
+    // Two channels to the same peer cannot share a batch.
+    let duplicated: error::Result<response::MultiFundChannel> = node1
+        .lampod()
+        .call(
+            "multifundchannel",
+            request::MultiFundChannel {
+                channels: vec![open(&node2, 500_000), open(&node2, 600_000)],
+            },
+        )
+        .await;
+    assert!(duplicated.is_err(), "{duplicated:?}");
+    Ok(())
+}
+
+#[tokio_test_shutdown_timeout::test(5)]
+pub async fn splice_in_channel() -> error::Result<()> {
+    init();
+    let node1 = LampoTesting::tmp().await?;
+    let node2 = Arc::new(LampoTesting::new(node1.btc.clone()).await?);
+    node1.fund_channel_with(node2.clone(), 1_000_000).await?;
+    // Confirm the change of the funding transaction so it can be spliced in.
+    node1.fund_wallet(6).await?;
+
+    let channels: response::Channels = node1.lampod().call("channels", json::json!({})).await?;
+    let channel_id = channels.channels[0].channel_id.clone();
+
+    let spendable = |funds: &response::Utxos| {
+        funds
+            .transactions
+            .iter()
+            .filter(|utxo| !utxo.reserved)
+            .map(|utxo| (utxo.txid.clone(), utxo.vout))
+            .collect::<std::collections::HashSet<_>>()
+    };
+    let funds: response::Utxos = node1.lampod().call("funds", json::json!({})).await?;
+    let before = spendable(&funds);
+
+    let splice_in = request::SpliceIn {
+        channel_id: channel_id.clone(),
+        amount_sat: 100_000,
+        feerate_sat_per_kw: None,
+    };
+    let splice: response::Splice = node1.lampod().call("splicein", splice_in.clone()).await?;
+    assert_eq!(splice.channel_id, channel_id);
+
+    let channels: response::Channels = node1.lampod().call("channels", json::json!({})).await?;
+    let channel = channels
+        .channels
+        .iter()
+        .find(|channel| channel.channel_id == channel_id)
+        .expect("a spliced channel stays open");
+    assert_eq!(channel.pending_splice_txid, Some(splice.txid.clone()));
+
+    // The coins of the splice are locked until it locks in, or already spent
+    // in the mempool, either way they are not spendable anymore.
+    let funds: response::Utxos = node1.lampod().call("funds", json::json!({})).await?;
+    let during = spendable(&funds);
+    assert!(
+        !before.is_subset(&during),
+        "the splice coins should not be spendable: {funds:?}"
+    );
+
+    // A second splice fails and must release the coins it locked.
+    let again: error::Result<response::Splice> = node1.lampod().call("splicein", splice_in).await;
+    assert!(again.is_err(), "{:?}", again);
+    let funds: response::Utxos = node1.lampod().call("funds", json::json!({})).await?;
+    assert!(
+        during.is_subset(&spendable(&funds)),
+        "a failed splice should not keep coins locked: {funds:?}"
+    );
+
+    node1.fund_wallet(6).await?;
+    async_wait!(async {
+        let channels: response::Channels = node1
+            .lampod()
+            .call("channels", json::json!({}))
+            .await
+            .unwrap();
+        let locked_in = channels.channels.iter().any(|channel| {
+            channel.channel_id == channel_id && channel.pending_splice_txid.is_none()
+        });
+        if !locked_in {
+            node1.fund_wallet(1).await.unwrap();
+            return Err(());
+        }
+        Ok(())
+    });
+    let funds: response::Utxos = node1.lampod().call("funds", json::json!({})).await?;
+    assert!(
+        !funds.transactions.iter().any(|utxo| utxo.reserved),
+        "the splice coins should be released once it locks in: {funds:?}"
+    );
+    Ok(())
+}
+
+#[tokio_test_shutdown_timeout::test(5)]
+pub async fn splice_out_channel() -> error::Result<()> {
+    init();
+    let node1 = LampoTesting::tmp().await?;
+    let node2 = Arc::new(LampoTesting::new(node1.btc.clone()).await?);
+    node1.fund_channel_with(node2.clone(), 1_000_000).await?;
+
+    let channels: response::Channels = node1.lampod().call("channels", json::json!({})).await?;
+    let channel_id = channels.channels[0].channel_id.clone();
+    let funds: response::Utxos = node1.lampod().call("funds", json::json!({})).await?;
+    let reserved = funds
+        .transactions
+        .iter()
+        .filter(|utxo| utxo.reserved)
+        .count();
+
+    let address: response::NewAddress = node2.lampod().call("new_addr", json::json!({})).await?;
+    let splice: response::Splice = node1
+        .lampod()
+        .call(
+            "spliceout",
+            request::SpliceOut {
+                channel_id: channel_id.clone(),
+                amount_sat: 100_000,
+                address: address.address,
+                feerate_sat_per_kw: None,
+            },
+        )
+        .await?;
+    assert_eq!(splice.channel_id, channel_id);
+
+    let channels: response::Channels = node1.lampod().call("channels", json::json!({})).await?;
+    let channel = channels
+        .channels
+        .iter()
+        .find(|channel| channel.channel_id == channel_id)
+        .expect("a spliced channel stays open");
+    assert_eq!(channel.pending_splice_txid, Some(splice.txid));
+
+    // A splice out pays from the channel, so it locks no wallet coin.
+    let funds: response::Utxos = node1.lampod().call("funds", json::json!({})).await?;
+    assert_eq!(
+        funds
+            .transactions
+            .iter()
+            .filter(|utxo| utxo.reserved)
+            .count(),
+        reserved,
+        "{funds:?}"
+    );
+    Ok(())
+}
+
+#[tokio_test_shutdown_timeout::test(5)]
+pub async fn force_close_channel() -> error::Result<()> {
+    init();
+    let node1 = LampoTesting::tmp().await?;
+    let node2 = Arc::new(LampoTesting::new(node1.btc.clone()).await?);
+    node1.fund_channel_with(node2.clone(), 1_000_000).await?;
+
+    let refused: error::Result<response::CloseChannel> = node1
+        .lampod()
+        .call(
+            "close",
+            request::CloseChannel {
+                node_id: node2.info.node_id.clone(),
+                force: true,
+                feerate_sat_per_kw: Some(253),
+                ..Default::default()
+            },
+        )
+        .await;
+    assert!(refused.is_err(), "{:?}", refused);
+
+    let close: response::CloseChannel = node1
+        .lampod()
+        .call(
+            "close",
+            request::CloseChannel {
+                node_id: node2.info.node_id.clone(),
+                force: true,
+                ..Default::default()
             },
         )
         .await?;
+    assert!(close.forced);
+    assert_eq!(close.peer_id, node2.info.node_id);
 
-    log::info!(target: &node2.info.node_id, "offer generated `{:?}`", offer);
+    let channels: response::Channels = node1.lampod().call("channels", json::json!({})).await?;
+    assert!(
+        channels.channels.is_empty(),
+        "the force closed channel should be gone: {channels:?}"
+    );
 
-    let decode_result: response::Decode = node2
+    let closed: response::ClosedChannels = node1
         .lampod()
         .call(
-            "decode",
-            request::DecodeInvoice {
-                invoice_str: offer.bolt12.clone(),
+            "listclosedchannels",
+            request::ListClosedChannels {
+                peer_id: Some(node2.info.node_id.clone()),
             },
         )
         .await?;
+    assert_eq!(closed.channels.len(), 1, "{closed:?}");
+    let channel = &closed.channels[0];
+    assert_eq!(channel.channel_id, close.channel_id);
+    assert_eq!(channel.capacity_sat, Some(1_000_000));
+    assert!(channel.short_channel_id.is_some(), "{channel:?}");
+    assert!(channel.funding_txo.is_some(), "{channel:?}");
+    assert!(channel.closure_reason.is_some(), "{channel:?}");
+    Ok(())
+}
 
-    let decode: response::Bolt12InvoiceInfo = match decode_result {
-        response::Decode::Bolt12(x) => x,
-        _ => panic!("Should be a bolt12 invoice"),
-    };
-
-    assert!(!decode.offer_id.is_empty(), "Offer ID should be present");
-    assert_eq!(decode.network, "regtest", "Network should be regtest");
-    assert_eq!(
-        decode.description,
-        Some("test offer for decode".to_owned()),
-        "Description should match"
-    );
+#[tokio_test_shutdown_timeout::test(10)]
+pub async fn balances_count_force_closed_funds() -> error::Result<()> {
+    init();
+    let node1 = LampoTesting::tmp().await?;
+    let node2 = Arc::new(LampoTesting::new(node1.btc.clone()).await?);
+    node1.fund_channel_with(node2.clone(), 1_000_000).await?;
 
-    log::info!(target: &node1.info.node_id, "Successfully decoded offer with ID: {}", decode.offer_id);
+    let balances: response::Balances = node1.lampod().call("balances", json::json!({})).await?;
+    let on_channel: u64 = balances
+        .channels
+        .iter()
+        .filter(|balance| balance.kind == response::ClaimableKind::OnChannel)
+        .map(|balance| balance.amount_sat)
+        .sum();
+    assert!(on_channel > 900_000, "{balances:?}");
 
-    let pay: response::PayResult = node1
+    let close: response::CloseChannel = node1
         .lampod()
         .call(
-            "pay",
-            request::Pay {
-                invoice_str: offer.bolt12,
-                amount: None,
-                bolt12: None,
-                timeout: Default::default(),
+            "close",
+            request::CloseChannel {
+                node_id: node2.info.node_id.clone(),
+                force: true,
+                ..Default::default()
             },
         )
         .await?;
 
-    assert_eq!(
-        pay.state,
-        response::PaymentState::Success,
-        "Payment should succeed"
-    );
-    assert!(pay.payment_hash.is_some(), "Payment hash should be present");
-    assert!(!pay.path.is_empty(), "Payment path should not be empty");
-    assert_eq!(
-        pay.path.last().unwrap().node_id,
-        node2.info.node_id,
-        "Last hop should be to the destination node"
-    );
-
-    log::info!(target: &node1.info.node_id, "Payment completed successfully: {:?}", pay);
+    // The channel is gone, but its funds are still ours until they are
+    // swept back to the wallet.
+    let balances: response::Balances = node1.lampod().call("balances", json::json!({})).await?;
+    let claimable: u64 = balances
+        .channels
+        .iter()
+        .filter(|balance| balance.channel_id == close.channel_id)
+        .map(|balance| balance.amount_sat)
+        .sum();
+    assert!(claimable > 900_000, "{balances:?}");
+    assert!(balances.total_sat >= balances.wallet.total_sat() + claimable);
     Ok(())
 }
 
@@ -582,6 +1968,7 @@ pub async fn sweep_funds_after_channel_close() -> error::Result<()> {
             request::CloseChannel {
                 node_id: node2.info.node_id.clone(),
                 channel_id: None,
+                ..Default::default()
             },
         )
         .await?;
@@ -612,3 +1999,346 @@ pub async fn sweep_funds_after_channel_close() -> error::Result<()> {
     );
     Ok(())
 }
+
+#[tokio_test_shutdown_timeout::test(10)]
+pub async fn sweep_closed_channel_outputs() -> error::Result<()> {
+    init();
+    let node1 = LampoTesting::tmp().await?;
+    let btc = node1.btc.clone();
+    let node2 = Arc::new(LampoTesting::new(btc.clone()).await?);
+    node1.fund_channel_with(node2.clone(), 1_000_000).await?;
+
+    let sweeps: response::Sweeps = node1
+        .lampod()
+        .call("listsweeps", request::ListSweeps::default())
+        .await?;
+    assert!(sweeps.sweeps.is_empty(), "{sweeps:?}");
+    let address: response::NewAddress = node1.lampod().call("new_addr", json::json!({})).await?;
+    let nothing: error::Result<response::SweepTransaction> = node1
+        .lampod()
+        .call(
+            "sweep",
+            request::Sweep {
+                address: address.address,
+                outpoints: Vec::new(),
+                feerate_sat_per_kw: None,
+            },
+        )
+        .await;
+    assert!(nothing.is_err(), "{nothing:?}");
+
+    let close: response::CloseChannel = node1
+        .lampod()
+        .call(
+            "close",
+            request::CloseChannel {
+                node_id: node2.info.node_id.clone(),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+    // Wait until the sweeper tracks the outputs of the close and spent
+    // them. Once the spend is deep enough they are dropped again.
+    let tracked = std::cell::Cell::new(false);
+    async_wait!(
+        async {
+            node1.fund_wallet(1).await.unwrap();
+            tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+            let sweeps: response::Sweeps = node1
+                .lampod()
+                .call(
+                    "listsweeps",
+                    request::ListSweeps {
+                        channel_id: Some(close.channel_id.clone()),
+                    },
+                )
+                .await
+                .unwrap();
+            if sweeps.sweeps.is_empty() {
+                return if tracked.get() { Ok(()) } else { Err(()) };
+            }
+            tracked.set(true);
+            if sweeps
+                .sweeps
+                .iter()
+                .any(|sweep| sweep.status == response::SweepStatus::Pending)
+            {
+                return Err(());
+            }
+            Ok(())
+        },
+        10
+    );
+
+    // Whatever the sweeper still tracks was spent by its sweep.
+    let sweeps: response::Sweeps = node1
+        .lampod()
+        .call(
+            "listsweeps",
+            request::ListSweeps {
+                channel_id: Some(close.channel_id.clone()),
+            },
+        )
+        .await?;
+    for sweep in &sweeps.sweeps {
+        assert_ne!(sweep.status, response::SweepStatus::Pending, "{sweep:?}");
+        assert!(sweep.spending_txid.is_some(), "{sweep:?}");
+    }
+    // A spend is in flight already, a second one is refused.
+    if let Some(spent) = sweeps.sweeps.first() {
+        let address: response::NewAddress =
+            node1.lampod().call("new_addr", json::json!({})).await?;
+        let again: error::Result<response::SweepTransaction> = node1
+            .lampod()
+            .call(
+                "sweep",
+                request::Sweep {
+                    address: address.address,
+                    outpoints: vec![spent.outpoint.clone()],
+                    feerate_sat_per_kw: None,
+                },
+            )
+            .await;
+        let err = again.expect_err("the output is already spent");
+        assert!(err.to_string().contains(&spent.outpoint), "{err}");
+    }
+    Ok(())
+}
+
+#[tokio_test_shutdown_timeout::test(5)]
+pub async fn probe_direct_peer_sends_no_probe() -> error::Result<()> {
+    init();
+    let node1 = LampoTesting::tmp().await?;
+    let btc = node1.btc.clone();
+    let node2 = Arc::new(LampoTesting::new(btc.clone()).await?);
+    node1.fund_channel_with(node2.clone(), 1_000_000).await?;
+
+    // Single hop paths are not probed: the channel balance already tells.
+    let result: response::ProbeResult = node1
+        .lampod()
+        .call(
+            "probe",
+            request::Probe {
+                destination: node2.info.node_id.clone(),
+                amount_msat: 100_000,
+                timeout: request::PayTimeout::Fast,
+            },
+        )
+        .await?;
+    assert!(result.probes.is_empty());
+
+    let invoice: response::Invoice = node2
+        .lampod()
+        .call(
+            "invoice",
+            request::GenerateInvoice {
+                description: "probe".to_owned(),
+                amount_msat: None,
+                expiring_in: None,
+                ..Default::default()
+            },
+        )
+        .await?;
+    let probe: Result<response::ProbeResult, _> = node1
+        .lampod()
+        .call(
+            "probeinvoice",
+            request::ProbeInvoice {
+                invoice_str: invoice.bolt11,
+                amount_msat: None,
+                timeout: request::PayTimeout::Fast,
+            },
+        )
+        .await;
+    assert!(probe.is_err(), "an amountless invoice needs `amount_msat`");
+    Ok(())
+}
+
+#[tokio_test_shutdown_timeout::test(5)]
+pub async fn getroute_to_channel_peer() -> error::Result<()> {
+    init();
+    let node1 = LampoTesting::tmp().await?;
+    let btc = node1.btc.clone();
+    let node2 = Arc::new(LampoTesting::new(btc.clone()).await?);
+    node1.fund_channel_with(node2.clone(), 1_000_000).await?;
+
+    let route: response::Route = node1
+        .lampod()
+        .call(
+            "getroute",
+            request::GetRoute {
+                destination: node2.info.node_id.clone(),
+                amount_msat: 100_000,
+                max_fee_msat: None,
+                max_cltv_expiry_delta: None,
+                exclude_nodes: vec![],
+                exclude_channels: vec![],
+            },
+        )
+        .await?;
+    assert_eq!(route.amount_msat, 100_000);
+    assert_eq!(route.paths.len(), 1);
+    let hops = &route.paths[0].hops;
+    assert_eq!(hops.len(), 1);
+    assert_eq!(hops[0].node_id, node2.info.node_id);
+
+    // Without its only channel there is no route left.
+    let excluded: Result<response::Route, _> = node1
+        .lampod()
+        .call(
+            "getroute",
+            request::GetRoute {
+                destination: node2.info.node_id.clone(),
+                amount_msat: 100_000,
+                max_fee_msat: None,
+                max_cltv_expiry_delta: None,
+                exclude_nodes: vec![],
+                exclude_channels: vec![hops[0].short_channel_id],
+            },
+        )
+        .await;
+    assert!(excluded.is_err());
+    Ok(())
+}
+
+#[tokio_test_shutdown_timeout::test(5)]
+pub async fn pay_with_fee_and_routing_limits() -> error::Result<()> {
+    init();
+    let node1 = LampoTesting::tmp().await?;
+    let btc = node1.btc.clone();
+    let node2 = Arc::new(LampoTesting::new(btc.clone()).await?);
+    node1.fund_channel_with(node2.clone(), 1_000_000).await?;
+
+    let route: response::Route = node1
+        .lampod()
+        .call(
+            "getroute",
+            request::GetRoute {
+                destination: node2.info.node_id.clone(),
+                amount_msat: 100_000,
+                max_fee_msat: None,
+                max_cltv_expiry_delta: None,
+                exclude_nodes: vec![],
+                exclude_channels: vec![],
+            },
+        )
+        .await?;
+    let scid = route.paths[0].hops[0].short_channel_id;
+
+    let invoice: response::Invoice = node2
+        .lampod()
+        .call(
+            "invoice",
+            request::GenerateInvoice {
+                description: "limits".to_owned(),
+                amount_msat: Some(100_000),
+                expiring_in: None,
+                ..Default::default()
+            },
+        )
+        .await?;
+
+    // Excluding the only channel leaves no route.
+    let excluded: Result<response::PayResult, _> = node1
+        .lampod()
+        .call(
+            "pay",
+            request::Pay {
+                invoice_str: invoice.bolt11.clone(),
+                amount: None,
+                bolt12: None,
+                timeout: request::PayTimeout::Fast,
+                r#async: false,
+                limits: request::PaymentLimits {
+                    exclude: vec![scid.to_string()],
+                    ..Default::default()
+                },
+            },
+        )
+        .await;
+    assert!(excluded.is_err());
+
+    // A direct channel costs no routing fee, so a zero cap still pays.
+    let pay: response::PayResult = node1
+        .lampod()
+        .call(
+            "pay",
+            request::Pay {
+                invoice_str: invoice.bolt11,
+                amount: None,
+                bolt12: None,
+                timeout: Default::default(),
+                r#async: false,
+                limits: request::PaymentLimits {
+                    maxfee_msat: Some(0),
+                    max_parts: Some(1),
+                    retry_for: Some(30),
+                    ..Default::default()
+                },
+            },
+        )
+        .await?;
+    assert_eq!(pay.state, response::PaymentState::Success);
+    Ok(())
+}
+
+#[tokio_test_shutdown_timeout::test(5)]
+pub async fn keysend_with_custom_tlvs() -> error::Result<()> {
+    init();
+    let node1 = LampoTesting::tmp().await?;
+    let btc = node1.btc.clone();
+    let node2 = Arc::new(LampoTesting::new(btc.clone()).await?);
+    node1.fund_channel_with(node2.clone(), 1_000_000).await?;
+
+    let custom_tlvs = vec![response::CustomTlv {
+        r#type: 34349334,
+        value: hex::encode("hello lampo"),
+    }];
+    let mut events = node2.lampod().handler().events();
+    let pay: response::PayResult = node1
+        .lampod()
+        .call(
+            "keysend",
+            request::KeySend {
+                destination: node2.info.node_id.clone(),
+                amount_msat: 100_000,
+                timeout: Default::default(),
+                r#async: false,
+                limits: Default::default(),
+                mpp: true,
+                custom_tlvs: custom_tlvs.clone(),
+            },
+        )
+        .await?;
+    assert_eq!(pay.state, response::PaymentState::Success);
+    assert_eq!(pay.custom_tlvs, custom_tlvs);
+
+    let payment_hash = pay.payment_hash.clone().unwrap();
+    while let Some(event) = events.recv().await {
+        if let Event::Lightning(LightningEvent::PaymentReceived {
+            payment_hash: hash,
+            custom_tlvs: received,
+            ..
+        }) = event
+        {
+            if hash == payment_hash {
+                assert_eq!(received, custom_tlvs);
+                break;
+            }
+        }
+    }
+
+    let invoices: response::Invoices = node2
+        .lampod()
+        .call(
+            "listinvoices",
+            request::ListInvoices {
+                payment_hash: Some(payment_hash),
+                ..Default::default()
+            },
+        )
+        .await?;
+    assert_eq!(invoices.invoices[0].custom_tlvs, custom_tlvs);
+    Ok(())
+}